@@ -0,0 +1,328 @@
+//! HTTP server that generates chunks with [`MapConfig`] and serves them as
+//! postcard-encoded [`ChunkPayload`]s, so clients can skip generation and
+//! extraction and share one world between sessions.
+//!
+//! Endpoints:
+//!
+//! - `GET /chunks/{cx},{cy},{cz}` — the payload for one chunk
+//! - `GET /config` — the postcard-encoded [`WorldInfo`] the chunks come
+//!   from, so clients can refuse a server generating a different world
+//! - `GET /health` — liveness probe, answers `ok`
+
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use engine::biome::BiomeMap;
+use engine::chunk_payload::{ChunkPayload, WorldInfo};
+use engine::map_features::MapConfig;
+use engine::material::MaterialRegistry;
+use engine::voxel::TEST_GRID_SEED;
+use glam::IVec3;
+use lru::LruCache;
+
+/// Default listen port.
+pub const DEFAULT_PORT: u16 = 3001;
+/// Default number of cached chunk payloads.
+pub const DEFAULT_CACHE_SIZE: usize = 4096;
+
+/// Command-line configuration.
+#[derive(Clone, Debug, clap::Parser)]
+#[command(about = "Serves generated voxel chunks over HTTP")]
+pub struct Config {
+    /// Listen port.
+    #[arg(long, default_value_t = DEFAULT_PORT)]
+    pub port: u16,
+    /// World generation seed. Defaults to the web client's.
+    #[arg(long, default_value_t = TEST_GRID_SEED)]
+    pub seed: u32,
+    /// Generate a single Perlin height field instead of the biome terrain
+    /// the web client uses.
+    #[arg(long)]
+    pub no_biomes: bool,
+    /// Maximum number of chunk payloads kept in memory.
+    #[arg(long, default_value_t = NonZeroUsize::new(DEFAULT_CACHE_SIZE).unwrap())]
+    pub cache_size: NonZeroUsize,
+}
+
+impl Config {
+    /// The world to serve. With default flags it is the web client's.
+    #[must_use]
+    pub fn map_config(&self) -> MapConfig {
+        let config = MapConfig::with_seed(self.seed);
+        if self.no_biomes {
+            config
+        } else {
+            config.with_biomes(BiomeMap::standard(self.seed))
+        }
+    }
+}
+
+/// Shared server state: the world generator and the encoded-payload cache.
+pub struct ChunkServer {
+    config: MapConfig,
+    materials: MaterialRegistry,
+    cache: Mutex<LruCache<IVec3, Bytes>>,
+}
+
+impl ChunkServer {
+    #[must_use]
+    pub fn new(seed: u32, cache_size: NonZeroUsize) -> Self {
+        Self::with_map_config(MapConfig::with_seed(seed), cache_size)
+    }
+
+    /// A server generating chunks with `config`.
+    #[must_use]
+    pub fn with_map_config(config: MapConfig, cache_size: NonZeroUsize) -> Self {
+        Self {
+            config,
+            materials: MaterialRegistry::default(),
+            cache: Mutex::new(LruCache::new(cache_size)),
+        }
+    }
+
+    /// The seed chunks are generated from.
+    #[must_use]
+    pub fn seed(&self) -> u32 {
+        self.config.seed
+    }
+
+    /// The world chunks are generated from.
+    #[must_use]
+    pub fn world(&self) -> WorldInfo {
+        WorldInfo::of(&self.config)
+    }
+
+    /// Number of payloads currently cached.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cache mutex is poisoned.
+    #[must_use]
+    pub fn cached_count(&self) -> usize {
+        self.cache.lock().unwrap().len()
+    }
+
+    /// The postcard-encoded payload for `coord`, generating and caching it
+    /// on a miss. Returns the bytes and whether they came from the cache.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cache mutex is poisoned.
+    pub fn payload_bytes(&self, coord: IVec3) -> (Bytes, bool) {
+        if let Some(bytes) = self.cache.lock().unwrap().get(&coord) {
+            return (bytes.clone(), true);
+        }
+        let payload = ChunkPayload::generate(&self.config, coord, &self.materials);
+        let bytes = Bytes::from(
+            postcard::to_allocvec(&payload).expect("in-memory serialization cannot fail"),
+        );
+        self.cache.lock().unwrap().put(coord, bytes.clone());
+        (bytes, false)
+    }
+}
+
+/// Build the router serving `server`.
+pub fn router(server: Arc<ChunkServer>) -> Router {
+    Router::new()
+        .route("/chunks/{coord}", get(get_chunk))
+        .route("/config", get(world_config))
+        .route("/health", get(health))
+        .layer(axum::middleware::map_response(allow_any_origin))
+        .with_state(server)
+}
+
+/// Parse `"cx,cy,cz"` into a chunk coordinate.
+#[must_use]
+pub fn parse_coord(s: &str) -> Option<IVec3> {
+    let mut parts = s.split(',').map(|p| p.trim().parse::<i32>());
+    let coord = IVec3::new(
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+        parts.next()?.ok()?,
+    );
+    parts.next().is_none().then_some(coord)
+}
+
+async fn get_chunk(State(server): State<Arc<ChunkServer>>, Path(coord): Path<String>) -> Response {
+    let Some(coord) = parse_coord(&coord) else {
+        return (StatusCode::BAD_REQUEST, "expected /chunks/{cx},{cy},{cz}").into_response();
+    };
+    let start = Instant::now();
+    let worker = Arc::clone(&server);
+    let Ok((bytes, hit)) = tokio::task::spawn_blocking(move || worker.payload_bytes(coord)).await
+    else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    tracing::info!(
+        "chunk {coord}: {} in {:.1} ms, {} bytes",
+        if hit { "hit" } else { "miss" },
+        start.elapsed().as_secs_f64() * 1000.0,
+        bytes.len()
+    );
+    ([(header::CONTENT_TYPE, "application/octet-stream")], bytes).into_response()
+}
+
+async fn world_config(State(server): State<Arc<ChunkServer>>) -> Response {
+    let bytes =
+        postcard::to_allocvec(&server.world()).expect("in-memory serialization cannot fail");
+    ([(header::CONTENT_TYPE, "application/octet-stream")], bytes).into_response()
+}
+
+async fn health() -> &'static str {
+    "ok"
+}
+
+async fn allow_any_origin(mut response: Response) -> Response {
+    response.headers_mut().insert(
+        header::ACCESS_CONTROL_ALLOW_ORIGIN,
+        HeaderValue::from_static("*"),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use engine::collision::CollisionMap;
+    use engine::terrain_grid::TerrainGrid;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// Serve `server` on an ephemeral localhost port.
+    async fn spawn(server: Arc<ChunkServer>) -> std::net::SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router(server)).await.unwrap() });
+        addr
+    }
+
+    /// Minimal HTTP/1.1 GET returning the status line, headers and body.
+    async fn get(addr: std::net::SocketAddr, path: &str) -> (String, String, Vec<u8>) {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request =
+            format!("GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n");
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw).await.unwrap();
+        let split = raw.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        let head = String::from_utf8(raw[..split].to_vec()).unwrap();
+        let (status, headers) = head.split_once("\r\n").unwrap_or((&head, ""));
+        (
+            status.to_string(),
+            headers.to_lowercase(),
+            raw[split + 4..].to_vec(),
+        )
+    }
+
+    fn server(cache_size: usize) -> Arc<ChunkServer> {
+        Arc::new(ChunkServer::new(42, NonZeroUsize::new(cache_size).unwrap()))
+    }
+
+    #[test]
+    fn parse_coord_accepts_three_integers() {
+        assert_eq!(parse_coord("1,-2,3"), Some(IVec3::new(1, -2, 3)));
+        assert_eq!(parse_coord("1,2"), None);
+        assert_eq!(parse_coord("1,2,3,4"), None);
+        assert_eq!(parse_coord("a,2,3"), None);
+    }
+
+    #[tokio::test]
+    async fn health_answers_ok_with_cors() {
+        let addr = spawn(server(4)).await;
+        let (status, headers, body) = get(addr, "/health").await;
+        assert!(status.contains("200"), "{status}");
+        assert!(headers.contains("access-control-allow-origin: *"));
+        assert_eq!(body, b"ok");
+    }
+
+    #[tokio::test]
+    async fn config_reports_the_world() {
+        let addr = spawn(server(4)).await;
+        let (status, _, body) = get(addr, "/config").await;
+        assert!(status.contains("200"), "{status}");
+        let world: WorldInfo = postcard::from_bytes(&body).unwrap();
+        assert_eq!(world, WorldInfo::of(&MapConfig::with_seed(42)));
+    }
+
+    #[test]
+    fn default_flags_serve_the_web_client_world() {
+        let client = MapConfig::default().with_biomes(BiomeMap::standard(TEST_GRID_SEED));
+        let config = Config::parse_from(["chunk-server"]);
+        assert_eq!(WorldInfo::of(&config.map_config()), WorldInfo::of(&client));
+        let flat = Config::parse_from(["chunk-server", "--seed", "7", "--no-biomes"]);
+        assert_eq!(
+            WorldInfo::of(&flat.map_config()),
+            WorldInfo::of(&MapConfig::with_seed(7))
+        );
+    }
+
+    #[tokio::test]
+    async fn chunk_matches_local_generation() {
+        let addr = spawn(server(4)).await;
+        let coord = IVec3::new(1, 0, -1);
+        let (status, _, body) = get(addr, "/chunks/1,0,-1").await;
+        assert!(status.contains("200"), "{status}");
+        let payload: ChunkPayload = postcard::from_bytes(&body).unwrap();
+        assert_eq!(payload.coord(), coord);
+
+        let materials = MaterialRegistry::default();
+        let local = MapConfig::with_seed(42).generate_chunk(coord);
+        let decoded = payload.decode().unwrap();
+        assert_eq!(decoded.chunk, local);
+        assert_eq!(decoded.occupancy, local.occupancy_mask());
+        assert_eq!(
+            decoded.collision.as_bytes(),
+            CollisionMap::from_chunk(&local, &materials).as_bytes()
+        );
+        assert_eq!(
+            decoded.terrain.to_bytes(),
+            TerrainGrid::from_chunk(&local, &materials).to_bytes()
+        );
+    }
+
+    #[test]
+    fn biome_server_matches_local_biome_generation() {
+        let biomes = || MapConfig::with_seed(42).with_biomes(BiomeMap::standard(42));
+        let server = ChunkServer::with_map_config(biomes(), NonZeroUsize::new(1).unwrap());
+        let coord = IVec3::new(30, 0, -12);
+        let (bytes, _) = server.payload_bytes(coord);
+        let payload: ChunkPayload = postcard::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            payload.decode().unwrap().chunk,
+            biomes().generate_chunk(coord)
+        );
+    }
+
+    #[tokio::test]
+    async fn malformed_coord_is_bad_request() {
+        let addr = spawn(server(4)).await;
+        let (status, _, _) = get(addr, "/chunks/1,zero,2").await;
+        assert!(status.contains("400"), "{status}");
+    }
+
+    #[test]
+    fn cache_returns_identical_bytes_and_evicts_least_recent() {
+        let server = server(2);
+        let a = IVec3::ZERO;
+        let (first, hit) = server.payload_bytes(a);
+        assert!(!hit);
+        let (again, hit) = server.payload_bytes(a);
+        assert!(hit);
+        assert_eq!(first, again);
+
+        server.payload_bytes(IVec3::X);
+        server.payload_bytes(IVec3::Y);
+        assert_eq!(server.cached_count(), 2);
+        let (regenerated, hit) = server.payload_bytes(a);
+        assert!(!hit, "oldest entry should have been evicted");
+        assert_eq!(regenerated, first, "generation is deterministic");
+    }
+}
@@ -0,0 +1,23 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use chunk_server::{ChunkServer, Config, router};
+use clap::Parser;
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    tracing_subscriber::fmt::init();
+    let config = Config::parse();
+    let server = Arc::new(ChunkServer::with_map_config(
+        config.map_config(),
+        config.cache_size,
+    ));
+    let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!(
+        "serving chunks on {addr} ({}, cache {})",
+        server.world(),
+        config.cache_size
+    );
+    axum::serve(listener, router(server)).await
+}
@@ -0,0 +1,366 @@
+use glam::IVec3;
+use noise::{NoiseFn, Perlin};
+
+use crate::map_rng::{FEATURE_BIOME_DECORATION, MapRng};
+use crate::voxel::{
+    CHUNK_SIZE, CHUNK_VOLUME, Chunk, MAT_AIR, MAT_CRYSTAL, MAT_DIRT, MAT_GLOWSTONE, MAT_GRASS,
+    MAT_ICE, MAT_STONE, pack_voxel, voxel_index,
+};
+
+/// World-space frequency of the temperature and moisture fields. Low enough
+/// that a biome spans a few hundred voxels.
+const CLIMATE_FREQUENCY: f64 = 1.0 / 384.0;
+
+/// Seed offsets so the climate fields and the height field are independent.
+const TEMPERATURE_SEED: u32 = 0x7E3A_0001;
+const MOISTURE_SEED: u32 = 0x3015_7002;
+
+/// How much farther in climate space than the nearest biome another biome
+/// may be and still contribute to the blended height.
+const BLEND_WIDTH: f64 = 0.2;
+
+/// One kind of terrain, placed where the climate is close to its
+/// `temperature` and `moisture`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Biome {
+    pub name: &'static str,
+    /// Climate the biome is centered on, roughly in `[-0.6, 0.6]` like the
+    /// noise fields.
+    pub temperature: f64,
+    pub moisture: f64,
+    /// Height curve: the surface is at
+    /// `base_height + amplitude * noise(x * frequency, z * frequency)`.
+    pub base_height: f64,
+    pub amplitude: f64,
+    pub frequency: f64,
+    /// Material of the top voxel of each column.
+    pub surface: u8,
+    /// Material of the `subsurface_depth` voxels under the surface; stone
+    /// lies below.
+    pub subsurface: u8,
+    pub subsurface_depth: i32,
+    /// Material of the single voxel placed on decorated columns.
+    pub decoration: u8,
+    /// Fraction of columns decorated, in `[0, 1]`.
+    pub decoration_density: f64,
+}
+
+impl Biome {
+    /// Material at world `y` in a column of this biome whose surface is at
+    /// `surface_y`.
+    #[must_use]
+    pub fn material(&self, y: i32, surface_y: i32, decorated: bool) -> u8 {
+        if y > surface_y {
+            if decorated && y == surface_y + 1 {
+                self.decoration
+            } else {
+                MAT_AIR
+            }
+        } else if y == surface_y {
+            self.surface
+        } else if y + self.subsurface_depth >= surface_y {
+            self.subsurface
+        } else {
+            MAT_STONE
+        }
+    }
+}
+
+/// The generated shape of one world column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Column {
+    /// Index into [`BiomeMap::biomes`] of the dominant biome.
+    pub biome: usize,
+    /// World y of the surface voxel.
+    pub surface_y: i32,
+    /// Whether a decoration voxel sits on the surface.
+    pub decorated: bool,
+}
+
+/// Terrain generator that picks biomes from temperature and moisture noise.
+///
+/// Each column is owned by the biome nearest its climate, which supplies
+/// materials and decorations. Heights blend across every biome within
+/// `BLEND_WIDTH` of the nearest, so borders are smooth slopes rather than
+/// cliffs.
+#[derive(Clone)]
+pub struct BiomeMap {
+    pub seed: u32,
+    biomes: Vec<Biome>,
+    temperature: Perlin,
+    moisture: Perlin,
+    height: Perlin,
+}
+
+impl BiomeMap {
+    /// A map over `biomes`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `biomes` is empty.
+    #[must_use]
+    pub fn new(seed: u32, biomes: Vec<Biome>) -> Self {
+        assert!(!biomes.is_empty(), "a biome map needs at least one biome");
+        Self {
+            seed,
+            biomes,
+            temperature: Perlin::new(seed ^ TEMPERATURE_SEED),
+            moisture: Perlin::new(seed ^ MOISTURE_SEED),
+            height: Perlin::new(seed),
+        }
+    }
+
+    /// Meadow, tundra, mountains, badlands and marsh.
+    #[must_use]
+    pub fn standard(seed: u32) -> Self {
+        Self::new(seed, standard_biomes())
+    }
+
+    #[must_use]
+    pub fn biomes(&self) -> &[Biome] {
+        &self.biomes
+    }
+
+    /// `(temperature, moisture)` at world column `(x, z)`.
+    #[must_use]
+    pub fn climate(&self, x: i32, z: i32) -> (f64, f64) {
+        let p = [
+            f64::from(x) * CLIMATE_FREQUENCY,
+            f64::from(z) * CLIMATE_FREQUENCY,
+        ];
+        (self.temperature.get(p), self.moisture.get(p))
+    }
+
+    /// The dominant biome at world column `(x, z)`.
+    #[must_use]
+    pub fn biome_at(&self, x: i32, z: i32) -> &Biome {
+        &self.biomes[self.column(x, z).biome]
+    }
+
+    /// Surface height (world y) at world column `(x, z)`.
+    #[must_use]
+    pub fn surface_height(&self, x: i32, z: i32) -> i32 {
+        self.column(x, z).surface_y
+    }
+
+    /// Dominant biome, blended surface height and decoration of world
+    /// column `(x, z)`.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn column(&self, x: i32, z: i32) -> Column {
+        let (t, m) = self.climate(x, z);
+        let distances: Vec<f64> = self
+            .biomes
+            .iter()
+            .map(|b| (b.temperature - t).hypot(b.moisture - m))
+            .collect();
+        let mut biome = 0;
+        for (i, d) in distances.iter().enumerate() {
+            if *d < distances[biome] {
+                biome = i;
+            }
+        }
+        let nearest = distances[biome];
+
+        let (mut height, mut total) = (0.0, 0.0);
+        for (b, d) in self.biomes.iter().zip(&distances) {
+            let weight = (1.0 - (d - nearest) / BLEND_WIDTH).max(0.0);
+            if weight > 0.0 {
+                // Smoothstep keeps the slope continuous where a biome fades in.
+                let weight = weight * weight * (3.0 - 2.0 * weight);
+                height += weight * self.biome_height(b, x, z);
+                total += weight;
+            }
+        }
+
+        let dominant = &self.biomes[biome];
+        Column {
+            biome,
+            surface_y: (height / total).floor() as i32,
+            decorated: MapRng::for_position(
+                self.seed,
+                FEATURE_BIOME_DECORATION,
+                IVec3::new(x, 0, z),
+            )
+            .next_f64()
+                < dominant.decoration_density,
+        }
+    }
+
+    fn biome_height(&self, biome: &Biome, x: i32, z: i32) -> f64 {
+        let noise = self.height.get([
+            f64::from(x) * biome.frequency,
+            f64::from(z) * biome.frequency,
+        ]);
+        biome.base_height + biome.amplitude * noise
+    }
+
+    /// Generate the chunk at `chunk_coord`. Continuous across chunk
+    /// boundaries, like [`Chunk::new_terrain_at`].
+    #[must_use]
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn generate_chunk(&self, chunk_coord: IVec3) -> Chunk {
+        let mut voxels = vec![0u32; CHUNK_VOLUME];
+        let origin = chunk_coord * CHUNK_SIZE as i32;
+        for z in 0..CHUNK_SIZE {
+            for x in 0..CHUNK_SIZE {
+                let column = self.column(origin.x + x as i32, origin.z + z as i32);
+                let biome = &self.biomes[column.biome];
+                for y in 0..CHUNK_SIZE {
+                    let world_y = origin.y + y as i32;
+                    let mat = biome.material(world_y, column.surface_y, column.decorated);
+                    if mat != MAT_AIR {
+                        voxels[voxel_index(x, y, z)] = pack_voxel(mat, 0, 0, 0);
+                    }
+                }
+            }
+        }
+        Chunk::from_voxels(&voxels)
+    }
+}
+
+/// The biome set of [`BiomeMap::standard`].
+#[must_use]
+pub fn standard_biomes() -> Vec<Biome> {
+    vec![
+        Biome {
+            name: "meadow",
+            temperature: 0.0,
+            moisture: 0.0,
+            base_height: 24.0,
+            amplitude: 10.0,
+            frequency: 1.0 / 12.0,
+            surface: MAT_GRASS,
+            subsurface: MAT_DIRT,
+            subsurface_depth: 3,
+            decoration: MAT_GRASS,
+            decoration_density: 0.03,
+        },
+        Biome {
+            name: "tundra",
+            temperature: -0.4,
+            moisture: 0.25,
+            base_height: 20.0,
+            amplitude: 5.0,
+            frequency: 1.0 / 24.0,
+            surface: MAT_ICE,
+            subsurface: MAT_DIRT,
+            subsurface_depth: 2,
+            decoration: MAT_ICE,
+            decoration_density: 0.01,
+        },
+        Biome {
+            name: "mountains",
+            temperature: -0.3,
+            moisture: -0.35,
+            base_height: 44.0,
+            amplitude: 28.0,
+            frequency: 1.0 / 28.0,
+            surface: MAT_STONE,
+            subsurface: MAT_STONE,
+            subsurface_depth: 0,
+            decoration: MAT_CRYSTAL,
+            decoration_density: 0.004,
+        },
+        Biome {
+            name: "badlands",
+            temperature: 0.4,
+            moisture: -0.3,
+            base_height: 30.0,
+            amplitude: 8.0,
+            frequency: 1.0 / 10.0,
+            surface: MAT_DIRT,
+            subsurface: MAT_DIRT,
+            subsurface_depth: 6,
+            decoration: MAT_STONE,
+            decoration_density: 0.02,
+        },
+        Biome {
+            name: "marsh",
+            temperature: 0.3,
+            moisture: 0.4,
+            base_height: 18.0,
+            amplitude: 2.5,
+            frequency: 1.0 / 16.0,
+            surface: MAT_GRASS,
+            subsurface: MAT_DIRT,
+            subsurface_depth: 5,
+            decoration: MAT_GLOWSTONE,
+            decoration_density: 0.002,
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::voxel::material_id;
+
+    #[test]
+    fn standard_map_mixes_biomes() {
+        let map = BiomeMap::standard(42);
+        let names: HashSet<&str> = (-20..20)
+            .flat_map(|i| (-20..20).map(move |j| (i * 128, j * 128)))
+            .map(|(x, z)| map.biome_at(x, z).name)
+            .collect();
+        assert!(names.len() >= 4, "only {names:?}");
+    }
+
+    #[test]
+    fn heights_blend_across_biome_borders() {
+        let map = BiomeMap::standard(42);
+        let mut borders = 0;
+        for z in (-2048..2048).step_by(64) {
+            let mut prev = map.column(-2048, z);
+            for x in -2047..2048 {
+                let column = map.column(x, z);
+                assert!(
+                    (column.surface_y - prev.surface_y).abs() <= 4,
+                    "cliff at ({x}, {z})"
+                );
+                borders += usize::from(column.biome != prev.biome);
+                prev = column;
+            }
+        }
+        assert!(borders > 0, "the sweep should cross biome borders");
+    }
+
+    #[test]
+    fn chunk_columns_follow_their_biome() {
+        let map = BiomeMap::standard(7);
+        for (x, z) in [(101, -55), (900, 1700), (-2500, 300)] {
+            let column = map.column(x, z);
+            let biome = &map.biomes()[column.biome];
+            let chunk_y = column.surface_y.div_euclid(32);
+            let chunk = map.generate_chunk(IVec3::new(x.div_euclid(32), chunk_y, z.div_euclid(32)));
+            let local = |v: i32| usize::try_from(v.rem_euclid(32)).unwrap();
+            let voxel = |y: i32| material_id(chunk.voxel_at(local(x), local(y), local(z)));
+            assert_eq!(voxel(column.surface_y), biome.surface);
+            let above = column.surface_y + 1;
+            if above.div_euclid(32) == chunk_y {
+                let expected = if column.decorated {
+                    biome.decoration
+                } else {
+                    MAT_AIR
+                };
+                assert_eq!(voxel(above), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn decoration_density_controls_decorated_columns() {
+        let biome = |density| Biome {
+            decoration_density: density,
+            ..standard_biomes()[0].clone()
+        };
+        let everywhere = BiomeMap::new(1, vec![biome(1.0)]);
+        let nowhere = BiomeMap::new(1, vec![biome(0.0)]);
+        for x in 0..64 {
+            assert!(everywhere.column(x, 5).decorated);
+            assert!(!nowhere.column(x, 5).decorated);
+        }
+    }
+}
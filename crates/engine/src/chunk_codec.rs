@@ -0,0 +1,408 @@
+//! Versioned binary chunk format shared by persistence, the chunk server and
+//! test fixtures.
+//!
+//! Layout (all integers little-endian):
+//!
+//! | bytes | field |
+//! |-------|-------|
+//! | 4 | magic `b"VXCK"` |
+//! | 1 | format version ([`CHUNK_FORMAT_VERSION`]) |
+//! | 1 | encoding: `0` uniform, `1` palette + run-length |
+//! | 2 | reserved, zero |
+//! | n | payload |
+//! | 4 | CRC-32 (IEEE) of everything before it |
+//!
+//! A uniform payload is the single packed voxel (`u32`). A palette payload
+//! is a `u16` palette length, the palette entries (`u32` each), then runs of
+//! `(length, palette index)` pairs as LEB128 varints covering all
+//! [`CHUNK_VOLUME`] voxels in [`voxel_index`](crate::voxel::voxel_index)
+//! order.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::voxel::{CHUNK_VOLUME, Chunk};
+
+/// Leading bytes of every encoded chunk.
+pub const CHUNK_MAGIC: [u8; 4] = *b"VXCK";
+/// Format version written by [`Chunk::encode`]. Bump on layout changes.
+pub const CHUNK_FORMAT_VERSION: u8 = 1;
+
+const ENCODING_UNIFORM: u8 = 0;
+const ENCODING_PALETTE_RLE: u8 = 1;
+const HEADER_LEN: usize = 8;
+const CRC_LEN: usize = 4;
+
+/// Why [`Chunk::decode`] rejected its input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkCodecError {
+    /// The input ended before a complete chunk was read.
+    Truncated,
+    /// The input does not start with [`CHUNK_MAGIC`].
+    BadMagic,
+    /// The chunk was written by a format version this build cannot read.
+    UnsupportedVersion(u8),
+    /// The header names an encoding this version does not define.
+    UnknownEncoding(u8),
+    /// The stored CRC does not match the bytes it covers.
+    ChecksumMismatch { stored: u32, computed: u32 },
+    /// A run refers to a palette entry that does not exist.
+    PaletteIndexOutOfRange { index: u32, palette_len: usize },
+    /// The runs do not add up to exactly one chunk of voxels.
+    VoxelCountMismatch { decoded: usize },
+    /// A varint in the run data is longer than a `u32` allows.
+    InvalidVarint,
+    /// Bytes remain after the payload was fully decoded.
+    TrailingBytes(usize),
+}
+
+impl fmt::Display for ChunkCodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "encoded chunk is truncated"),
+            Self::BadMagic => write!(f, "input is not an encoded chunk"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported chunk format version {v}"),
+            Self::UnknownEncoding(e) => write!(f, "unknown chunk encoding {e}"),
+            Self::ChecksumMismatch { stored, computed } => write!(
+                f,
+                "chunk checksum mismatch: stored {stored:#010x}, computed {computed:#010x}"
+            ),
+            Self::PaletteIndexOutOfRange { index, palette_len } => write!(
+                f,
+                "palette index {index} out of range for palette of {palette_len}"
+            ),
+            Self::VoxelCountMismatch { decoded } => write!(
+                f,
+                "chunk runs cover {decoded} voxels, expected {CHUNK_VOLUME}"
+            ),
+            Self::InvalidVarint => write!(f, "malformed varint in chunk runs"),
+            Self::TrailingBytes(n) => write!(f, "{n} unexpected bytes after chunk payload"),
+        }
+    }
+}
+
+impl std::error::Error for ChunkCodecError {}
+
+impl Chunk {
+    /// Serialize this chunk in the versioned binary format described in the
+    /// [module docs](self).
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&CHUNK_MAGIC);
+        out.push(CHUNK_FORMAT_VERSION);
+        if let Some(voxel) = self.uniform_voxel() {
+            out.extend_from_slice(&[ENCODING_UNIFORM, 0, 0]);
+            out.extend_from_slice(&voxel.to_le_bytes());
+        } else {
+            out.extend_from_slice(&[ENCODING_PALETTE_RLE, 0, 0]);
+            encode_palette_rle(self, &mut out);
+        }
+        let crc = crc32(&out);
+        out.extend_from_slice(&crc.to_le_bytes());
+        out
+    }
+
+    /// Parse a chunk written by [`encode`](Self::encode).
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ChunkCodecError`] if the input is truncated, has the wrong
+    /// magic or version, fails its checksum, or has an inconsistent payload.
+    pub fn decode(bytes: &[u8]) -> Result<Self, ChunkCodecError> {
+        if bytes.len() < HEADER_LEN + CRC_LEN {
+            return Err(ChunkCodecError::Truncated);
+        }
+        if bytes[..4] != CHUNK_MAGIC {
+            return Err(ChunkCodecError::BadMagic);
+        }
+        if bytes[4] != CHUNK_FORMAT_VERSION {
+            return Err(ChunkCodecError::UnsupportedVersion(bytes[4]));
+        }
+        let (body, stored) = bytes.split_at(bytes.len() - CRC_LEN);
+        let stored = u32::from_le_bytes([stored[0], stored[1], stored[2], stored[3]]);
+        let computed = crc32(body);
+        if stored != computed {
+            return Err(ChunkCodecError::ChecksumMismatch { stored, computed });
+        }
+
+        let mut reader = Reader {
+            bytes: &body[HEADER_LEN..],
+        };
+        let chunk = match body[5] {
+            ENCODING_UNIFORM => Self::uniform(reader.u32()?),
+            ENCODING_PALETTE_RLE => decode_palette_rle(&mut reader)?,
+            other => return Err(ChunkCodecError::UnknownEncoding(other)),
+        };
+        if !reader.bytes.is_empty() {
+            return Err(ChunkCodecError::TrailingBytes(reader.bytes.len()));
+        }
+        Ok(chunk)
+    }
+}
+
+fn encode_palette_rle(chunk: &Chunk, out: &mut Vec<u8>) {
+    let mut palette = Vec::new();
+    let mut lookup = HashMap::new();
+    let mut runs: Vec<(u32, u32)> = Vec::new();
+    for voxel in chunk.iter() {
+        let index = *lookup.entry(voxel).or_insert_with(|| {
+            palette.push(voxel);
+            palette.len() as u32 - 1
+        });
+        match runs.last_mut() {
+            Some((len, last)) if *last == index => *len += 1,
+            _ => runs.push((1, index)),
+        }
+    }
+    // At most CHUNK_VOLUME distinct values, which fits in a u16.
+    out.extend_from_slice(&(palette.len() as u16).to_le_bytes());
+    for voxel in &palette {
+        out.extend_from_slice(&voxel.to_le_bytes());
+    }
+    for (len, index) in runs {
+        write_varint(out, len);
+        write_varint(out, index);
+    }
+}
+
+fn decode_palette_rle(reader: &mut Reader<'_>) -> Result<Chunk, ChunkCodecError> {
+    let palette_len = usize::from(reader.u16()?);
+    let palette = (0..palette_len)
+        .map(|_| reader.u32())
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut voxels = Vec::with_capacity(CHUNK_VOLUME);
+    while voxels.len() < CHUNK_VOLUME {
+        let len = reader.varint()? as usize;
+        let index = reader.varint()?;
+        let &voxel = palette
+            .get(index as usize)
+            .ok_or(ChunkCodecError::PaletteIndexOutOfRange { index, palette_len })?;
+        if len == 0 || voxels.len() + len > CHUNK_VOLUME {
+            return Err(ChunkCodecError::VoxelCountMismatch {
+                decoded: voxels.len() + len,
+            });
+        }
+        voxels.resize(voxels.len() + len, voxel);
+    }
+    Ok(Chunk::from_voxels(&voxels))
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], ChunkCodecError> {
+        let (head, rest) = self
+            .bytes
+            .split_first_chunk::<N>()
+            .ok_or(ChunkCodecError::Truncated)?;
+        self.bytes = rest;
+        Ok(*head)
+    }
+
+    fn u16(&mut self) -> Result<u16, ChunkCodecError> {
+        self.take().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, ChunkCodecError> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    fn varint(&mut self) -> Result<u32, ChunkCodecError> {
+        let mut value = 0u32;
+        for shift in (0..32).step_by(7) {
+            let [byte] = self.take()?;
+            let bits = u32::from(byte & 0x7F);
+            if shift == 28 && bits > 0x0F {
+                return Err(ChunkCodecError::InvalidVarint);
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ChunkCodecError::InvalidVarint)
+    }
+}
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+/// CRC-32 (IEEE 802.3, as used by zip and PNG) of `bytes`.
+#[must_use]
+pub fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0u32, |crc, &b| {
+        CRC_TABLE[((crc ^ u32::from(b)) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_features::MapConfig;
+    use crate::voxel::{MAT_STONE, pack_voxel};
+    use glam::IVec3;
+
+    /// Re-seal `bytes` with a fresh CRC after tampering with the body.
+    fn reseal(bytes: &mut Vec<u8>) {
+        bytes.truncate(bytes.len() - CRC_LEN);
+        let crc = crc32(bytes);
+        bytes.extend_from_slice(&crc.to_le_bytes());
+    }
+
+    /// Header and a one-entry (air) palette, ready for hand-written runs.
+    fn single_air_palette() -> Vec<u8> {
+        let mut bytes = CHUNK_MAGIC.to_vec();
+        bytes.extend_from_slice(&[CHUNK_FORMAT_VERSION, ENCODING_PALETTE_RLE, 0, 0]);
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn crc32_matches_reference_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn uniform_chunk_encodes_to_sixteen_bytes() {
+        let chunk = Chunk::uniform(pack_voxel(MAT_STONE, 0, 0, 0));
+        let bytes = chunk.encode();
+        assert_eq!(bytes.len(), HEADER_LEN + 4 + CRC_LEN);
+        assert_eq!(Chunk::decode(&bytes), Ok(chunk));
+    }
+
+    #[test]
+    fn terrain_chunk_round_trips_compactly() {
+        let chunk = Chunk::new_terrain_at(42, IVec3::ZERO);
+        let bytes = chunk.encode();
+        assert!(bytes.len() < CHUNK_VOLUME / 2, "{} bytes", bytes.len());
+        assert_eq!(Chunk::decode(&bytes), Ok(chunk));
+    }
+
+    #[test]
+    fn shaded_chunk_round_trips() {
+        let chunk = MapConfig::default().generate_chunk(IVec3::ZERO);
+        let bytes = chunk.encode();
+        assert!(bytes.len() < CHUNK_VOLUME * 4);
+        assert_eq!(Chunk::decode(&bytes), Ok(chunk));
+    }
+
+    #[test]
+    fn truncated_input_is_rejected() {
+        let bytes = Chunk::new_terrain_at(42, IVec3::ZERO).encode();
+        assert_eq!(Chunk::decode(&bytes[..6]), Err(ChunkCodecError::Truncated));
+        let mut cut = bytes[..bytes.len() / 2].to_vec();
+        cut.extend_from_slice(&[0; CRC_LEN]);
+        reseal(&mut cut);
+        assert_eq!(Chunk::decode(&cut), Err(ChunkCodecError::Truncated));
+    }
+
+    #[test]
+    fn header_errors_are_typed() {
+        let mut bytes = Chunk::default().encode();
+        bytes[0] = b'X';
+        assert_eq!(Chunk::decode(&bytes), Err(ChunkCodecError::BadMagic));
+        bytes[0] = CHUNK_MAGIC[0];
+        bytes[4] = CHUNK_FORMAT_VERSION + 1;
+        assert_eq!(
+            Chunk::decode(&bytes),
+            Err(ChunkCodecError::UnsupportedVersion(
+                CHUNK_FORMAT_VERSION + 1
+            ))
+        );
+        bytes[4] = CHUNK_FORMAT_VERSION;
+        bytes[5] = 9;
+        reseal(&mut bytes);
+        assert_eq!(
+            Chunk::decode(&bytes),
+            Err(ChunkCodecError::UnknownEncoding(9))
+        );
+    }
+
+    #[test]
+    fn flipped_bit_fails_checksum() {
+        let mut bytes = Chunk::new_terrain_at(42, IVec3::ZERO).encode();
+        bytes[HEADER_LEN + 3] ^= 0x10;
+        assert!(matches!(
+            Chunk::decode(&bytes),
+            Err(ChunkCodecError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn inconsistent_payload_is_rejected() {
+        // Palette of one entry, then a run pointing at entry 1.
+        let mut bytes = single_air_palette();
+        let body_len = bytes.len();
+        write_varint(&mut bytes, 10);
+        write_varint(&mut bytes, 1);
+        bytes.extend_from_slice(&[0; CRC_LEN]);
+        reseal(&mut bytes);
+        assert_eq!(
+            Chunk::decode(&bytes),
+            Err(ChunkCodecError::PaletteIndexOutOfRange {
+                index: 1,
+                palette_len: 1
+            })
+        );
+
+        // A run longer than the chunk.
+        bytes.truncate(body_len);
+        write_varint(&mut bytes, CHUNK_VOLUME as u32 + 1);
+        write_varint(&mut bytes, 0);
+        bytes.extend_from_slice(&[0; CRC_LEN]);
+        reseal(&mut bytes);
+        assert_eq!(
+            Chunk::decode(&bytes),
+            Err(ChunkCodecError::VoxelCountMismatch {
+                decoded: CHUNK_VOLUME + 1
+            })
+        );
+
+        // Extra bytes after a complete chunk.
+        let mut bytes = Chunk::default().encode();
+        bytes.truncate(bytes.len() - CRC_LEN);
+        bytes.push(0);
+        bytes.extend_from_slice(&[0; CRC_LEN]);
+        reseal(&mut bytes);
+        assert_eq!(
+            Chunk::decode(&bytes),
+            Err(ChunkCodecError::TrailingBytes(1))
+        );
+
+        // A varint that never terminates.
+        let mut bytes = single_air_palette();
+        bytes.extend_from_slice(&[0xFF; 6]);
+        bytes.extend_from_slice(&[0; CRC_LEN]);
+        reseal(&mut bytes);
+        assert_eq!(Chunk::decode(&bytes), Err(ChunkCodecError::InvalidVarint));
+    }
+}
@@ -1,24 +1,55 @@
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 use glam::{IVec3, UVec3, Vec3};
 
+use crate::chunk_payload::{ChunkPayload, ChunkPayloadError, DecodedPayload};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::chunk_source::WorkerPoolSource;
+use crate::chunk_source::{ChunkGen, ChunkSource, LocalSource, SourcedChunk};
 use crate::collision::CollisionMap;
-use crate::render::chunk_atlas::{ChunkAtlas, world_to_slot};
-use crate::terrain_grid::TerrainGrid;
-#[cfg(test)]
-use crate::voxel::CHUNK_SIZE;
+use crate::edit_overlay::EditOverlay;
+use crate::interest::{InterestPoint, PinnedRegion};
+use crate::load_priority::{LEAD_TICKS, LoadFocus, LoadView};
+use crate::material::{MaterialDef, MaterialRegistry};
+use crate::mutation::{DirtyBox, RegionTooLarge, VoxelRegion, group_by_chunk};
+use crate::render::chunk_atlas::{Allocation, ChunkAtlas, SlotAllocator};
+use crate::render::static_lights::chunk_static_lights;
+use crate::terrain_grid::{TerrainGrid, WorldSurface};
+use crate::view_volume::{SurfaceHeight, ViewVolume};
 use crate::voxel::{
-    Chunk, pack_voxel, pos_to_chunk_coord, world_ivec_to_chunk, world_pos_to_chunk,
+    CHUNK_SIZE, Chunk, FLAG_INDESTRUCTIBLE, flags, material_id, pack_voxel, pos_to_chunk_coord,
+    voxel_coords, with_flags, world_ivec_to_chunk, world_pos_to_chunk,
 };
 
 /// Per-chunk data retained after GPU upload: atlas slot + collision bitfield + terrain grid.
+/// Empty chunks hold no slot and no derived data.
 struct LoadedChunk {
-    slot: u32,
+    slot: Option<u32>,
     collision: Option<CollisionMap>,
     terrain: Option<TerrainGrid>,
     chunk: Chunk,
 }
 
+/// Ticks a source request may stay unanswered before the chunk is generated
+/// locally instead (about two seconds at 60 fps).
+pub const DEFAULT_REQUEST_TIMEOUT_TICKS: u32 = 120;
+
+/// Where a chunk is in its load lifecycle:
+/// `Empty → Requested → Ready`, or `Requested → Failed → Ready` when the
+/// source fails or times out and the chunk is generated locally.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChunkState {
+    /// Not loaded and not requested.
+    Empty,
+    /// Requested from the [`ChunkSource`], awaiting an answer.
+    Requested,
+    /// Loaded into the atlas.
+    Ready,
+    /// The source failed or timed out; awaiting local generation.
+    Failed,
+}
+
 /// Streaming state derived from tick statistics.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum StreamingState {
@@ -53,10 +84,24 @@ pub struct TickStats {
     pub total_loaded: u32,
     pub total_visible: u32,
     pub cached_count: u32,
+    /// Chunks requested from the source and not yet answered.
+    pub in_flight_count: u32,
     pub budget: u32,
     pub streaming_state: StreamingState,
 }
 
+/// How [`ChunkManager`] found room for a chunk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Placement {
+    /// Took a free slot.
+    Placed,
+    /// Reclaimed the slot of a cached chunk.
+    Evicted,
+    /// Every slot holds a chunk that may not be evicted; the chunk was
+    /// dropped.
+    NoRoom,
+}
+
 /// Result of a `ChunkManager::tick()` call.
 pub struct TickResult {
     pub grid_info: crate::camera::GridInfo,
@@ -66,25 +111,66 @@ pub struct TickResult {
 /// Manages dynamic chunk loading and unloading around the camera.
 ///
 /// Wraps a [`ChunkAtlas`] and tracks which world coordinates are loaded.
-/// Slot assignment uses modular mapping (`world_coord % atlas_slots`) so
-/// chunks keep stable atlas positions as the camera moves.
+/// Slots come from a [`SlotAllocator`]: a new chunk takes a free slot, or
+/// else the slot of the least recently visible cached chunk. Visible chunks,
+/// chunks around [interest points](Self::set_interest_point) and
+/// [pinned regions](Self::pin_region) are never evicted.
 pub struct ChunkManager {
     atlas: ChunkAtlas,
+    slot_alloc: SlotAllocator,
     /// Maps loaded world chunk coordinate to per-chunk data.
     loaded: HashMap<IVec3, LoadedChunk>,
     /// The set of chunk coordinates currently visible from the camera.
     visible: HashSet<IVec3>,
-    chunk_gen: Box<dyn Fn(IVec3) -> Chunk + Send>,
-    view_distance: u32,
+    /// Named points streamed around besides the camera.
+    interest_points: HashMap<String, InterestPoint>,
+    /// Chunks the last tick kept loaded: visible, around interest points
+    /// or pinned. None of them is evicted.
+    kept: HashSet<IVec3>,
+    /// Named regions loaded and kept regardless of the camera.
+    pinned: HashMap<String, PinnedRegion>,
+    /// Local generator, used directly by [`load_chunk`](Self::load_chunk)
+    /// and as the fallback when the source fails.
+    chunk_gen: Arc<ChunkGen>,
+    /// Provider of streamed chunks.
+    source: Box<dyn ChunkSource>,
+    /// Chunks in the [`ChunkState::Requested`] or [`ChunkState::Failed`] state.
+    requests: HashMap<IVec3, Request>,
+    /// Number of streaming ticks so far, the clock for request timeouts.
+    tick_count: u64,
+    request_timeout_ticks: u32,
+    /// Camera view volume used to load on-screen chunks first.
+    view: Option<LoadView>,
+    /// Camera position at the previous tick, for estimating velocity.
+    last_eye: Option<Vec3>,
+    /// Voxel edits replayed onto chunks when they are regenerated after eviction.
+    edits: EditOverlay,
+    /// Chunks whose terrain grid changed after load through seam stitching.
+    terrain_dirty: HashSet<IVec3>,
+    /// Material properties used to derive collision and terrain data.
+    materials: MaterialRegistry,
+    /// Whether `materials` was changed from the default table that chunk
+    /// servers extract [`ChunkPayload`]s with.
+    custom_materials: bool,
+    /// Region streamed around the camera.
+    volume: ViewVolume,
+    /// Terrain height estimate for the volume's surface band.
+    surface_height: Option<Box<SurfaceHeight>>,
     atlas_slots: UVec3,
 }
 
+/// An outstanding chunk request.
+#[derive(Clone, Copy)]
+enum Request {
+    InFlight { since_tick: u64 },
+    Failed,
+}
+
 impl ChunkManager {
     /// # Panics
     ///
-    /// Panics if any axis of `atlas_slots` is smaller than `2 * view_distance + 1`.
-    /// The atlas must be at least as large as the visible set to avoid modular
-    /// slot collisions.
+    /// Panics if the atlas has fewer slots than the `(2 * view_distance + 1)^3`
+    /// visible chunks.
     #[must_use]
     pub fn new(device: &wgpu::Device, seed: u32, view_distance: u32, atlas_slots: UVec3) -> Self {
         Self::with_chunk_gen(
@@ -95,72 +181,319 @@ impl ChunkManager {
         )
     }
 
-    /// Create a `ChunkManager` with a custom chunk generation closure.
+    /// Create a `ChunkManager` with a custom chunk generation closure. Chunks
+    /// are streamed from a [`LocalSource`] running the same closure until
+    /// [`set_source`](Self::set_source) installs another source.
     ///
     /// # Panics
     ///
-    /// Panics if any axis of `atlas_slots` is smaller than `2 * view_distance + 1`.
+    /// Panics if the atlas has fewer slots than the `(2 * view_distance + 1)^3`
+    /// visible chunks.
     #[must_use]
     pub fn with_chunk_gen(
         device: &wgpu::Device,
         view_distance: u32,
         atlas_slots: UVec3,
-        chunk_gen: Box<dyn Fn(IVec3) -> Chunk + Send>,
+        chunk_gen: Box<ChunkGen>,
+    ) -> Self {
+        Self::with_view_volume(
+            device,
+            ViewVolume::cube(view_distance),
+            atlas_slots,
+            chunk_gen,
+        )
+    }
+
+    /// Like [`with_chunk_gen`](Self::with_chunk_gen), streaming the chunks
+    /// in `volume` instead of a cube.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the atlas has fewer slots than
+    /// [`ViewVolume::max_chunks`].
+    #[must_use]
+    pub fn with_view_volume(
+        device: &wgpu::Device,
+        volume: ViewVolume,
+        atlas_slots: UVec3,
+        chunk_gen: Box<ChunkGen>,
     ) -> Self {
-        let min_slots = 2 * view_distance + 1;
+        let chunk_gen: Arc<ChunkGen> = Arc::from(chunk_gen);
+        let total_slots = atlas_slots.x * atlas_slots.y * atlas_slots.z;
+        let visible_chunks = volume.max_chunks();
         assert!(
-            atlas_slots.x >= min_slots && atlas_slots.y >= min_slots && atlas_slots.z >= min_slots,
-            "atlas_slots ({atlas_slots}) must be >= 2*view_distance+1 ({min_slots}) on every axis"
+            total_slots >= visible_chunks,
+            "atlas_slots ({atlas_slots}) must hold the {visible_chunks} visible chunks"
         );
+        let grid = volume.bounding_size();
         Self {
-            atlas: ChunkAtlas::new(device, atlas_slots),
+            atlas: ChunkAtlas::with_grid_capacity(device, atlas_slots, grid.x * grid.y * grid.z),
+            slot_alloc: SlotAllocator::new(total_slots),
             loaded: HashMap::new(),
             visible: HashSet::new(),
+            interest_points: HashMap::new(),
+            kept: HashSet::new(),
+            pinned: HashMap::new(),
+            source: Box::new(LocalSource::new(Arc::clone(&chunk_gen))),
             chunk_gen,
-            view_distance,
+            requests: HashMap::new(),
+            tick_count: 0,
+            request_timeout_ticks: DEFAULT_REQUEST_TIMEOUT_TICKS,
+            view: None,
+            last_eye: None,
+            edits: EditOverlay::default(),
+            terrain_dirty: HashSet::new(),
+            materials: MaterialRegistry::default(),
+            custom_materials: false,
+            volume,
+            surface_height: None,
             atlas_slots,
         }
     }
 
-    /// Generate terrain for `coord` and upload to the atlas.
+    /// Replace the source streamed chunks are requested from. `None`
+    /// restores local generation. Requests still in flight with the old
+    /// source are forgotten and issued again on later ticks.
+    pub fn set_source(&mut self, source: Option<Box<dyn ChunkSource>>) {
+        self.source =
+            source.unwrap_or_else(|| Box::new(LocalSource::new(Arc::clone(&self.chunk_gen))));
+        self.requests.clear();
+    }
+
+    /// Generate and extract streamed chunks on `threads` worker threads with
+    /// the local generator. Loading stays deterministic: each tick waits for
+    /// the chunks it requested. Native only.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn use_worker_pool(&mut self, threads: std::num::NonZeroUsize) {
+        let pool = WorkerPoolSource::new(&self.chunk_gen, self.materials.clone(), threads);
+        self.set_source(Some(Box::new(pool)));
+    }
+
+    /// Provide the terrain height estimate the volume's
+    /// [`SurfaceBand`](crate::view_volume::SurfaceBand) is measured from.
+    /// Without one the band is ignored.
+    pub fn set_surface_height(&mut self, height: Option<Box<SurfaceHeight>>) {
+        self.surface_height = height;
+    }
+
+    /// Stream chunks around `point` under `name`, replacing any point
+    /// already there. Its chunks load within the tick budget alongside the
+    /// camera's and are not evicted while the point exists, as far as the
+    /// atlas has room left after the visible chunks.
+    ///
+    /// Returns `false` and leaves the points unchanged if the point's
+    /// volume alone holds more chunks than the atlas.
+    pub fn set_interest_point(&mut self, name: &str, point: InterestPoint) -> bool {
+        let slots = u64::from(self.atlas.total_slots());
+        let side = 2 * u64::from(point.volume.horizontal) + 1;
+        let height = 2 * u64::from(point.volume.vertical) + 1;
+        // Cheap bounds first so a huge radius is never enumerated: the
+        // volume's disc covers more than half its bounding square.
+        if side.saturating_mul(side) > 2 * slots
+            || height > slots
+            || u64::from(point.volume.max_chunks()) > slots
+        {
+            return false;
+        }
+        self.interest_points.insert(name.to_owned(), point);
+        true
+    }
+
+    /// Stop streaming around the interest point `name`. Its chunks stay
+    /// cached. Returns `false` if there was no such point.
+    pub fn remove_interest_point(&mut self, name: &str) -> bool {
+        self.interest_points.remove(name).is_some()
+    }
+
+    /// Keep the chunks in `region` loaded under `name`, replacing any
+    /// region already there. Missing chunks load within the tick budget;
+    /// loaded ones are never evicted while pinned, as far as the atlas has
+    /// room left after the visible chunks.
+    ///
+    /// Returns `false` and leaves the regions unchanged if `region` holds
+    /// more chunks than the atlas.
+    pub fn pin_region(&mut self, name: &str, region: PinnedRegion) -> bool {
+        let size = (region.max.as_i64vec3() - region.min.as_i64vec3()) + 1;
+        if size.x * size.y * size.z > i64::from(self.atlas.total_slots()) {
+            return false;
+        }
+        self.pinned.insert(name.to_owned(), region);
+        true
+    }
+
+    /// Release the pinned region `name`; its chunks stay cached. Returns
+    /// `false` if there was no such region.
+    pub fn unpin_region(&mut self, name: &str) -> bool {
+        self.pinned.remove(name).is_some()
+    }
+
+    /// Whether the chunk at `coord` lies in a pinned region.
+    #[must_use]
+    pub fn is_pinned(&self, coord: IVec3) -> bool {
+        self.pinned.values().any(|region| region.contains(coord))
+    }
+
+    /// Set how many ticks a source request may stay unanswered before the
+    /// chunk is generated locally.
+    pub fn set_request_timeout(&mut self, ticks: u32) {
+        self.request_timeout_ticks = ticks;
+    }
+
+    /// Set the camera view volume. Missing chunks inside it are loaded
+    /// before those off screen; with `None` all count as on screen.
+    pub fn set_view(&mut self, view: Option<LoadView>) {
+        self.view = view;
+    }
+
+    /// Where the chunk at `coord` is in its load lifecycle.
+    #[must_use]
+    pub fn chunk_state(&self, coord: IVec3) -> ChunkState {
+        if self.loaded.contains_key(&coord) {
+            return ChunkState::Ready;
+        }
+        match self.requests.get(&coord) {
+            None => ChunkState::Empty,
+            Some(Request::InFlight { .. }) => ChunkState::Requested,
+            Some(Request::Failed) => ChunkState::Failed,
+        }
+    }
+
+    /// Number of chunks requested from the source and not yet answered.
+    #[must_use]
+    pub fn in_flight_count(&self) -> usize {
+        self.requests
+            .values()
+            .filter(|r| matches!(r, Request::InFlight { .. }))
+            .count()
+    }
+
+    /// Generate terrain for `coord`, replay any recorded edits, and upload to
+    /// the atlas, bypassing the source. An outstanding request for the
+    /// chunk is dropped and its answer ignored.
     ///
-    /// If another chunk already occupies the same modular slot, it is evicted
-    /// first (implicit LRU via slot collision).
+    /// If the atlas is full, the least recently visible cached chunk is
+    /// evicted first. Its edits survive in the [`EditOverlay`] and are
+    /// reapplied when it is loaded again. When every slot holds a visible
+    /// chunk, nothing is loaded.
     pub fn load_chunk(&mut self, queue: &wgpu::Queue, coord: IVec3) {
         if self.loaded.contains_key(&coord) {
             return;
         }
+        self.requests.remove(&coord);
+        self.generate_local(queue, coord);
+    }
 
-        let slot = world_to_slot(coord, self.atlas_slots);
+    /// Generate `coord` with the local generator and insert it.
+    fn generate_local(&mut self, queue: &wgpu::Queue, coord: IVec3) -> Placement {
+        let mut chunk = (self.chunk_gen)(coord);
+        self.edits.apply(coord, &mut chunk);
+        self.insert_chunk(queue, coord, chunk, None)
+    }
 
-        // Evict any chunk currently occupying this slot.
-        let occupant = self
-            .loaded
-            .iter()
-            .find(|(_, lc)| lc.slot == slot)
-            .map(|(c, _)| *c);
-        if let Some(old_coord) = occupant {
-            self.loaded.remove(&old_coord);
-            self.atlas.clear_slot(queue, slot);
+    /// Load a chunk from a precomputed [`ChunkPayload`] instead of the
+    /// generator. The payload's occupancy mask, collision bits and terrain
+    /// grid are used as-is, skipping extraction entirely, unless the
+    /// [`EditOverlay`] holds edits for the chunk or a material was
+    /// [defined](Self::define_material) since the payload was extracted with
+    /// the default table; then they are stale and rebuilt from the voxels as
+    /// in [`load_chunk`](Self::load_chunk).
+    /// Does nothing if the chunk is already loaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ChunkPayloadError`] if the payload is malformed, leaving
+    /// the manager unchanged.
+    pub fn ingest_payload(
+        &mut self,
+        queue: &wgpu::Queue,
+        payload: &ChunkPayload,
+    ) -> Result<(), ChunkPayloadError> {
+        if self.loaded.contains_key(&payload.coord()) {
+            return Ok(());
         }
+        self.insert_payload(queue, payload).map(|_| ())
+    }
 
-        let chunk = (self.chunk_gen)(coord);
-        if chunk.is_empty() {
-            // Track as loaded but don't upload — shader sees flags=0.
-            self.loaded.insert(
-                coord,
-                LoadedChunk {
-                    slot,
-                    collision: None,
-                    terrain: None,
-                    chunk,
-                },
-            );
-            return;
+    /// Decode and insert `payload`, keeping its derived data only while the
+    /// material table is the default one it was extracted with.
+    fn insert_payload(
+        &mut self,
+        queue: &wgpu::Queue,
+        payload: &ChunkPayload,
+    ) -> Result<Placement, ChunkPayloadError> {
+        let decoded = payload.decode()?;
+        if !self.custom_materials {
+            return Ok(self.insert_decoded(queue, decoded));
         }
-        let collision = Some(CollisionMap::from_voxels(&chunk.voxels));
-        let terrain = Some(TerrainGrid::from_chunk(&chunk));
-        self.atlas.upload_chunk(queue, slot, &chunk, coord);
+        let (coord, mut chunk) = (decoded.coord, decoded.chunk);
+        self.edits.apply(coord, &mut chunk);
+        Ok(self.insert_chunk(queue, coord, chunk, None))
+    }
+
+    /// Insert a chunk with precomputed derived data, recomputing it if the
+    /// [`EditOverlay`] changes the voxels.
+    fn insert_decoded(&mut self, queue: &wgpu::Queue, decoded: DecodedPayload) -> Placement {
+        let DecodedPayload {
+            coord,
+            mut chunk,
+            occupancy,
+            collision,
+            terrain,
+        } = decoded;
+        let edited = self.edits.apply(coord, &mut chunk);
+        let precomputed = (!edited).then_some((occupancy, collision, terrain));
+        self.insert_chunk(queue, coord, chunk, precomputed)
+    }
+
+    /// Place `chunk` at `coord`, upload it and derive its collision and
+    /// terrain data, or take `(occupancy, collision, terrain)` from
+    /// `precomputed` when given.
+    fn insert_chunk(
+        &mut self,
+        queue: &wgpu::Queue,
+        coord: IVec3,
+        chunk: Chunk,
+        precomputed: Option<(u64, CollisionMap, TerrainGrid)>,
+    ) -> Placement {
+        self.requests.remove(&coord);
+        // Empty chunks are tracked as loaded but take no slot — shader sees flags=0.
+        let (slot, evicted) = if chunk.is_empty() {
+            (None, None)
+        } else {
+            let (visible, kept) = (&self.visible, &self.kept);
+            let Some(Allocation { slot, evicted }) = self.slot_alloc.allocate(coord, |owner| {
+                visible.contains(&owner) || kept.contains(&owner)
+            }) else {
+                return Placement::NoRoom;
+            };
+            if let Some(old_coord) = evicted {
+                self.loaded.remove(&old_coord);
+                self.atlas.clear_slot(queue, slot);
+            }
+            self.slot_alloc.touch(slot, self.tick_count);
+            (Some(slot), evicted)
+        };
+
+        let (collision, terrain) = if let Some(slot) = slot {
+            self.atlas.upload_static_lights(
+                queue,
+                slot,
+                &chunk_static_lights(&chunk, coord, &self.materials),
+            );
+            if let Some((occupancy, collision, terrain)) = precomputed {
+                self.atlas
+                    .upload_precomputed(queue, slot, &chunk, coord, occupancy);
+                (Some(collision), Some(terrain))
+            } else {
+                self.atlas.upload_chunk(queue, slot, &chunk, coord);
+                (
+                    Some(CollisionMap::from_chunk(&chunk, &self.materials)),
+                    Some(TerrainGrid::from_chunk(&chunk, &self.materials)),
+                )
+            }
+        } else {
+            (None, None)
+        };
         self.loaded.insert(
             coord,
             LoadedChunk {
@@ -170,13 +503,73 @@ impl ChunkManager {
                 chunk,
             },
         );
+
+        self.stitch_seam(coord);
+        self.stitch_below(coord);
+        if let Some(old_coord) = evicted {
+            self.stitch_below(old_coord);
+        }
+        if evicted.is_some() {
+            Placement::Evicted
+        } else {
+            Placement::Placed
+        }
+    }
+
+    /// Empty chunks hold no slot, so eviction never reclaims them. Once
+    /// there are more of them than atlas slots, drop those no longer wanted.
+    fn forget_empty_chunks(&mut self) {
+        let empty = self.loaded.values().filter(|l| l.slot.is_none()).count();
+        if empty <= self.atlas.total_slots() as usize {
+            return;
+        }
+        let kept = &self.kept;
+        let mut forgotten = Vec::new();
+        self.loaded.retain(|coord, loaded| {
+            let keep = loaded.slot.is_some() || kept.contains(coord);
+            if !keep {
+                forgotten.push(*coord);
+            }
+            keep
+        });
+        for coord in forgotten {
+            self.stitch_below(coord);
+        }
+    }
+
+    /// Re-stitch the chunk below `coord` after `coord` was loaded, edited or
+    /// dropped, marking its terrain dirty if the grid changed.
+    fn stitch_below(&mut self, coord: IVec3) {
+        let below = coord - IVec3::Y;
+        if self.stitch_seam(below) {
+            self.terrain_dirty.insert(below);
+        }
+    }
+
+    /// Stitch the terrain grid of `below` against the chunk directly above
+    /// it (or un-stitch it if that chunk is not loaded). Returns `true` if
+    /// the grid of `below` changed.
+    fn stitch_seam(&mut self, below: IVec3) -> bool {
+        let above = below + IVec3::Y;
+        let [Some(lower), upper] = self.loaded.get_disjoint_mut([&below, &above]) else {
+            return false;
+        };
+        let Some(terrain) = lower.terrain.as_mut() else {
+            return false;
+        };
+        terrain.stitch_above(&lower.chunk, upper.map(|u| &u.chunk), &self.materials)
     }
 
     /// Unload a chunk: clear its atlas slot and stop tracking it.
     pub fn unload_chunk(&mut self, queue: &wgpu::Queue, coord: IVec3) {
-        if let Some(loaded) = self.loaded.remove(&coord) {
-            self.atlas.clear_slot(queue, loaded.slot);
+        let Some(loaded) = self.loaded.remove(&coord) else {
+            return;
+        };
+        if let Some(slot) = loaded.slot {
+            self.atlas.clear_slot(queue, slot);
+            self.slot_alloc.release(slot);
         }
+        self.stitch_below(coord);
     }
 
     /// Number of currently loaded chunks.
@@ -191,10 +584,21 @@ impl ChunkManager {
         self.visible.len()
     }
 
-    /// Number of cached chunks (loaded but not in the current view box).
+    /// Number of cached chunks: loaded but not visible, around an interest
+    /// point or pinned.
     #[must_use]
     pub fn cached_count(&self) -> usize {
-        self.loaded.len().saturating_sub(self.visible.len())
+        self.loaded
+            .keys()
+            .filter(|c| !self.visible.contains(c) && !self.kept.contains(c))
+            .count()
+    }
+
+    /// Heap bytes held by the palette-compressed voxel data of all loaded
+    /// and cached chunks.
+    #[must_use]
+    pub fn voxel_memory_bytes(&self) -> usize {
+        self.loaded.values().map(|lc| lc.chunk.heap_bytes()).sum()
     }
 
     /// Whether a chunk at `coord` is currently loaded.
@@ -215,10 +619,26 @@ impl ChunkManager {
         self.atlas_slots
     }
 
-    /// The view distance in chunks.
+    /// The horizontal view distance in chunks.
     #[must_use]
     pub fn view_distance(&self) -> u32 {
-        self.view_distance
+        self.volume.horizontal
+    }
+
+    /// The region streamed around the camera.
+    #[must_use]
+    pub fn view_volume(&self) -> ViewVolume {
+        self.volume
+    }
+
+    /// Chunks the [view volume](Self::view_volume) covers for a camera at
+    /// `camera_pos`.
+    #[must_use]
+    pub fn visible_set_at(&self, camera_pos: Vec3) -> Vec<IVec3> {
+        self.volume.chunks_around(
+            pos_to_chunk_coord(camera_pos),
+            self.surface_height.as_deref(),
+        )
     }
 
     /// Check if the voxel at `world_pos` is solid. Returns `false` for
@@ -242,22 +662,238 @@ impl ChunkManager {
         self.loaded.get(&coord).and_then(|lc| lc.terrain.as_ref())
     }
 
+    /// All walkable surfaces in world column `(wx, wz)` across every loaded
+    /// vertical layer, sorted bottom-to-top by world y.
+    #[must_use]
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn surfaces_at_world(&self, wx: i32, wz: i32) -> Vec<WorldSurface> {
+        let (chunk_xz, (lx, _, lz)) = world_ivec_to_chunk(IVec3::new(wx, 0, wz));
+        let chunk_size = CHUNK_SIZE as i32;
+        let mut surfaces: Vec<WorldSurface> = self
+            .loaded
+            .iter()
+            .filter(|(c, _)| c.x == chunk_xz.x && c.z == chunk_xz.z)
+            .filter_map(|(c, lc)| lc.terrain.as_ref().map(|t| (c.y * chunk_size, t)))
+            .flat_map(|(base_y, terrain)| {
+                terrain
+                    .surfaces_at(lx, lz)
+                    .iter()
+                    .map(move |&surface| WorldSurface {
+                        y: base_y + i32::from(surface.y),
+                        surface,
+                    })
+            })
+            .collect();
+        surfaces.sort_by_key(|s| s.y);
+        surfaces
+    }
+
+    /// Drain the chunks whose terrain grids changed after load because a
+    /// neighbour across the vertical seam was loaded or mutated. Sorted by
+    /// `(x, y, z)`. Consumers that cache grids should re-fetch these.
+    pub fn take_dirty_terrain(&mut self) -> Vec<IVec3> {
+        let mut dirty: Vec<IVec3> = self.terrain_dirty.drain().collect();
+        dirty.sort_by_key(|c| (c.x, c.y, c.z));
+        dirty
+    }
+
+    /// The material registry used for collision and terrain extraction.
+    #[must_use]
+    pub fn materials(&self) -> &MaterialRegistry {
+        &self.materials
+    }
+
+    /// Define or replace material `id` and rebuild collision, terrain and
+    /// static light data for every loaded chunk, since solidity or emission
+    /// may have changed. Rebuilt grids are reported through
+    /// [`take_dirty_terrain`](Self::take_dirty_terrain). Returns `false` (and
+    /// changes nothing) for air.
+    pub fn define_material(&mut self, queue: &wgpu::Queue, id: u8, def: MaterialDef) -> bool {
+        if !self.materials.define(id, def) {
+            return false;
+        }
+        self.custom_materials = true;
+        self.source.set_materials(&self.materials);
+        for (coord, loaded) in &mut self.loaded {
+            let Some(slot) = loaded.slot else {
+                continue;
+            };
+            loaded.collision = Some(CollisionMap::from_chunk(&loaded.chunk, &self.materials));
+            loaded.terrain = Some(TerrainGrid::from_chunk(&loaded.chunk, &self.materials));
+            self.atlas.upload_static_lights(
+                queue,
+                slot,
+                &chunk_static_lights(&loaded.chunk, *coord, &self.materials),
+            );
+            self.terrain_dirty.insert(*coord);
+        }
+        let coords: Vec<IVec3> = self.loaded.keys().copied().collect();
+        for coord in coords {
+            self.stitch_seam(coord);
+        }
+        true
+    }
+
+    /// Number of chunks with voxel edits retained across eviction.
+    #[must_use]
+    pub fn edited_chunk_count(&self) -> usize {
+        self.edits.edited_chunk_count()
+    }
+
     /// Mutate a single voxel at `world_pos` to the given `material_id`.
     ///
-    /// Updates the chunk's voxel data, rebuilds collision and terrain maps,
-    /// and re-uploads the chunk to the GPU atlas. No-op if the chunk at
-    /// `world_pos` is not loaded.
+    /// Convenience wrapper around [`mutate_voxels`](Self::mutate_voxels).
+    /// No-op if the chunk at `world_pos` is not loaded.
     pub fn mutate_voxel(&mut self, queue: &wgpu::Queue, world_pos: IVec3, material_id: u8) {
-        let (chunk_coord, (lx, ly, lz)) = world_ivec_to_chunk(world_pos);
-        if let Some(loaded) = self.loaded.get_mut(&chunk_coord) {
-            loaded
-                .chunk
-                .set_voxel(lx, ly, lz, pack_voxel(material_id, 0, 0, 0));
-            loaded.collision = Some(CollisionMap::from_voxels(&loaded.chunk.voxels));
-            loaded.terrain = Some(TerrainGrid::from_chunk(&loaded.chunk));
-            self.atlas
-                .upload_chunk(queue, loaded.slot, &loaded.chunk, chunk_coord);
+        self.mutate_voxels(queue, &[(world_pos, material_id)]);
+    }
+
+    /// Set every voxel in `region` to `material_id`. See
+    /// [`mutate_voxels`](Self::mutate_voxels) for rebuild and reporting
+    /// semantics.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RegionTooLarge`] and changes nothing if the region covers
+    /// more than [`MAX_REGION_VOXELS`](crate::mutation::MAX_REGION_VOXELS).
+    pub fn mutate_region(
+        &mut self,
+        queue: &wgpu::Queue,
+        region: &VoxelRegion,
+        material_id: u8,
+    ) -> Result<Vec<IVec3>, RegionTooLarge> {
+        let voxel = pack_voxel(material_id, 0, 0, 0);
+        let voxels = region.voxels()?;
+        Ok(self.apply_writes(queue, voxels.into_iter().map(|p| (p, voxel)), false))
+    }
+
+    /// Apply a batch of `(world_pos, material_id)` writes.
+    ///
+    /// Writes are grouped by chunk. Each touched chunk patches its collision
+    /// bits per voxel, rescans only the terrain columns it touched, and
+    /// re-uploads only the bounding box of its changed voxels to the atlas.
+    /// Every write is recorded in the edit overlay so it survives eviction.
+    /// Writes into unloaded chunks are ignored, as are writes that leave a
+    /// voxel unchanged or target a voxel flagged [`FLAG_INDESTRUCTIBLE`].
+    /// Returns the coordinates of chunks whose
+    /// voxels actually changed, sorted by `(x, y, z)`.
+    pub fn mutate_voxels(&mut self, queue: &wgpu::Queue, writes: &[(IVec3, u8)]) -> Vec<IVec3> {
+        self.apply_writes(
+            queue,
+            writes
+                .iter()
+                .map(|&(pos, material_id)| (pos, pack_voxel(material_id, 0, 0, 0))),
+            false,
+        )
+    }
+
+    /// Replace the `flags` byte of each voxel in a batch of
+    /// `(world_pos, flags)` writes, keeping material and shading params.
+    /// This is how doors are opened and closed, and is allowed on
+    /// indestructible voxels. Rebuild and reporting semantics match
+    /// [`mutate_voxels`](Self::mutate_voxels).
+    pub fn set_voxel_flags(&mut self, queue: &wgpu::Queue, writes: &[(IVec3, u8)]) -> Vec<IVec3> {
+        let packed: Vec<(IVec3, u32)> = writes
+            .iter()
+            .filter_map(|&(pos, f)| self.voxel_at(pos).map(|v| (pos, with_flags(v, f))))
+            .collect();
+        self.apply_writes(queue, packed, true)
+    }
+
+    /// The packed voxel at `world_pos`, or `None` if its chunk is not loaded.
+    #[must_use]
+    pub fn voxel_at(&self, world_pos: IVec3) -> Option<u32> {
+        let (coord, (lx, ly, lz)) = world_ivec_to_chunk(world_pos);
+        self.loaded
+            .get(&coord)
+            .map(|loaded| loaded.chunk.voxel_at(lx, ly, lz))
+    }
+
+    /// Write packed voxels. Indestructible voxels only take writes when
+    /// `flags_only`, i.e. from [`set_voxel_flags`](Self::set_voxel_flags),
+    /// which keeps their material and params.
+    fn apply_writes(
+        &mut self,
+        queue: &wgpu::Queue,
+        writes: impl IntoIterator<Item = (IVec3, u32)>,
+        flags_only: bool,
+    ) -> Vec<IVec3> {
+        let mut changed = Vec::new();
+        for (chunk_coord, chunk_writes) in group_by_chunk(writes) {
+            let Some(loaded) = self.loaded.get_mut(&chunk_coord) else {
+                continue;
+            };
+            let mut dirty: Option<DirtyBox> = None;
+            let mut dirty_columns = HashSet::new();
+            let mut lights_changed = false;
+            for (index, voxel) in chunk_writes {
+                let current = loaded.chunk.get(index);
+                if current == voxel || (!flags_only && flags(current) & FLAG_INDESTRUCTIBLE != 0) {
+                    continue;
+                }
+                lights_changed |= self.materials.emissive(material_id(current)) > 0.0
+                    || self.materials.emissive(material_id(voxel)) > 0.0;
+                loaded.chunk.set(index, voxel);
+                self.edits.record(chunk_coord, index, voxel);
+                let (lx, ly, lz) = voxel_coords(index);
+                if let Some(collision) = loaded.collision.as_mut() {
+                    collision.set(lx, ly, lz, self.materials.is_solid_voxel(voxel));
+                }
+                dirty_columns.insert((lx, lz));
+                let local = UVec3::new(lx as u32, ly as u32, lz as u32);
+                match dirty.as_mut() {
+                    Some(dirty_box) => dirty_box.include(local),
+                    None => dirty = Some(DirtyBox::from_point(local)),
+                }
+            }
+            let Some(dirty) = dirty else {
+                continue;
+            };
+            // Edits only ever grow the palette; repack once it outgrows
+            // byte-wide indices so an edited chunk does not stay wide.
+            if loaded.chunk.storage().bits_per_voxel() > 8 {
+                loaded.chunk.compact();
+            }
+            if loaded.chunk.is_empty() {
+                loaded.collision = None;
+                loaded.terrain = None;
+                if let Some(slot) = loaded.slot.take() {
+                    self.atlas.clear_slot(queue, slot);
+                    self.slot_alloc.release(slot);
+                }
+            } else if let Some(slot) = loaded.slot {
+                // Resident chunk: collision bits were patched above; rescan
+                // only the touched terrain columns and upload the dirty box.
+                if let Some(terrain) = loaded.terrain.as_mut() {
+                    for (lx, lz) in dirty_columns {
+                        terrain.update_column(lx, lz, &loaded.chunk, &self.materials);
+                    }
+                }
+                self.atlas.upload_sub_box(queue, slot, &loaded.chunk, dirty);
+                // Static lights only move when an emitter is placed or removed.
+                if lights_changed {
+                    self.atlas.upload_static_lights(
+                        queue,
+                        slot,
+                        &chunk_static_lights(&loaded.chunk, chunk_coord, &self.materials),
+                    );
+                }
+            } else if let Some(previous) = self.loaded.remove(&chunk_coord) {
+                // Previously all-air chunk: it holds no slot yet, so place it
+                // like a fresh load. Without room it is dropped and its edits
+                // replay when it loads again.
+                self.insert_chunk(queue, chunk_coord, previous.chunk, None);
+            }
+            changed.push(chunk_coord);
         }
+        changed.sort_by_key(|c| (c.x, c.y, c.z));
+        for &coord in &changed {
+            // Newly built grids need their own seam; the grid below may gain
+            // or lose surfaces if voxels near y=0 changed.
+            self.stitch_seam(coord);
+            self.stitch_below(coord);
+        }
+        changed
     }
 
     /// Compute the set of chunk coordinates visible from `camera_pos` with the
@@ -288,9 +924,16 @@ impl ChunkManager {
 
     /// Advance chunk streaming with a per-tick budget.
     ///
-    /// Loads up to `budget` new chunks per call, prioritized by distance from
-    /// camera (closest first). Stale chunks stay cached; eviction happens only
-    /// when a new chunk's modular slot is occupied.
+    /// Requests up to `budget` missing chunks per call from the
+    /// [`ChunkSource`] in [`LoadFocus::priority`] order: closest to where
+    /// the camera is heading first, with chunks outside the
+    /// [view](Self::set_view) and likely sky chunks pushed back. Chunks
+    /// around [interest points](Self::set_interest_point) and in
+    /// [pinned regions](Self::pin_region) compete for the same budget. Loads
+    /// whatever the source has answered. Failed and timed-out
+    /// requests are generated locally, drawing on the same budget. Stale
+    /// chunks stay cached until the atlas is full; then the least recently
+    /// visible one makes room for each new chunk.
     pub fn tick_budgeted(
         &mut self,
         queue: &wgpu::Queue,
@@ -325,25 +968,33 @@ impl ChunkManager {
         budget: u32,
         animation: Option<&crate::camera::CameraAnimation>,
     ) -> TickResult {
-        let visible = Self::compute_visible_set(camera_pos, self.view_distance);
-        let visible_set: HashSet<IVec3> = visible.iter().copied().collect();
-        self.visible.clone_from(&visible_set);
-
-        let cam_chunk = pos_to_chunk_coord(camera_pos);
-
-        // Current-view chunks: sorted by distance (highest priority).
-        let mut to_load: Vec<IVec3> = self
-            .visible
-            .iter()
-            .filter(|c| !self.loaded.contains_key(c))
-            .copied()
+        self.visible = self.visible_set_at(camera_pos).into_iter().collect();
+        self.tick_count += 1;
+        let focus = self.load_focus(camera_pos);
+        let wanted = self.wanted_chunks(&focus);
+        self.kept = wanted.keys().copied().collect();
+        for coord in &self.kept {
+            if let Some(slot) = self.loaded.get(coord).and_then(|loaded| loaded.slot) {
+                self.slot_alloc.touch(slot, self.tick_count);
+            }
+        }
+        self.forget_empty_chunks();
+        let grid_info = self.compute_grid_info();
+        self.atlas.set_grid(queue, grid_info.origin, grid_info.size);
+
+        // Visible, interest and pinned chunks, highest priority first.
+        let mut ranked: Vec<(f32, IVec3)> = wanted
+            .into_iter()
+            .filter(|(c, _)| !self.loaded.contains_key(c))
+            .map(|(c, priority)| (priority, c))
             .collect();
-        to_load.sort_by_key(|c| {
-            let d = *c - cam_chunk;
-            d.x * d.x + d.y * d.y + d.z * d.z
+        // Ties break on the coordinate so the budget picks the same chunks
+        // regardless of hash order.
+        ranked.sort_by(|(pa, a), (pb, b)| {
+            pa.total_cmp(pb)
+                .then_with(|| (a.z, a.y, a.x).cmp(&(b.z, b.y, b.x)))
         });
-
-        let visible_pending = to_load.len() as u32;
+        let mut to_load: Vec<IVec3> = ranked.into_iter().map(|(_, c)| c).collect();
 
         // Prediction chunks: appended after current-view (lower priority).
         if let Some(anim) = animation {
@@ -355,29 +1006,65 @@ impl ChunkManager {
             }
         }
 
+        // Requests left unanswered too long fall back to local generation.
+        let timeout = u64::from(self.request_timeout_ticks);
+        for request in self.requests.values_mut() {
+            if let Request::InFlight { since_tick } = *request
+                && self.tick_count - since_tick > timeout
+            {
+                *request = Request::Failed;
+            }
+        }
+
         let mut loaded_this_tick: u32 = 0;
         let mut unloaded_this_tick: u32 = 0;
-        for coord in to_load.iter().take(budget as usize) {
-            let slot = world_to_slot(*coord, self.atlas_slots);
-            let will_evict = self
-                .loaded
-                .iter()
-                .any(|(c, lc)| lc.slot == slot && *c != *coord);
-            self.load_chunk(queue, *coord);
-            loaded_this_tick += 1;
-            if will_evict {
-                unloaded_this_tick += 1;
+        let mut spent: u32 = 0;
+        for coord in to_load {
+            if spent >= budget {
+                break;
+            }
+            match self.requests.get(&coord) {
+                Some(Request::InFlight { .. }) => continue,
+                Some(Request::Failed) => {
+                    self.requests.remove(&coord);
+                    match self.generate_local(queue, coord) {
+                        Placement::Placed => loaded_this_tick += 1,
+                        Placement::Evicted => {
+                            loaded_this_tick += 1;
+                            unloaded_this_tick += 1;
+                        }
+                        Placement::NoRoom => {}
+                    }
+                }
+                None => {
+                    self.requests.insert(
+                        coord,
+                        Request::InFlight {
+                            since_tick: self.tick_count,
+                        },
+                    );
+                    self.source.request(coord);
+                }
             }
+            spent += 1;
         }
 
-        let pending_count = visible_pending.saturating_sub(loaded_this_tick);
+        let (loaded, evicted) = self.drain_source(queue);
+        loaded_this_tick += loaded;
+        unloaded_this_tick += evicted;
+
+        let pending_count = self
+            .kept
+            .iter()
+            .filter(|c| !self.loaded.contains_key(c))
+            .count() as u32;
         let total_loaded = self.loaded.len() as u32;
         let total_visible = self.visible.len() as u32;
-        let cached_count = total_loaded.saturating_sub(total_visible);
+        let cached_count = self.cached_count() as u32;
         let streaming_state = StreamingState::from_counts(pending_count, loaded_this_tick);
 
         TickResult {
-            grid_info: self.compute_grid_info(),
+            grid_info,
             stats: TickStats {
                 loaded_this_tick,
                 unloaded_this_tick,
@@ -385,12 +1072,134 @@ impl ChunkManager {
                 total_loaded,
                 total_visible,
                 cached_count,
+                in_flight_count: self.in_flight_count() as u32,
                 budget,
                 streaming_state,
             },
         }
     }
 
+    /// Focus for this tick, leading `camera_pos` by the distance covered in
+    /// [`LEAD_TICKS`] at the speed since the last tick, capped at the view
+    /// distance so teleports don't pull loading away.
+    #[allow(clippy::cast_precision_loss)]
+    fn load_focus(&mut self, camera_pos: Vec3) -> LoadFocus {
+        let velocity = self.last_eye.map_or(Vec3::ZERO, |last| camera_pos - last);
+        self.last_eye = Some(camera_pos);
+        let max_lead = (self.volume.horizontal * CHUNK_SIZE as u32) as f32;
+        LoadFocus {
+            eye: camera_pos,
+            lead: (velocity * LEAD_TICKS).clamp_length_max(max_lead),
+            view: self.view,
+        }
+    }
+
+    /// Every chunk this tick keeps loaded, with its load priority (lower
+    /// loads first): visible chunks ranked by `focus`, chunks around an
+    /// interest point by distance to the point scaled by its weight, and
+    /// pinned chunks by distance to their region's center. A chunk wanted
+    /// more than once takes its best priority.
+    ///
+    /// Visible chunks are always wanted; the rest are added best priority
+    /// first until the atlas is full, so the set never outgrows it.
+    fn wanted_chunks(&self, focus: &LoadFocus) -> HashMap<IVec3, f32> {
+        let mut wanted: HashMap<IVec3, f32> = self
+            .visible
+            .iter()
+            .map(|&coord| (coord, focus.priority(coord, self.likely_sky(coord))))
+            .collect();
+        let mut extra = Vec::new();
+        let mut want = |coord: IVec3, priority: f32| extra.push((coord, priority));
+        let surface = self.surface_height.as_deref();
+        for point in self.interest_points.values() {
+            let around = LoadFocus {
+                eye: point.position,
+                lead: Vec3::ZERO,
+                view: None,
+            };
+            let center = pos_to_chunk_coord(point.position);
+            for coord in point.volume.chunks_around(center, surface) {
+                want(
+                    coord,
+                    around.priority(coord, self.likely_sky(coord)) * point.weight,
+                );
+            }
+        }
+        for region in self.pinned.values() {
+            let around = LoadFocus {
+                eye: region.center(),
+                lead: Vec3::ZERO,
+                view: None,
+            };
+            for coord in region.chunks() {
+                want(coord, around.priority(coord, false));
+            }
+        }
+        extra.sort_by(|a, b| {
+            a.1.total_cmp(&b.1)
+                .then((a.0.z, a.0.y, a.0.x).cmp(&(b.0.z, b.0.y, b.0.x)))
+        });
+        let slots = self.atlas.total_slots() as usize;
+        for (coord, priority) in extra {
+            if let Some(p) = wanted.get_mut(&coord) {
+                *p = p.min(priority);
+            } else if wanted.len() < slots {
+                wanted.insert(coord, priority);
+            }
+        }
+        wanted
+    }
+
+    /// Whether the chunk at `coord` is probably all air: the chunk below it
+    /// is loaded and empty, so the column has already topped out.
+    fn likely_sky(&self, coord: IVec3) -> bool {
+        self.loaded
+            .get(&(coord - IVec3::Y))
+            .is_some_and(|below| below.chunk.is_empty())
+    }
+
+    /// Insert every chunk the source has answered. Failed answers move to
+    /// [`ChunkState::Failed`] for local generation on a later tick. Returns
+    /// the number of chunks loaded and of chunks evicted to make room.
+    fn drain_source(&mut self, queue: &wgpu::Queue) -> (u32, u32) {
+        let mut loaded = 0;
+        let mut evicted = 0;
+        for completed in self.source.poll() {
+            let coord = completed.coord;
+            // Answers to dropped or timed-out requests are stale.
+            if !matches!(self.requests.get(&coord), Some(Request::InFlight { .. })) {
+                continue;
+            }
+            let inserted = match completed.result {
+                Ok(SourcedChunk::Voxels(mut chunk)) => {
+                    self.edits.apply(coord, &mut chunk);
+                    Ok(self.insert_chunk(queue, coord, chunk, None))
+                }
+                Ok(SourcedChunk::Extracted(decoded)) if decoded.coord == coord => {
+                    Ok(self.insert_decoded(queue, *decoded))
+                }
+                Ok(SourcedChunk::Payload(payload)) if payload.coord() == coord => self
+                    .insert_payload(queue, &payload)
+                    .map_err(|e| e.to_string()),
+                Ok(_) => Err("answer carries a different chunk".to_owned()),
+                Err(e) => Err(e.to_string()),
+            };
+            match inserted {
+                Ok(Placement::Placed) => loaded += 1,
+                Ok(Placement::Evicted) => {
+                    loaded += 1;
+                    evicted += 1;
+                }
+                Ok(Placement::NoRoom) => {}
+                Err(e) => {
+                    log::warn!("chunk {coord}: {e}; falling back to local generation");
+                    self.requests.insert(coord, Request::Failed);
+                }
+            }
+        }
+        (loaded, evicted)
+    }
+
     /// Compute the [`GridInfo`](crate::camera::GridInfo) bounding box from
     /// the currently visible chunk set.
     #[allow(clippy::cast_precision_loss)]
@@ -428,6 +1237,8 @@ impl ChunkManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::chunk_source::DelayedSource;
+    use crate::map_features::MapConfig;
     use crate::render::gpu::GpuContext;
 
     fn make_manager(seed: u32, view_distance: u32) -> (GpuContext, ChunkManager) {
@@ -437,6 +1248,14 @@ mod tests {
         (gpu, mgr)
     }
 
+    /// A vd=1 manager whose 32-slot atlas fits the 27 visible chunks and
+    /// only 5 cached ones.
+    fn make_small_manager() -> (GpuContext, ChunkManager) {
+        let gpu = pollster::block_on(GpuContext::new_headless()).expect("GPU init");
+        let mgr = ChunkManager::new(&gpu.device, 42, 1, UVec3::new(4, 4, 2));
+        (gpu, mgr)
+    }
+
     #[test]
     fn new_manager_has_no_loaded_chunks() {
         let (_gpu, mgr) = make_manager(42, 3);
@@ -464,12 +1283,30 @@ mod tests {
 
     #[test]
     fn empty_chunks_not_uploaded() {
+        use crate::voxel::MAT_STONE;
         let (gpu, mut mgr) = make_manager(42, 3);
         // Chunk at high Y should be all air
         let coord = IVec3::new(0, 10, 0);
         mgr.load_chunk(&gpu.queue, coord);
-        // Still tracked as loaded (we know about it) but marked as empty
+        // Still tracked as loaded (we know about it) but holds no atlas slot
         assert!(mgr.is_loaded(coord));
+        assert!(mgr.loaded[&coord].slot.is_none());
+        assert_eq!(
+            mgr.slot_alloc.free_count(),
+            mgr.atlas().total_slots() as usize
+        );
+
+        // Filling it in gives it a slot; emptying it again frees the slot.
+        let base = coord * 32;
+        mgr.mutate_voxel(&gpu.queue, base, MAT_STONE);
+        assert!(mgr.loaded[&coord].slot.is_some());
+        assert!(mgr.is_solid(base.as_vec3() + 0.5));
+        mgr.mutate_voxel(&gpu.queue, base, 0);
+        assert!(mgr.loaded[&coord].slot.is_none());
+        assert_eq!(
+            mgr.slot_alloc.free_count(),
+            mgr.atlas().total_slots() as usize
+        );
     }
 
     #[test]
@@ -539,10 +1376,10 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "must be >= 2*view_distance+1")]
+    #[should_panic(expected = "must hold the 343 visible chunks")]
     fn new_panics_on_undersized_atlas() {
         let gpu = pollster::block_on(GpuContext::new_headless()).expect("GPU init");
-        // vd=3 needs at least 7 per axis; (8, 4, 8) is too small on Y
+        // vd=3 sees 7^3 = 343 chunks; (8, 4, 8) holds only 256
         let _mgr = ChunkManager::new(&gpu.device, 42, 3, UVec3::new(8, 4, 8));
     }
 
@@ -608,19 +1445,63 @@ mod tests {
     }
 
     #[test]
-    fn slot_collision_evicts_occupant() {
-        let (gpu, mut mgr) = make_manager(42, 1);
-        // atlas_slots = 8x8x8. Chunks at x=0 and x=8 map to the same slot.
-        let coord_a = IVec3::new(0, 0, 0);
-        let coord_b = IVec3::new(8, 0, 0);
-        mgr.load_chunk(&gpu.queue, coord_a);
-        assert!(mgr.is_loaded(coord_a));
-        mgr.load_chunk(&gpu.queue, coord_b);
-        assert!(mgr.is_loaded(coord_b));
-        assert!(
-            !mgr.is_loaded(coord_a),
-            "coord_a should be evicted by slot collision"
+    fn full_atlas_evicts_least_recently_visible_chunks() {
+        let (gpu, mut mgr) = make_small_manager();
+        let at = |cx: f32| Vec3::new(16.0 + cx * 32.0, 16.0, 16.0);
+        let view = |cx: f32| ChunkManager::compute_visible_set(at(cx), 1);
+        mgr.tick(&gpu.queue, at(0.0));
+        mgr.tick(&gpu.queue, at(3.0));
+        mgr.tick(&gpu.queue, at(6.0));
+        assert_eq!(mgr.loaded_count(), 32);
+        // The five leftovers of the oldest view go before the newer view.
+        assert!(view(0.0).iter().all(|c| !mgr.is_loaded(*c)));
+        assert_eq!(view(3.0).iter().filter(|c| mgr.is_loaded(**c)).count(), 5);
+        assert!(view(6.0).iter().all(|c| mgr.is_loaded(*c)));
+    }
+
+    #[test]
+    fn visible_chunks_are_never_evicted() {
+        let (gpu, mut mgr) = make_small_manager();
+        mgr.tick(&gpu.queue, Vec3::new(16.0, 16.0, 16.0));
+        let far = |x| IVec3::new(x, 50, 0);
+        for x in 0..6 {
+            mgr.load_chunk(&gpu.queue, far(x));
+        }
+        assert_eq!(mgr.loaded_count(), 32);
+        assert!(!mgr.is_loaded(far(0)), "oldest cached chunk makes room");
+        assert!(mgr.is_loaded(far(5)));
+        let view = ChunkManager::compute_visible_set(Vec3::new(16.0, 16.0, 16.0), 1);
+        assert!(view.iter().all(|c| mgr.is_loaded(*c)));
+    }
+
+    #[test]
+    fn wide_banded_volume_streams_only_around_the_surface() {
+        use crate::view_volume::{SurfaceBand, ViewShape};
+        let gpu = pollster::block_on(GpuContext::new_headless()).expect("GPU init");
+        let volume = ViewVolume {
+            horizontal: 3,
+            vertical: 3,
+            shape: ViewShape::Cylinder,
+            surface_band: Some(SurfaceBand { below: 1, above: 0 }),
+        };
+        // 29 columns of at most 5 chunks fit an atlas smaller than the 7^3
+        // cube.
+        let mut mgr = ChunkManager::with_view_volume(
+            &gpu.device,
+            volume,
+            UVec3::new(6, 6, 6),
+            Box::new(|_| Chunk::default()),
         );
+        mgr.set_surface_height(Some(Box::new(|_, _| 40)));
+        // Band y=0..=1 plus the camera's layers y=0..=2.
+        mgr.tick_budgeted(&gpu.queue, Vec3::new(16.0, 48.0, 16.0), 128);
+        assert_eq!(mgr.loaded_count(), 29 * 3);
+        assert!(mgr.is_loaded(IVec3::new(3, 1, 0)));
+        assert!(mgr.is_loaded(IVec3::new(0, 0, -3)));
+        assert!(mgr.is_loaded(IVec3::new(0, 2, 0)));
+        assert!(!mgr.is_loaded(IVec3::new(0, 3, 0)));
+        assert!(!mgr.is_loaded(IVec3::new(0, -1, 0)));
+        assert!(!mgr.is_loaded(IVec3::new(3, 1, 3)));
     }
 
     #[test]
@@ -654,6 +1535,156 @@ mod tests {
         assert_eq!(result.stats.loaded_this_tick, 1);
     }
 
+    #[test]
+    fn tick_loads_on_screen_chunks_first() {
+        let (gpu, mut mgr) = make_manager(42, 1);
+        let camera = crate::camera::Camera {
+            position: Vec3::splat(16.0),
+            yaw: 0.0,
+            pitch: 0.0,
+            fov: 40.0_f32.to_radians(),
+        };
+        mgr.set_view(Some(LoadView::perspective(&camera, 1.0)));
+        mgr.tick_budgeted(&gpu.queue, camera.position, 4);
+        // Looking down -Z: the chunk ahead loads before equally close ones
+        // behind and beside.
+        assert!(mgr.is_loaded(IVec3::ZERO));
+        assert!(mgr.is_loaded(IVec3::NEG_Z));
+        assert!(!mgr.is_loaded(IVec3::Z));
+        assert!(!mgr.is_loaded(IVec3::X));
+        assert!(!mgr.is_loaded(IVec3::NEG_X));
+    }
+
+    #[test]
+    fn tick_defers_chunks_above_empty_ones() {
+        use crate::voxel::MAT_STONE;
+        let gpu = pollster::block_on(GpuContext::new_headless()).expect("GPU init");
+        let mut mgr = ChunkManager::with_chunk_gen(
+            &gpu.device,
+            1,
+            UVec3::splat(4),
+            Box::new(|coord| {
+                if coord.y < 0 {
+                    Chunk::uniform(pack_voxel(MAT_STONE, 0, 0, 0))
+                } else {
+                    Chunk::default()
+                }
+            }),
+        );
+        let cam = Vec3::splat(16.0);
+        mgr.tick_budgeted(&gpu.queue, cam, 1);
+        mgr.tick_budgeted(&gpu.queue, cam, 5);
+        // (0,0,0) is air, so (0,1,0) waits behind the other face neighbors.
+        assert!(mgr.is_loaded(IVec3::Z));
+        assert!(!mgr.is_loaded(IVec3::Y));
+    }
+
+    #[test]
+    fn tick_leads_loading_in_the_direction_of_travel() {
+        let (gpu, mut mgr) = make_manager(42, 1);
+        mgr.tick_budgeted(&gpu.queue, Vec3::splat(16.0), 0);
+        mgr.tick_budgeted(&gpu.queue, Vec3::new(24.0, 16.0, 16.0), 1);
+        assert!(mgr.is_loaded(IVec3::X));
+        assert!(!mgr.is_loaded(IVec3::ZERO));
+    }
+
+    #[test]
+    fn interest_points_share_the_tick_budget() {
+        let (gpu, mut mgr) = make_manager(42, 1);
+        let cam = Vec3::splat(16.0);
+        mgr.tick(&gpu.queue, cam);
+        let point = |x: f32, weight| InterestPoint {
+            position: Vec3::new(x, 16.0, 16.0),
+            volume: ViewVolume::cube(0),
+            weight,
+        };
+        // Both 12 units from the center of their chunk, (10,0,0) and (-10,0,0).
+        assert!(mgr.set_interest_point("near", point(324.0, 1.0)));
+        assert!(mgr.set_interest_point("deferred", point(-316.0, 4.0)));
+        let result = mgr.tick_budgeted(&gpu.queue, cam, 1);
+        assert_eq!(result.stats.loaded_this_tick, 1);
+        assert_eq!(result.stats.pending_count, 1);
+        assert!(mgr.is_loaded(IVec3::new(10, 0, 0)));
+        mgr.tick_budgeted(&gpu.queue, cam, 1);
+        assert!(mgr.is_loaded(IVec3::new(-10, 0, 0)));
+
+        assert!(mgr.remove_interest_point("near"));
+        assert!(!mgr.remove_interest_point("near"));
+        mgr.tick(&gpu.queue, cam);
+        assert_eq!(
+            mgr.cached_count(),
+            1,
+            "the removed point's chunk stays cached"
+        );
+    }
+
+    #[test]
+    fn pinned_regions_load_within_budget_and_are_never_evicted() {
+        let (gpu, mut mgr) = make_small_manager();
+        let cam = Vec3::splat(16.0);
+        mgr.tick(&gpu.queue, cam);
+        let region = PinnedRegion::new(IVec3::new(0, 40, 0), IVec3::new(1, 40, 0));
+        assert!(mgr.pin_region("arena", region));
+        assert!(mgr.is_pinned(IVec3::new(1, 40, 0)));
+        let result = mgr.tick_budgeted(&gpu.queue, cam, 1);
+        assert_eq!(result.stats.loaded_this_tick, 1);
+        mgr.tick_budgeted(&gpu.queue, cam, 1);
+        assert!(region.chunks().all(|c| mgr.is_loaded(c)));
+
+        // 27 visible + 2 pinned leave 3 slots for the far chunks to cycle through.
+        for x in 0..6 {
+            mgr.load_chunk(&gpu.queue, IVec3::new(x, -40, 0));
+        }
+        assert!(region.chunks().all(|c| mgr.is_loaded(c)));
+
+        assert!(mgr.unpin_region("arena"));
+        mgr.tick(&gpu.queue, cam);
+        // Five fresh chunks push out all five cached ones.
+        for x in 6..11 {
+            mgr.load_chunk(&gpu.queue, IVec3::new(x, -40, 0));
+        }
+        assert!(!region.chunks().any(|c| mgr.is_loaded(c)));
+    }
+
+    #[test]
+    fn protected_chunks_never_outgrow_the_atlas() {
+        let (gpu, mut mgr) = make_small_manager();
+        let huge = PinnedRegion::new(IVec3::splat(-100), IVec3::splat(100));
+        assert!(!mgr.pin_region("huge", huge));
+        assert!(!mgr.is_pinned(IVec3::ZERO));
+        let far = InterestPoint {
+            position: Vec3::splat(1.0e6),
+            volume: ViewVolume::cube(u32::MAX),
+            weight: 1.0,
+        };
+        assert!(!mgr.set_interest_point("far", far));
+
+        // Each region fits alone, but together they want 16 of the 5 slots
+        // the 27 visible chunks leave over.
+        let row = |x: i32| PinnedRegion::new(IVec3::new(x, 40, 0), IVec3::new(x + 7, 40, 0));
+        assert!(mgr.pin_region("east", row(20)));
+        assert!(mgr.pin_region("west", row(-27)));
+        for cam_x in [16.0, -48.0, 80.0] {
+            let cam = Vec3::new(cam_x, 16.0, 16.0);
+            mgr.tick(&gpu.queue, cam);
+            let center = pos_to_chunk_coord(cam);
+            let volume = ViewVolume::cube(1);
+            assert!(
+                volume
+                    .chunks_around(center, None)
+                    .iter()
+                    .all(|&c| mgr.is_loaded(c)),
+                "camera at {cam_x} must get its whole view"
+            );
+            let pinned = [row(20), row(-27)]
+                .iter()
+                .flat_map(PinnedRegion::chunks)
+                .filter(|&c| mgr.is_loaded(c))
+                .count();
+            assert_eq!(pinned, 5);
+        }
+    }
+
     #[test]
     fn tick_budget_exhaustion_reaches_idle() {
         let (gpu, mut mgr) = make_manager(42, 1);
@@ -693,14 +1724,13 @@ mod tests {
 
     #[test]
     fn tick_eviction_counted_in_stats() {
-        let (gpu, mut mgr) = make_manager(42, 1);
+        let (gpu, mut mgr) = make_small_manager();
         let cam_pos = Vec3::new(16.0, 16.0, 16.0);
         // Fill with all visible chunks (no budget limit — use large budget).
         mgr.tick_budgeted(&gpu.queue, cam_pos, 100);
-        // Now move camera so some new chunks collide with cached slots.
+        // 27 new chunks, 5 free slots: the rest evict cached chunks.
         let result = mgr.tick_budgeted(&gpu.queue, Vec3::new(16.0 + 8.0 * 32.0, 16.0, 16.0), 100);
-        // Atlas is 8x8x8. Moving 8 chunks on x wraps modular slots. Some evictions.
-        assert!(result.stats.unloaded_this_tick > 0);
+        assert_eq!(result.stats.unloaded_this_tick, 22);
     }
 
     #[test]
@@ -712,6 +1742,38 @@ mod tests {
         assert!(grid.unwrap().surface_count() > 0);
     }
 
+    #[test]
+    fn loaded_chunks_stay_palette_compressed() {
+        let gpu = pollster::block_on(GpuContext::new_headless()).expect("GPU init");
+        let config = MapConfig::default();
+        let mut mgr = ChunkManager::with_chunk_gen(
+            &gpu.device,
+            1,
+            UVec3::new(8, 8, 8),
+            Box::new(move |coord| config.generate_chunk(coord)),
+        );
+        let eye = Vec3::new(80.0, 16.0, 80.0);
+        mgr.tick_budgeted(&gpu.queue, eye, 64);
+        let raw = mgr.loaded_count() * CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE * 4;
+        assert!(mgr.voxel_memory_bytes() * 4 < raw);
+
+        // Scatter more distinct values than byte-wide indices can address,
+        // then put plain stone back.
+        let points: Vec<IVec3> = (0..300)
+            .map(|i| IVec3::new(64 + i % 32, 8 + i / 32, 64))
+            .collect();
+        let stone =
+            |shade: usize| pack_voxel(crate::voxel::MAT_STONE, shade as u8, (shade / 256) as u8, 0);
+        mgr.apply_writes(
+            &gpu.queue,
+            points.iter().enumerate().map(|(i, &p)| (p, stone(i))),
+            false,
+        );
+        mgr.apply_writes(&gpu.queue, points.iter().map(|&p| (p, stone(0))), false);
+        let chunk = &mgr.loaded[&IVec3::new(2, 0, 2)].chunk;
+        assert!(chunk.storage().bits_per_voxel() <= 8);
+    }
+
     #[test]
     fn unloaded_chunk_has_no_terrain_grid() {
         let (_gpu, mgr) = make_manager(42, 1);
@@ -732,7 +1794,7 @@ mod tests {
                     pack_voxel(crate::voxel::MAT_STONE, 0, 0, 0);
                     CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE
                 ];
-                Chunk { voxels }
+                Chunk::from_voxels(&voxels)
             }),
         );
         mgr.load_chunk(&gpu.queue, IVec3::ZERO);
@@ -747,6 +1809,247 @@ mod tests {
         );
     }
 
+    #[test]
+    fn ingest_payload_uses_precomputed_data_unless_edited() {
+        use crate::voxel::MAT_STONE;
+        let (gpu, mut mgr) = make_manager(42, 1);
+        let stone = Chunk::uniform(pack_voxel(MAT_STONE, 0, 0, 0));
+        let mut payload =
+            ChunkPayload::from_chunk(IVec3::ZERO, &stone, &MaterialRegistry::default());
+        // Doctor the derived fields so using them is observable.
+        payload.occupancy = 0b1010;
+        payload.collision = vec![0; payload.collision.len()];
+        payload.terrain_grid =
+            TerrainGrid::from_chunk(&Chunk::default(), &mgr.materials).to_bytes();
+
+        mgr.ingest_payload(&gpu.queue, &payload)
+            .expect("valid payload");
+        let slot = mgr.loaded[&IVec3::ZERO]
+            .slot
+            .expect("solid chunk has a slot");
+        assert_eq!(mgr.atlas().occupancy_masks()[slot as usize], 0b1010);
+        assert!(!mgr.is_solid(Vec3::new(1.5, 1.5, 1.5)));
+        assert_eq!(mgr.terrain_grid(IVec3::ZERO).unwrap().surface_count(), 0);
+
+        // Once the chunk carries edits the payload's derived data is stale.
+        mgr.mutate_voxel(&gpu.queue, IVec3::new(0, 31, 0), 0);
+        mgr.unload_chunk(&gpu.queue, IVec3::ZERO);
+        mgr.ingest_payload(&gpu.queue, &payload)
+            .expect("valid payload");
+        assert!(mgr.is_solid(Vec3::new(1.5, 1.5, 1.5)));
+        assert!(!mgr.is_solid(Vec3::new(0.5, 31.5, 0.5)));
+        let slot = mgr.loaded[&IVec3::ZERO]
+            .slot
+            .expect("solid chunk has a slot");
+        assert_eq!(mgr.atlas().occupancy_masks()[slot as usize], u64::MAX);
+
+        payload.collision.pop();
+        mgr.unload_chunk(&gpu.queue, IVec3::ZERO);
+        assert!(mgr.ingest_payload(&gpu.queue, &payload).is_err());
+        assert!(!mgr.is_loaded(IVec3::ZERO));
+    }
+
+    #[test]
+    fn payloads_are_reextracted_after_a_material_is_defined() {
+        use crate::voxel::MAT_STONE;
+        let (gpu, mut mgr) = make_manager(42, 1);
+        let stone = Chunk::uniform(pack_voxel(MAT_STONE, 0, 0, 0));
+        // Extracted server-side with the default table, where stone is solid.
+        let payload = ChunkPayload::from_chunk(IVec3::ZERO, &stone, &MaterialRegistry::default());
+        let mut ghost = mgr.materials().get(MAT_STONE).expect("stone").clone();
+        ghost.solid = false;
+        assert!(mgr.define_material(&gpu.queue, MAT_STONE, ghost));
+
+        mgr.ingest_payload(&gpu.queue, &payload)
+            .expect("valid payload");
+        assert!(!mgr.is_solid(Vec3::new(1.5, 1.5, 1.5)));
+    }
+
+    #[test]
+    fn door_flags_toggle_collision_and_indestructible_resists_mutation() {
+        use crate::voxel::{FLAG_DOOR, FLAG_OPEN, MAT_STONE};
+        let gpu = pollster::block_on(GpuContext::new_headless()).expect("GPU init");
+        let mut mgr = ChunkManager::with_chunk_gen(
+            &gpu.device,
+            3,
+            UVec3::splat(7),
+            Box::new(|_| Chunk::uniform(pack_voxel(MAT_STONE, 0, 0, 0))),
+        );
+        mgr.load_chunk(&gpu.queue, IVec3::ZERO);
+        let door = IVec3::new(4, 4, 4);
+        let center = Vec3::new(4.5, 4.5, 4.5);
+
+        mgr.set_voxel_flags(&gpu.queue, &[(door, FLAG_DOOR)]);
+        assert!(mgr.is_solid(center), "closed door blocks");
+        let changed = mgr.set_voxel_flags(&gpu.queue, &[(door, FLAG_DOOR | FLAG_OPEN)]);
+        assert_eq!(changed, vec![IVec3::ZERO]);
+        assert!(!mgr.is_solid(center), "open door is passable");
+        assert_eq!(
+            material_id(mgr.voxel_at(door).unwrap()),
+            MAT_STONE,
+            "flags must not change the material"
+        );
+
+        mgr.set_voxel_flags(&gpu.queue, &[(door, FLAG_INDESTRUCTIBLE)]);
+        assert!(mgr.mutate_voxels(&gpu.queue, &[(door, 0)]).is_empty());
+        assert!(
+            mgr.is_solid(center),
+            "indestructible voxel survives mutation"
+        );
+
+        // Rewriting its own material must not strip the flag either.
+        assert!(
+            mgr.mutate_voxels(&gpu.queue, &[(door, MAT_STONE)])
+                .is_empty()
+        );
+        assert_eq!(flags(mgr.voxel_at(door).unwrap()), FLAG_INDESTRUCTIBLE);
+        assert!(mgr.mutate_voxels(&gpu.queue, &[(door, 0)]).is_empty());
+        assert!(mgr.is_solid(center), "a second write cannot destroy it");
+    }
+
+    #[test]
+    fn mutation_survives_eviction_and_reload() {
+        let (gpu, mut mgr) = make_small_manager();
+        let coord = IVec3::ZERO;
+        mgr.load_chunk(&gpu.queue, coord);
+        let pos = Vec3::new(16.5, 0.5, 16.5);
+        assert!(mgr.is_solid(pos), "precondition: voxel must be solid");
+        mgr.mutate_voxel(&gpu.queue, IVec3::new(16, 0, 16), 0);
+        assert_eq!(mgr.edited_chunk_count(), 1);
+
+        // Filling the 32-slot atlas evicts the oldest chunk.
+        for x in 1..=32 {
+            mgr.load_chunk(&gpu.queue, IVec3::new(x, 0, 0));
+        }
+        assert!(!mgr.is_loaded(coord));
+
+        mgr.load_chunk(&gpu.queue, coord);
+        assert!(
+            !mgr.is_solid(pos),
+            "edit must be reapplied after regeneration"
+        );
+    }
+
+    #[test]
+    fn mutate_region_reports_each_touched_chunk_once() {
+        let (gpu, mut mgr) = make_manager(42, 1);
+        mgr.load_chunk(&gpu.queue, IVec3::ZERO);
+        mgr.load_chunk(&gpu.queue, IVec3::X);
+        // Box straddles the x=32 chunk boundary at the bottom of the world.
+        let region = VoxelRegion::Box {
+            min: IVec3::new(30, 0, 0),
+            max: IVec3::new(33, 1, 1),
+        };
+        let changed = mgr.mutate_region(&gpu.queue, &region, 0).unwrap();
+        assert_eq!(changed, vec![IVec3::ZERO, IVec3::X]);
+        assert!(!mgr.is_solid(Vec3::new(31.5, 0.5, 0.5)));
+        assert!(!mgr.is_solid(Vec3::new(32.5, 0.5, 0.5)));
+    }
+
+    #[test]
+    fn mutate_voxels_skips_unchanged_and_unloaded() {
+        let (gpu, mut mgr) = make_manager(42, 1);
+        mgr.load_chunk(&gpu.queue, IVec3::Y);
+        // Terrain never reaches y=63, so that voxel is already air; chunk
+        // x=5 is not loaded.
+        let changed = mgr.mutate_voxels(
+            &gpu.queue,
+            &[(IVec3::new(0, 63, 0), 0), (IVec3::new(5 * 32, 0, 0), 0)],
+        );
+        assert!(changed.is_empty());
+        assert_eq!(mgr.edited_chunk_count(), 0);
+    }
+
+    #[test]
+    fn incremental_terrain_matches_full_rebuild() {
+        let (gpu, mut mgr) = make_manager(42, 1);
+        mgr.load_chunk(&gpu.queue, IVec3::ZERO);
+        let region = VoxelRegion::Line {
+            from: IVec3::new(3, 2, 3),
+            to: IVec3::new(20, 30, 9),
+        };
+        mgr.mutate_region(&gpu.queue, &region, 0).unwrap();
+
+        let mut expected = Chunk::new_terrain_at(42, IVec3::ZERO);
+        for p in region.voxels().unwrap() {
+            let (_, (lx, ly, lz)) = world_ivec_to_chunk(p);
+            expected.set_voxel(lx, ly, lz, 0);
+        }
+        assert_eq!(
+            mgr.terrain_grid(IVec3::ZERO).unwrap().to_bytes(),
+            TerrainGrid::from_chunk(&expected, &MaterialRegistry::default()).to_bytes()
+        );
+    }
+
+    #[test]
+    fn loading_chunk_above_stitches_seam() {
+        let gpu = pollster::block_on(GpuContext::new_headless()).expect("GPU init");
+        // Solid below y=32, solid slab at world y=32..=33 in the layer above.
+        let mut mgr = ChunkManager::with_chunk_gen(
+            &gpu.device,
+            1,
+            UVec3::splat(8),
+            Box::new(|coord| {
+                let mut chunk = Chunk::default();
+                let solid_to = if coord.y == 0 { CHUNK_SIZE } else { 2 };
+                for z in 0..CHUNK_SIZE {
+                    for y in 0..solid_to {
+                        for x in 0..CHUNK_SIZE {
+                            chunk.set_voxel(x, y, z, pack_voxel(crate::voxel::MAT_STONE, 0, 0, 0));
+                        }
+                    }
+                }
+                chunk
+            }),
+        );
+        mgr.load_chunk(&gpu.queue, IVec3::ZERO);
+        assert_eq!(mgr.surfaces_at_world(3, 3).len(), 1);
+        assert!(mgr.take_dirty_terrain().is_empty());
+
+        mgr.load_chunk(&gpu.queue, IVec3::Y);
+        assert_eq!(mgr.take_dirty_terrain(), vec![IVec3::ZERO]);
+        let surfaces = mgr.surfaces_at_world(3, 3);
+        assert_eq!(surfaces.len(), 1, "covered y=31 surface must be dropped");
+        assert_eq!(surfaces[0].y, 33);
+
+        // Digging through the slab's bottom re-exposes the seam surface.
+        mgr.mutate_voxel(&gpu.queue, IVec3::new(3, 32, 3), 0);
+        assert_eq!(mgr.take_dirty_terrain(), vec![IVec3::ZERO]);
+        let ys: Vec<i32> = mgr.surfaces_at_world(3, 3).iter().map(|s| s.y).collect();
+        assert_eq!(ys, vec![31, 33]);
+        assert_eq!(mgr.surfaces_at_world(3, 3)[0].surface.headroom, 1);
+
+        // Once the chunk above leaves, the seam is unknown again.
+        mgr.mutate_voxel(&gpu.queue, IVec3::new(3, 32, 3), crate::voxel::MAT_STONE);
+        mgr.take_dirty_terrain();
+        mgr.unload_chunk(&gpu.queue, IVec3::Y);
+        assert_eq!(mgr.take_dirty_terrain(), vec![IVec3::ZERO]);
+        assert_eq!(mgr.surfaces_at_world(3, 3).len(), 1);
+        assert_eq!(mgr.surfaces_at_world(3, 3)[0].y, 31);
+    }
+
+    #[test]
+    fn evicting_chunk_above_unstitches_seam() {
+        let gpu = pollster::block_on(GpuContext::new_headless()).expect("GPU init");
+        let mut mgr = ChunkManager::with_chunk_gen(
+            &gpu.device,
+            0,
+            UVec3::new(2, 1, 1),
+            Box::new(|_| Chunk::uniform(pack_voxel(crate::voxel::MAT_STONE, 0, 0, 0))),
+        );
+        mgr.load_chunk(&gpu.queue, IVec3::ZERO);
+        mgr.load_chunk(&gpu.queue, IVec3::Y);
+        assert!(mgr.surfaces_at_world(3, 3).iter().all(|s| s.y != 31));
+        mgr.take_dirty_terrain();
+
+        // Two slots: loading a third chunk evicts the older of the two.
+        mgr.tick(&gpu.queue, Vec3::splat(16.0));
+        mgr.load_chunk(&gpu.queue, IVec3::new(5, 1, 0));
+        assert!(!mgr.is_loaded(IVec3::Y));
+        assert_eq!(mgr.take_dirty_terrain(), vec![IVec3::ZERO]);
+        assert!(mgr.surfaces_at_world(3, 3).iter().any(|s| s.y == 31));
+    }
+
     #[test]
     fn custom_chunk_generator_is_used() {
         let gpu = pollster::block_on(GpuContext::new_headless()).expect("GPU init");
@@ -761,11 +2064,92 @@ mod tests {
                 for v in &mut voxels[..CHUNK_SIZE * CHUNK_SIZE] {
                     *v = crate::voxel::pack_voxel(crate::voxel::MAT_STONE, 0, 0, 0);
                 }
-                Chunk { voxels }
+                Chunk::from_voxels(&voxels)
             }),
         );
         mgr.load_chunk(&gpu.queue, IVec3::ZERO);
         // The chunk should be loaded and solid at y=0 (stone).
         assert!(mgr.is_solid(Vec3::new(0.5, 0.5, 0.5)));
     }
+
+    fn delayed_source(delay: u32) -> DelayedSource<LocalSource> {
+        let seed = 42;
+        DelayedSource::new(
+            LocalSource::new(Arc::new(move |coord| Chunk::new_terrain_at(seed, coord))),
+            delay,
+        )
+    }
+
+    #[test]
+    fn slow_source_moves_chunks_through_requested_to_ready() {
+        let (gpu, mut mgr) = make_manager(42, 0);
+        mgr.set_source(Some(Box::new(delayed_source(2))));
+        let cam = Vec3::new(16.0, 16.0, 16.0);
+
+        let stats = mgr.tick_budgeted(&gpu.queue, cam, 4).stats;
+        assert_eq!(mgr.chunk_state(IVec3::ZERO), ChunkState::Requested);
+        assert_eq!(stats.in_flight_count, 1);
+        assert_eq!(stats.loaded_this_tick, 0);
+
+        // Still in flight: no duplicate request, nothing loaded.
+        let stats = mgr.tick_budgeted(&gpu.queue, cam, 4).stats;
+        assert_eq!(stats.in_flight_count, 1);
+
+        let stats = mgr.tick_budgeted(&gpu.queue, cam, 4).stats;
+        assert_eq!(mgr.chunk_state(IVec3::ZERO), ChunkState::Ready);
+        assert_eq!(stats.in_flight_count, 0);
+        assert_eq!(stats.loaded_this_tick, 1);
+    }
+
+    #[test]
+    fn worker_pool_loads_same_chunks_as_local_generation() {
+        let cam = Vec3::new(16.0, 16.0, 16.0);
+        let (gpu, mut local) = make_manager(42, 1);
+        let (_, mut pooled) = make_manager(42, 1);
+        pooled.use_worker_pool(std::num::NonZeroUsize::new(4).unwrap());
+
+        for _ in 0..3 {
+            let a = local.tick_budgeted(&gpu.queue, cam, 5).stats;
+            let b = pooled.tick_budgeted(&gpu.queue, cam, 5).stats;
+            assert_eq!(a.loaded_this_tick, b.loaded_this_tick);
+            assert_eq!(b.in_flight_count, 0);
+            for coord in ChunkManager::compute_visible_set(cam, 1) {
+                assert_eq!(local.is_loaded(coord), pooled.is_loaded(coord));
+                assert_eq!(
+                    local.terrain_grid(coord).map(TerrainGrid::to_bytes),
+                    pooled.terrain_grid(coord).map(TerrainGrid::to_bytes)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn failed_request_falls_back_to_local_generation() {
+        let (gpu, mut mgr) = make_manager(42, 0);
+        mgr.set_source(Some(Box::new(delayed_source(0).failing(IVec3::ZERO))));
+        let cam = Vec3::new(16.0, 16.0, 16.0);
+
+        mgr.tick_budgeted(&gpu.queue, cam, 4);
+        assert_eq!(mgr.chunk_state(IVec3::ZERO), ChunkState::Failed);
+        let stats = mgr.tick_budgeted(&gpu.queue, cam, 4).stats;
+        assert_eq!(mgr.chunk_state(IVec3::ZERO), ChunkState::Ready);
+        assert_eq!(stats.loaded_this_tick, 1);
+        assert!(mgr.is_solid(Vec3::new(16.0, 0.5, 16.0)));
+    }
+
+    #[test]
+    fn unanswered_request_times_out_to_local_generation() {
+        let (gpu, mut mgr) = make_manager(42, 0);
+        mgr.set_source(Some(Box::new(delayed_source(u32::MAX))));
+        mgr.set_request_timeout(3);
+        let cam = Vec3::new(16.0, 16.0, 16.0);
+
+        for _ in 0..4 {
+            mgr.tick_budgeted(&gpu.queue, cam, 4);
+            assert_eq!(mgr.chunk_state(IVec3::ZERO), ChunkState::Requested);
+        }
+        let stats = mgr.tick_budgeted(&gpu.queue, cam, 4).stats;
+        assert_eq!(mgr.chunk_state(IVec3::ZERO), ChunkState::Ready);
+        assert_eq!(stats.in_flight_count, 0);
+    }
 }
@@ -0,0 +1,230 @@
+use std::fmt;
+
+use glam::IVec3;
+
+use crate::chunk_codec::ChunkCodecError;
+use crate::collision::CollisionMap;
+use crate::map_features::MapConfig;
+use crate::material::MaterialRegistry;
+use crate::terrain_grid::TerrainGrid;
+use crate::voxel::Chunk;
+
+/// A chunk together with the data the client would otherwise derive from it
+/// after loading: sub-region occupancy, collision bits and the terrain grid.
+///
+/// Produced by the chunk server and ingested through
+/// [`ChunkManager::ingest_payload`](crate::chunk_manager::ChunkManager::ingest_payload),
+/// which uploads it without re-running extraction. With the `serde` feature
+/// enabled the type implements `Serialize`/`Deserialize` for the wire.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChunkPayload {
+    pub cx: i32,
+    pub cy: i32,
+    pub cz: i32,
+    /// Voxels in the [`chunk_codec`](crate::chunk_codec) format.
+    pub voxels: Vec<u8>,
+    /// [`Chunk::occupancy_mask`] of the voxels.
+    pub occupancy: u64,
+    /// [`CollisionMap::as_bytes`] of the voxels.
+    pub collision: Vec<u8>,
+    /// Unstitched [`TerrainGrid::to_bytes`] of the voxels.
+    pub terrain_grid: Vec<u8>,
+}
+
+/// The generator settings that decide what a chunk server's payloads
+/// contain. A client only takes chunks from a server whose world matches
+/// its own [`MapConfig`], so streamed and locally generated chunks agree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorldInfo {
+    pub seed: u32,
+    /// Seed of the [`BiomeMap`](crate::biome::BiomeMap) terrain, or `None`
+    /// for the single Perlin height field.
+    pub biome_seed: Option<u32>,
+}
+
+impl WorldInfo {
+    /// The world `config` generates.
+    #[must_use]
+    pub fn of(config: &MapConfig) -> Self {
+        Self {
+            seed: config.seed,
+            biome_seed: config.biomes.as_ref().map(|biomes| biomes.seed),
+        }
+    }
+}
+
+impl fmt::Display for WorldInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "seed {}", self.seed)?;
+        match self.biome_seed {
+            Some(seed) => write!(f, ", biomes {seed}"),
+            None => write!(f, ", no biomes"),
+        }
+    }
+}
+
+/// A payload unpacked into engine types, ready to be loaded.
+pub struct DecodedPayload {
+    pub coord: IVec3,
+    pub chunk: Chunk,
+    pub occupancy: u64,
+    pub collision: CollisionMap,
+    pub terrain: TerrainGrid,
+}
+
+impl DecodedPayload {
+    /// Run every extraction step on `chunk` directly, without going through
+    /// the wire format.
+    #[must_use]
+    pub fn extract(coord: IVec3, chunk: Chunk, materials: &MaterialRegistry) -> Self {
+        Self {
+            coord,
+            occupancy: chunk.occupancy_mask(),
+            collision: CollisionMap::from_chunk(&chunk, materials),
+            terrain: TerrainGrid::from_chunk(&chunk, materials),
+            chunk,
+        }
+    }
+}
+
+/// Why a [`ChunkPayload`] could not be decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkPayloadError {
+    /// The voxel bytes are not a valid encoded chunk.
+    Voxels(ChunkCodecError),
+    /// The collision bitfield has the wrong length.
+    Collision(usize),
+    /// The terrain grid bytes are malformed.
+    TerrainGrid,
+}
+
+impl fmt::Display for ChunkPayloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Voxels(e) => write!(f, "invalid payload voxels: {e}"),
+            Self::Collision(len) => write!(f, "invalid payload collision length {len}"),
+            Self::TerrainGrid => write!(f, "invalid payload terrain grid"),
+        }
+    }
+}
+
+impl std::error::Error for ChunkPayloadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Voxels(e) => Some(e),
+            Self::Collision(_) | Self::TerrainGrid => None,
+        }
+    }
+}
+
+impl From<ChunkCodecError> for ChunkPayloadError {
+    fn from(e: ChunkCodecError) -> Self {
+        Self::Voxels(e)
+    }
+}
+
+impl ChunkPayload {
+    /// Package `chunk` at `coord`, running every extraction step once.
+    #[must_use]
+    pub fn from_chunk(coord: IVec3, chunk: &Chunk, materials: &MaterialRegistry) -> Self {
+        Self {
+            cx: coord.x,
+            cy: coord.y,
+            cz: coord.z,
+            voxels: chunk.encode(),
+            occupancy: chunk.occupancy_mask(),
+            collision: CollisionMap::from_chunk(chunk, materials)
+                .as_bytes()
+                .to_vec(),
+            terrain_grid: TerrainGrid::from_chunk(chunk, materials).to_bytes(),
+        }
+    }
+
+    /// Generate the chunk at `coord` with `config` and package it.
+    #[must_use]
+    pub fn generate(config: &MapConfig, coord: IVec3, materials: &MaterialRegistry) -> Self {
+        Self::from_chunk(coord, &config.generate_chunk(coord), materials)
+    }
+
+    /// World chunk coordinate of the payload.
+    #[must_use]
+    pub fn coord(&self) -> IVec3 {
+        IVec3::new(self.cx, self.cy, self.cz)
+    }
+
+    /// Unpack the payload. Only the voxels are decompressed; the derived
+    /// data is taken as-is.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ChunkPayloadError`] if any field is malformed.
+    pub fn decode(&self) -> Result<DecodedPayload, ChunkPayloadError> {
+        Ok(DecodedPayload {
+            coord: self.coord(),
+            chunk: Chunk::decode(&self.voxels)?,
+            occupancy: self.occupancy,
+            collision: CollisionMap::from_bytes(&self.collision)
+                .ok_or(ChunkPayloadError::Collision(self.collision.len()))?,
+            terrain: TerrainGrid::from_bytes(&self.terrain_grid)
+                .ok_or(ChunkPayloadError::TerrainGrid)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::biome::BiomeMap;
+
+    #[test]
+    fn decode_returns_precomputed_fields() {
+        let coord = IVec3::new(1, 0, -2);
+        let payload =
+            ChunkPayload::generate(&MapConfig::default(), coord, &MaterialRegistry::default());
+        let decoded = payload.decode().expect("valid payload");
+        assert_eq!(decoded.coord, coord);
+        assert_eq!(decoded.occupancy, decoded.chunk.occupancy_mask());
+        assert_eq!(decoded.collision.as_bytes(), &payload.collision[..]);
+        assert_eq!(decoded.terrain.to_bytes(), payload.terrain_grid);
+    }
+
+    #[test]
+    fn malformed_fields_are_reported() {
+        let payload =
+            ChunkPayload::from_chunk(IVec3::ZERO, &Chunk::default(), &MaterialRegistry::default());
+        let mut bad = payload.clone();
+        bad.voxels.clear();
+        assert!(matches!(bad.decode(), Err(ChunkPayloadError::Voxels(_))));
+        let mut bad = payload.clone();
+        bad.collision.pop();
+        assert!(matches!(bad.decode(), Err(ChunkPayloadError::Collision(_))));
+        let mut bad = payload;
+        bad.terrain_grid.push(1);
+        assert!(matches!(bad.decode(), Err(ChunkPayloadError::TerrainGrid)));
+    }
+
+    #[test]
+    fn world_info_tells_biome_worlds_apart() {
+        let plain = WorldInfo::of(&MapConfig::default());
+        let biomes = WorldInfo::of(&MapConfig::default().with_biomes(BiomeMap::standard(42)));
+        assert_eq!(plain.biome_seed, None);
+        assert_eq!(biomes.seed, plain.seed);
+        assert_ne!(biomes, plain);
+        assert_eq!(biomes.to_string(), "seed 42, biomes 42");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn postcard_round_trip() {
+        let payload = ChunkPayload::generate(
+            &MapConfig::default(),
+            IVec3::ZERO,
+            &MaterialRegistry::default(),
+        );
+        let bytes = postcard::to_allocvec(&payload).expect("serialize");
+        let back: ChunkPayload = postcard::from_bytes(&bytes).expect("deserialize");
+        assert_eq!(back, payload);
+    }
+}
@@ -0,0 +1,530 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+
+use glam::IVec3;
+
+#[cfg(feature = "wasm")]
+use crate::chunk_payload::WorldInfo;
+use crate::chunk_payload::{ChunkPayload, DecodedPayload};
+use crate::material::MaterialRegistry;
+use crate::voxel::Chunk;
+
+/// Chunk generator closure, shared between [`LocalSource`] and the
+/// [`ChunkManager`](crate::chunk_manager::ChunkManager) fallback path.
+pub type ChunkGen = dyn Fn(IVec3) -> Chunk + Send + Sync;
+
+/// What a [`ChunkSource`] delivers for a requested chunk.
+pub enum SourcedChunk {
+    /// Plain voxels; collision and terrain data are derived on load.
+    Voxels(Chunk),
+    /// A packaged chunk whose derived data is used as-is.
+    Payload(ChunkPayload),
+    /// Voxels with derived data already extracted, used as-is.
+    Extracted(Box<DecodedPayload>),
+}
+
+/// Why a [`ChunkSource`] could not deliver a chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkSourceError {
+    /// The request failed (network error, non-success status).
+    Request(String),
+    /// The response could not be decoded.
+    Malformed(String),
+    /// The generator panicked while producing the chunk.
+    Panicked,
+    /// The chunk server generates a different world than the client.
+    WrongWorld,
+}
+
+impl fmt::Display for ChunkSourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Request(e) => write!(f, "chunk request failed: {e}"),
+            Self::Malformed(e) => write!(f, "malformed chunk response: {e}"),
+            Self::Panicked => write!(f, "chunk generator panicked"),
+            Self::WrongWorld => write!(f, "chunk server generates a different world"),
+        }
+    }
+}
+
+impl std::error::Error for ChunkSourceError {}
+
+/// A finished chunk request.
+pub struct Completed {
+    pub coord: IVec3,
+    pub result: Result<SourcedChunk, ChunkSourceError>,
+}
+
+/// Asynchronous provider of chunks for the
+/// [`ChunkManager`](crate::chunk_manager::ChunkManager).
+///
+/// The manager calls [`request`](Self::request) once per chunk it wants and
+/// [`poll`](Self::poll) once per tick. A source may answer within the same
+/// tick, several ticks later or never; the manager times out slow requests
+/// and generates those chunks locally.
+pub trait ChunkSource {
+    /// Start producing the chunk at `coord`.
+    fn request(&mut self, coord: IVec3);
+
+    /// Take every request that finished since the last call.
+    fn poll(&mut self) -> Vec<Completed>;
+
+    /// Called when the manager's material table changes. Sources that
+    /// extract collision or terrain data must use `materials` from now on.
+    fn set_materials(&mut self, _materials: &MaterialRegistry) {}
+}
+
+/// Generates requested chunks synchronously with a [`ChunkGen`] closure.
+/// Every request is answered by the next [`poll`](ChunkSource::poll).
+pub struct LocalSource {
+    chunk_gen: Arc<ChunkGen>,
+    completed: Vec<Completed>,
+}
+
+impl LocalSource {
+    #[must_use]
+    pub fn new(chunk_gen: Arc<ChunkGen>) -> Self {
+        Self {
+            chunk_gen,
+            completed: Vec::new(),
+        }
+    }
+}
+
+impl ChunkSource for LocalSource {
+    fn request(&mut self, coord: IVec3) {
+        self.completed.push(Completed {
+            coord,
+            result: Ok(SourcedChunk::Voxels((self.chunk_gen)(coord))),
+        });
+    }
+
+    fn poll(&mut self) -> Vec<Completed> {
+        std::mem::take(&mut self.completed)
+    }
+}
+
+/// Wraps another source and holds each of its answers back for a number of
+/// polls, optionally turning answers for chosen chunks into failures.
+/// Simulates a slow or flaky server in tests and demos.
+pub struct DelayedSource<S> {
+    inner: S,
+    delay: u32,
+    failing: HashSet<IVec3>,
+    /// Answers with the number of polls left before release.
+    queue: VecDeque<(u32, Completed)>,
+}
+
+impl<S: ChunkSource> DelayedSource<S> {
+    /// Release answers `delay` polls after the one that first sees them.
+    /// `u32::MAX` effectively never answers.
+    #[must_use]
+    pub fn new(inner: S, delay: u32) -> Self {
+        Self {
+            inner,
+            delay,
+            failing: HashSet::new(),
+            queue: VecDeque::new(),
+        }
+    }
+
+    /// Answer requests for `coord` with an error instead of the chunk.
+    #[must_use]
+    pub fn failing(mut self, coord: IVec3) -> Self {
+        self.failing.insert(coord);
+        self
+    }
+}
+
+impl<S: ChunkSource> ChunkSource for DelayedSource<S> {
+    fn request(&mut self, coord: IVec3) {
+        self.inner.request(coord);
+    }
+
+    fn poll(&mut self) -> Vec<Completed> {
+        for mut completed in self.inner.poll() {
+            if self.failing.contains(&completed.coord) {
+                completed.result = Err(ChunkSourceError::Request("simulated failure".into()));
+            }
+            self.queue.push_back((self.delay, completed));
+        }
+        let mut ready = Vec::new();
+        let mut waiting = VecDeque::with_capacity(self.queue.len());
+        for (remaining, completed) in self.queue.drain(..) {
+            if remaining == 0 {
+                ready.push(completed);
+            } else {
+                waiting.push_back((remaining - 1, completed));
+            }
+        }
+        self.queue = waiting;
+        ready
+    }
+}
+
+/// Generates chunks and extracts their derived data on a pool of worker
+/// threads. Native only.
+///
+/// [`poll`](ChunkSource::poll) waits for every outstanding request and
+/// returns the answers in request order, so what a tick loads never depends
+/// on thread scheduling; the gain is that a tick's requests run in parallel.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct WorkerPoolSource {
+    jobs: Option<std::sync::mpsc::Sender<PoolJob>>,
+    results: std::sync::mpsc::Receiver<(u64, Completed)>,
+    workers: Vec<std::thread::JoinHandle<()>>,
+    materials: Arc<MaterialRegistry>,
+    /// Ticket of the next request; answers are sorted by ticket.
+    next_ticket: u64,
+    outstanding: usize,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct PoolJob {
+    ticket: u64,
+    coord: IVec3,
+    materials: Arc<MaterialRegistry>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl WorkerPoolSource {
+    /// Start `threads` workers running `chunk_gen`, extracting with
+    /// `materials`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a worker thread cannot be spawned.
+    #[must_use]
+    pub fn new(
+        chunk_gen: &Arc<ChunkGen>,
+        materials: MaterialRegistry,
+        threads: std::num::NonZeroUsize,
+    ) -> Self {
+        let (job_tx, job_rx) = std::sync::mpsc::channel::<PoolJob>();
+        let (result_tx, results) = std::sync::mpsc::channel();
+        let job_rx = Arc::new(std::sync::Mutex::new(job_rx));
+        let workers = (0..threads.get())
+            .map(|i| {
+                let job_rx = Arc::clone(&job_rx);
+                let result_tx = result_tx.clone();
+                let chunk_gen = Arc::clone(chunk_gen);
+                std::thread::Builder::new()
+                    .name(format!("chunk-worker-{i}"))
+                    .spawn(move || run_worker(&job_rx, &result_tx, &*chunk_gen))
+                    .expect("spawn chunk worker")
+            })
+            .collect();
+        Self {
+            jobs: Some(job_tx),
+            results,
+            workers,
+            materials: Arc::new(materials),
+            next_ticket: 0,
+            outstanding: 0,
+        }
+    }
+}
+
+/// Worker thread body: run jobs until the job channel closes or the pool
+/// stops listening for results.
+#[cfg(not(target_arch = "wasm32"))]
+fn run_worker(
+    jobs: &std::sync::Mutex<std::sync::mpsc::Receiver<PoolJob>>,
+    results: &std::sync::mpsc::Sender<(u64, Completed)>,
+    chunk_gen: &ChunkGen,
+) {
+    loop {
+        // The lock guard is dropped at the end of this statement, so other
+        // workers can take jobs while this one runs.
+        let Ok(Ok(job)) = jobs.lock().map(|rx| rx.recv()) else {
+            return;
+        };
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            DecodedPayload::extract(job.coord, chunk_gen(job.coord), &job.materials)
+        }))
+        .map(|decoded| SourcedChunk::Extracted(Box::new(decoded)))
+        .map_err(|_| ChunkSourceError::Panicked);
+        let completed = Completed {
+            coord: job.coord,
+            result,
+        };
+        if results.send((job.ticket, completed)).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ChunkSource for WorkerPoolSource {
+    fn request(&mut self, coord: IVec3) {
+        let job = PoolJob {
+            ticket: self.next_ticket,
+            coord,
+            materials: Arc::clone(&self.materials),
+        };
+        self.next_ticket += 1;
+        self.outstanding += 1;
+        self.jobs
+            .as_ref()
+            .expect("pool is running")
+            .send(job)
+            .expect("chunk workers alive");
+    }
+
+    fn poll(&mut self) -> Vec<Completed> {
+        let mut answers: Vec<(u64, Completed)> =
+            self.results.iter().take(self.outstanding).collect();
+        self.outstanding = 0;
+        answers.sort_by_key(|(ticket, _)| *ticket);
+        answers
+            .into_iter()
+            .map(|(_, completed)| completed)
+            .collect()
+    }
+
+    fn set_materials(&mut self, materials: &MaterialRegistry) {
+        self.materials = Arc::new(materials.clone());
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Drop for WorkerPoolSource {
+    fn drop(&mut self) {
+        // Closing the job channel stops the workers once the queue drains.
+        self.jobs = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Fetches postcard-encoded [`ChunkPayload`]s from a chunk server at
+/// `{base_url}/chunks/{cx},{cy},{cz}`.
+///
+/// Requests run on the browser event loop and complete into a queue drained
+/// by [`poll`](ChunkSource::poll). Must be used from a worker, since the
+/// fetch goes through `WorkerGlobalScope`.
+///
+/// Chunks are only fetched once the server's `{base_url}/config` matches
+/// the client's [`WorldInfo`]. Until then requests wait; if the server
+/// generates another world or cannot say, every request fails with
+/// [`ChunkSourceError::WrongWorld`] and the chunk is generated locally.
+#[cfg(feature = "wasm")]
+pub struct HttpSource {
+    base_url: String,
+    check: std::rc::Rc<std::cell::Cell<WorldCheck>>,
+    waiting: Vec<IVec3>,
+    completed: std::rc::Rc<std::cell::RefCell<Vec<Completed>>>,
+}
+
+/// Outcome of comparing a chunk server's world with the client's.
+#[cfg(feature = "wasm")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum WorldCheck {
+    Pending,
+    Matched,
+    Mismatched,
+}
+
+#[cfg(feature = "wasm")]
+impl HttpSource {
+    /// A source for the server at `base_url`, which must generate `world`.
+    #[must_use]
+    pub fn new(base_url: &str, world: WorldInfo) -> Self {
+        let base_url = base_url.trim_end_matches('/').to_owned();
+        let check = std::rc::Rc::new(std::cell::Cell::new(WorldCheck::Pending));
+        let url = format!("{base_url}/config");
+        let result = std::rc::Rc::clone(&check);
+        wasm_bindgen_futures::spawn_local(async move {
+            let server = fetch_bytes(&url).await.and_then(|bytes| {
+                postcard::from_bytes::<WorldInfo>(&bytes)
+                    .map_err(|e| ChunkSourceError::Malformed(e.to_string()))
+            });
+            result.set(match server {
+                Ok(server) if server == world => WorldCheck::Matched,
+                Ok(server) => {
+                    log::warn!("chunk server world ({server}) differs from ours ({world})");
+                    WorldCheck::Mismatched
+                }
+                Err(e) => {
+                    log::warn!("chunk server config: {e}");
+                    WorldCheck::Mismatched
+                }
+            });
+        });
+        Self {
+            base_url,
+            check,
+            waiting: Vec::new(),
+            completed: std::rc::Rc::default(),
+        }
+    }
+
+    fn fetch(&self, coord: IVec3) {
+        let url = format!(
+            "{}/chunks/{},{},{}",
+            self.base_url, coord.x, coord.y, coord.z
+        );
+        let completed = std::rc::Rc::clone(&self.completed);
+        wasm_bindgen_futures::spawn_local(async move {
+            let result = fetch_payload(&url).await;
+            completed.borrow_mut().push(Completed { coord, result });
+        });
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl ChunkSource for HttpSource {
+    fn request(&mut self, coord: IVec3) {
+        match self.check.get() {
+            WorldCheck::Pending => self.waiting.push(coord),
+            WorldCheck::Matched => self.fetch(coord),
+            WorldCheck::Mismatched => self.completed.borrow_mut().push(Completed {
+                coord,
+                result: Err(ChunkSourceError::WrongWorld),
+            }),
+        }
+    }
+
+    fn poll(&mut self) -> Vec<Completed> {
+        if self.check.get() != WorldCheck::Pending {
+            for coord in std::mem::take(&mut self.waiting) {
+                self.request(coord);
+            }
+        }
+        std::mem::take(&mut *self.completed.borrow_mut())
+    }
+}
+
+#[cfg(feature = "wasm")]
+async fn fetch_bytes(url: &str) -> Result<Vec<u8>, ChunkSourceError> {
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+
+    let request_error = |e: wasm_bindgen::JsValue| ChunkSourceError::Request(format!("{e:?}"));
+    let scope: web_sys::WorkerGlobalScope = js_sys::global().unchecked_into();
+    let response: web_sys::Response = JsFuture::from(scope.fetch_with_str(url))
+        .await
+        .map_err(request_error)?
+        .unchecked_into();
+    if !response.ok() {
+        return Err(ChunkSourceError::Request(format!(
+            "HTTP {}",
+            response.status()
+        )));
+    }
+    let buffer = JsFuture::from(response.array_buffer().map_err(request_error)?)
+        .await
+        .map_err(request_error)?;
+    Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+}
+
+#[cfg(feature = "wasm")]
+async fn fetch_payload(url: &str) -> Result<SourcedChunk, ChunkSourceError> {
+    let bytes = fetch_bytes(url).await?;
+    postcard::from_bytes(&bytes)
+        .map(SourcedChunk::Payload)
+        .map_err(|e| ChunkSourceError::Malformed(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local() -> LocalSource {
+        LocalSource::new(Arc::new(|coord: IVec3| {
+            Chunk::uniform(u32::from(coord.x.unsigned_abs() as u8))
+        }))
+    }
+
+    fn coords(completed: &[Completed]) -> Vec<IVec3> {
+        completed.iter().map(|c| c.coord).collect()
+    }
+
+    #[test]
+    fn local_source_answers_on_next_poll() {
+        let mut source = local();
+        source.request(IVec3::X);
+        source.request(IVec3::Y);
+        let done = source.poll();
+        assert_eq!(coords(&done), [IVec3::X, IVec3::Y]);
+        assert!(matches!(
+            &done[0].result,
+            Ok(SourcedChunk::Voxels(chunk)) if chunk.uniform_voxel() == Some(1)
+        ));
+        assert!(source.poll().is_empty());
+    }
+
+    #[test]
+    fn delayed_source_holds_answers_back() {
+        let mut source = DelayedSource::new(local(), 2);
+        source.request(IVec3::X);
+        assert!(source.poll().is_empty());
+        source.request(IVec3::Y);
+        assert!(source.poll().is_empty());
+        assert_eq!(coords(&source.poll()), [IVec3::X]);
+        assert_eq!(coords(&source.poll()), [IVec3::Y]);
+    }
+
+    #[test]
+    fn worker_pool_answers_in_request_order_with_extracted_data() {
+        let materials = MaterialRegistry::default();
+        let chunk_gen: Arc<ChunkGen> = Arc::new(|coord| Chunk::new_terrain_at(7, coord));
+        let mut pool = WorkerPoolSource::new(
+            &chunk_gen,
+            materials.clone(),
+            std::num::NonZeroUsize::new(3).unwrap(),
+        );
+        let requested: Vec<IVec3> = (-3..3).map(|x| IVec3::new(x, 0, x / 2)).collect();
+        for &coord in &requested {
+            pool.request(coord);
+        }
+        let done = pool.poll();
+        assert_eq!(coords(&done), requested);
+        for completed in done {
+            let Ok(SourcedChunk::Extracted(decoded)) = completed.result else {
+                panic!("expected extracted chunk");
+            };
+            let expected = chunk_gen(completed.coord);
+            assert_eq!(decoded.chunk, expected);
+            assert_eq!(decoded.occupancy, expected.occupancy_mask());
+            assert_eq!(
+                decoded.collision.as_bytes(),
+                crate::collision::CollisionMap::from_chunk(&expected, &materials).as_bytes()
+            );
+        }
+        assert!(pool.poll().is_empty());
+    }
+
+    #[test]
+    fn worker_pool_reports_generator_panics() {
+        let chunk_gen: Arc<ChunkGen> = Arc::new(|coord: IVec3| {
+            assert!(coord != IVec3::Y, "bad chunk");
+            Chunk::default()
+        });
+        let mut pool = WorkerPoolSource::new(
+            &chunk_gen,
+            MaterialRegistry::default(),
+            std::num::NonZeroUsize::new(2).unwrap(),
+        );
+        pool.request(IVec3::X);
+        pool.request(IVec3::Y);
+        pool.request(IVec3::Z);
+        let done = pool.poll();
+        assert_eq!(coords(&done), [IVec3::X, IVec3::Y, IVec3::Z]);
+        assert!(done[0].result.is_ok());
+        assert!(matches!(done[1].result, Err(ChunkSourceError::Panicked)));
+        assert!(done[2].result.is_ok());
+    }
+
+    #[test]
+    fn delayed_source_fails_chosen_chunks() {
+        let mut source = DelayedSource::new(local(), 0).failing(IVec3::Y);
+        source.request(IVec3::X);
+        source.request(IVec3::Y);
+        let done = source.poll();
+        assert!(done[0].result.is_ok());
+        assert!(matches!(done[1].result, Err(ChunkSourceError::Request(_))));
+    }
+}
@@ -1,4 +1,5 @@
-use crate::voxel::CHUNK_SIZE;
+use crate::material::MaterialRegistry;
+use crate::voxel::{CHUNK_SIZE, Chunk};
 use glam::Vec3;
 
 /// 1-bit-per-voxel collision bitfield for a single chunk (4KB).
@@ -12,20 +13,76 @@ impl CollisionMap {
     const TOTAL_BITS: usize = Self::BITS_PER_AXIS * Self::BITS_PER_AXIS * Self::BITS_PER_AXIS;
     const BYTES: usize = Self::TOTAL_BITS / 8;
 
-    /// Build a collision map from a voxel array. Any voxel with non-zero
-    /// `material_id` (lowest byte) is marked solid.
+    /// Build a collision map from a voxel array. A voxel is marked solid if
+    /// its `material_id` is solid in `materials` and its flags do not make it
+    /// passable (see [`MaterialRegistry::is_solid_voxel`]).
     #[must_use]
-    pub fn from_voxels(voxels: &[u32]) -> Self {
+    pub fn from_voxels(voxels: &[u32], materials: &MaterialRegistry) -> Self {
         debug_assert_eq!(voxels.len(), Self::TOTAL_BITS);
         let mut bits = [0u8; Self::BYTES];
         for (i, &v) in voxels.iter().enumerate() {
-            if (v & 0xFF) != 0 {
+            if materials.is_solid_voxel(v) {
                 bits[i / 8] |= 1 << (i % 8);
             }
         }
         Self { bits }
     }
 
+    /// Build a collision map from a chunk. Uniform chunks are filled
+    /// directly without visiting each voxel.
+    #[must_use]
+    pub fn from_chunk(chunk: &Chunk, materials: &MaterialRegistry) -> Self {
+        if let Some(v) = chunk.uniform_voxel() {
+            let fill = if materials.is_solid_voxel(v) { 0xFF } else { 0 };
+            return Self {
+                bits: [fill; Self::BYTES],
+            };
+        }
+        let mut bits = [0u8; Self::BYTES];
+        for (i, v) in chunk.iter().enumerate() {
+            if materials.is_solid_voxel(v) {
+                bits[i / 8] |= 1 << (i % 8);
+            }
+        }
+        Self { bits }
+    }
+
+    /// Rebuild a collision map from the bytes returned by
+    /// [`as_bytes`](Self::as_bytes). Returns `None` if `bytes` is not exactly
+    /// one chunk's worth of bits.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Some(Self {
+            bits: bytes.try_into().ok()?,
+        })
+    }
+
+    /// The raw bitfield, one bit per voxel in [`voxel_index`] order.
+    ///
+    /// [`voxel_index`]: crate::voxel::voxel_index
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bits
+    }
+
+    /// Mark the voxel at local `(x, y, z)` as solid or empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any coordinate is outside `0..CHUNK_SIZE`.
+    pub fn set(&mut self, x: usize, y: usize, z: usize, solid: bool) {
+        assert!(
+            x < CHUNK_SIZE && y < CHUNK_SIZE && z < CHUNK_SIZE,
+            "voxel ({x}, {y}, {z}) out of chunk bounds"
+        );
+        let idx = z * CHUNK_SIZE * CHUNK_SIZE + y * CHUNK_SIZE + x;
+        if solid {
+            self.bits[idx / 8] |= 1 << (idx % 8);
+        } else {
+            self.bits[idx / 8] &= !(1 << (idx % 8));
+        }
+    }
+
     /// Check if two world positions are in different voxels.
     #[must_use]
     pub fn crosses_voxel_boundary(old: Vec3, new: Vec3) -> bool {
@@ -51,12 +108,14 @@ impl CollisionMap {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::voxel::{CHUNK_SIZE, MAT_STONE, pack_voxel};
+    use crate::voxel::{
+        CHUNK_SIZE, FLAG_DOOR, FLAG_OPEN, MAT_GLASS, MAT_STONE, MAT_WATER, pack_voxel, voxel_index,
+    };
 
     #[test]
     fn all_air_has_no_solid() {
         let voxels = vec![0u32; CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE];
-        let map = CollisionMap::from_voxels(&voxels);
+        let map = CollisionMap::from_voxels(&voxels, &MaterialRegistry::default());
         assert!(!map.is_solid(0, 0, 0));
         assert!(!map.is_solid(15, 15, 15));
         assert!(!map.is_solid(31, 31, 31));
@@ -67,15 +126,55 @@ mod tests {
         let mut voxels = vec![0u32; CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE];
         let idx = 20 * CHUNK_SIZE * CHUNK_SIZE + 10 * CHUNK_SIZE + 5;
         voxels[idx] = pack_voxel(MAT_STONE, 0, 0, 0);
-        let map = CollisionMap::from_voxels(&voxels);
+        let map = CollisionMap::from_voxels(&voxels, &MaterialRegistry::default());
         assert!(map.is_solid(5, 10, 20));
         assert!(!map.is_solid(5, 10, 19));
     }
 
+    #[test]
+    fn liquid_is_not_solid() {
+        let mut voxels = vec![0u32; CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE];
+        voxels[voxel_index(1, 1, 1)] = pack_voxel(MAT_WATER, 0, 0, 0);
+        voxels[voxel_index(2, 1, 1)] = pack_voxel(MAT_GLASS, 0, 0, 0);
+        let map = CollisionMap::from_voxels(&voxels, &MaterialRegistry::default());
+        assert!(!map.is_solid(1, 1, 1));
+        assert!(map.is_solid(2, 1, 1));
+    }
+
+    #[test]
+    fn open_door_is_passable() {
+        let mut voxels = vec![0u32; CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE];
+        voxels[voxel_index(1, 1, 1)] = pack_voxel(MAT_STONE, 0, 0, FLAG_DOOR);
+        voxels[voxel_index(2, 1, 1)] = pack_voxel(MAT_STONE, 0, 0, FLAG_DOOR | FLAG_OPEN);
+        let map = CollisionMap::from_voxels(&voxels, &MaterialRegistry::default());
+        assert!(map.is_solid(1, 1, 1));
+        assert!(!map.is_solid(2, 1, 1));
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let chunk = Chunk::new_terrain_at(42, glam::IVec3::ZERO);
+        let map = CollisionMap::from_chunk(&chunk, &MaterialRegistry::default());
+        let copy = CollisionMap::from_bytes(map.as_bytes()).expect("valid length");
+        assert_eq!(copy.as_bytes(), map.as_bytes());
+        assert!(CollisionMap::from_bytes(&map.as_bytes()[1..]).is_none());
+    }
+
+    #[test]
+    fn set_toggles_single_voxel() {
+        let voxels = vec![0u32; CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE];
+        let mut map = CollisionMap::from_voxels(&voxels, &MaterialRegistry::default());
+        map.set(3, 4, 5, true);
+        assert!(map.is_solid(3, 4, 5));
+        assert!(!map.is_solid(4, 4, 5));
+        map.set(3, 4, 5, false);
+        assert!(!map.is_solid(3, 4, 5));
+    }
+
     #[test]
     fn out_of_bounds_returns_false() {
         let voxels = vec![pack_voxel(MAT_STONE, 0, 0, 0); CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE];
-        let map = CollisionMap::from_voxels(&voxels);
+        let map = CollisionMap::from_voxels(&voxels, &MaterialRegistry::default());
         assert!(!map.is_solid(-1, 0, 0));
         assert!(!map.is_solid(0, -1, 0));
         assert!(!map.is_solid(0, 0, 32));
@@ -87,7 +186,7 @@ mod tests {
         use crate::voxel::Chunk;
         use glam::IVec3;
         let chunk = Chunk::new_terrain_at(42, IVec3::ZERO);
-        let map = CollisionMap::from_voxels(&chunk.voxels);
+        let map = CollisionMap::from_chunk(&chunk, &MaterialRegistry::default());
         assert!(map.is_solid(16, 0, 16));
         assert!(!map.is_solid(16, 31, 16));
     }
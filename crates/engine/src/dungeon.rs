@@ -0,0 +1,530 @@
+use glam::{IVec2, IVec3};
+
+use crate::map_features::MapFeature;
+use crate::map_rng::{FEATURE_DUNGEON, MapRng};
+use crate::voxel::{CHUNK_SIZE, Chunk, FLAG_DOOR, MAT_AIR, MAT_DIRT, MAT_STONE, pack_voxel};
+
+/// Interior height of rooms in voxels.
+const ROOM_HEIGHT: i32 = 4;
+
+/// Interior height of corridors, doorways and stairwells.
+const PASSAGE_HEIGHT: i32 = 3;
+
+/// Voxel filling a doorway: a closed door.
+const DOOR_VOXEL: u32 = pack_voxel(MAT_DIRT, 0, 0, FLAG_DOOR);
+
+/// Voxel of the walls, floors and ceilings around every carved space.
+const WALL_VOXEL: u32 = pack_voxel(MAT_STONE, 0, 0, 0);
+
+/// Shape of a [`Dungeon`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DungeonParams {
+    /// Minimum x/z corner of the footprint; `y` is the floor of the top
+    /// level (the y of the voxels one stands in).
+    pub origin: IVec3,
+    /// Footprint size along x and z in voxels.
+    pub footprint: IVec2,
+    /// Number of levels, each `level_spacing` voxels below the last.
+    pub levels: u32,
+    pub level_spacing: i32,
+    /// Smallest and largest room side, in interior voxels.
+    pub min_room: i32,
+    pub max_room: i32,
+    /// Most rooms on one level; fewer fit when the footprint is small.
+    pub rooms_per_level: u32,
+}
+
+impl Default for DungeonParams {
+    /// Three levels of up to eight rooms, east of the spawn platform with
+    /// the top level one voxel above its surface.
+    fn default() -> Self {
+        Self {
+            origin: IVec3::new(72, 25, -48),
+            footprint: IVec2::new(96, 96),
+            levels: 3,
+            level_spacing: 8,
+            min_room: 5,
+            max_room: 12,
+            rooms_per_level: 8,
+        }
+    }
+}
+
+/// A room's interior: the air voxels inside its walls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Room {
+    pub level: u32,
+    /// Inclusive corners; `min.y` is the floor.
+    pub min: IVec3,
+    pub max: IVec3,
+}
+
+impl Room {
+    /// The floor voxel at the room's center.
+    #[must_use]
+    pub fn center(&self) -> IVec3 {
+        IVec3::new(
+            i32::midpoint(self.min.x, self.max.x),
+            self.min.y,
+            i32::midpoint(self.min.z, self.max.z),
+        )
+    }
+
+    #[must_use]
+    pub fn contains(&self, p: IVec3) -> bool {
+        p.cmpge(self.min).all() && p.cmple(self.max).all()
+    }
+}
+
+/// A flight of steps from a room down to the next level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stair {
+    /// Room the stair starts in.
+    pub upper_room: usize,
+    /// Room on the level below that the foot of the stair connects to.
+    pub lower_room: usize,
+    /// Floor voxels of the top and bottom steps.
+    pub top: IVec3,
+    pub bottom: IVec3,
+}
+
+/// Queryable structure of a [`Dungeon`], for placing encounters.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DungeonLayout {
+    pub rooms: Vec<Room>,
+    /// Pairs of indices into `rooms` joined by a corridor. The rooms of
+    /// each level form a tree.
+    pub connections: Vec<(usize, usize)>,
+    /// Lowest voxel of each doorway; doors are `PASSAGE_HEIGHT` tall.
+    pub doors: Vec<IVec3>,
+    pub stairs: Vec<Stair>,
+}
+
+impl DungeonLayout {
+    /// Index of the room whose interior contains `p`.
+    #[must_use]
+    pub fn room_at(&self, p: IVec3) -> Option<usize> {
+        self.rooms.iter().position(|room| room.contains(p))
+    }
+
+    /// Indices of the rooms on `level`.
+    pub fn rooms_on_level(&self, level: u32) -> impl Iterator<Item = usize> + '_ {
+        (0..self.rooms.len()).filter(move |&i| self.rooms[i].level == level)
+    }
+}
+
+/// An axis-aligned box of voxels, inclusive.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Span {
+    min: IVec3,
+    max: IVec3,
+}
+
+impl Span {
+    fn new(a: IVec3, b: IVec3) -> Self {
+        Self {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    fn grow(self, by: i32) -> Self {
+        Self {
+            min: self.min - by,
+            max: self.max + by,
+        }
+    }
+
+    fn clip(self, other: Self) -> Option<Self> {
+        let (min, max) = (self.min.max(other.min), self.max.min(other.max));
+        max.cmpge(min).all().then_some(Self { min, max })
+    }
+}
+
+/// A seeded room-and-corridor dungeon over several levels.
+///
+/// Each level's footprint is split by BSP into leaves holding one room
+/// each; every room is joined by an L-shaped corridor to the nearest room
+/// generated before it, and a stair descends from one room to the next
+/// level. As a [`MapFeature`] it surrounds every room, corridor and stair
+/// with stone, then carves their interiors and hangs doors, so it cuts into
+/// hills and stands as walls and roofs above open ground alike.
+pub struct Dungeon {
+    pub seed: u32,
+    pub params: DungeonParams,
+    layout: DungeonLayout,
+    /// Interiors of rooms, corridors and stair steps.
+    spaces: Vec<Span>,
+}
+
+impl Dungeon {
+    /// Lay out the dungeon. The same `seed` and `params` always give the
+    /// same dungeon.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `level_spacing` leaves no rock between levels, or if the
+    /// room sizes are not `3 <= min_room <= max_room`.
+    #[must_use]
+    pub fn generate(seed: u32, params: DungeonParams) -> Self {
+        assert!(
+            params.level_spacing >= ROOM_HEIGHT + 2,
+            "level_spacing must be at least {}",
+            ROOM_HEIGHT + 2
+        );
+        assert!(
+            3 <= params.min_room && params.min_room <= params.max_room,
+            "room sizes must satisfy 3 <= min_room <= max_room"
+        );
+        let mut dungeon = Self {
+            seed,
+            params,
+            layout: DungeonLayout::default(),
+            spaces: Vec::new(),
+        };
+        let mut rng = MapRng::new(seed, FEATURE_DUNGEON);
+        let mut stair_foot: Option<(IVec3, usize)> = None;
+        for level in 0..params.levels {
+            let first = dungeon.layout.rooms.len();
+            dungeon.place_rooms(level, &mut rng);
+            let rooms = first..dungeon.layout.rooms.len();
+            if rooms.is_empty() {
+                break;
+            }
+            for i in rooms.clone().skip(1) {
+                let j = dungeon.nearest_room(rooms.start..i, dungeon.layout.rooms[i].center());
+                dungeon.layout.connections.push((j, i));
+                let (from, to) = (
+                    dungeon.layout.rooms[j].center(),
+                    dungeon.layout.rooms[i].center(),
+                );
+                dungeon.corridor(level, from, to, rng.coin());
+            }
+            if let Some((bottom, stair)) = stair_foot.take() {
+                let lower = dungeon.nearest_room(rooms.clone(), bottom);
+                dungeon.layout.stairs[stair].lower_room = lower;
+                let to = dungeon.layout.rooms[lower].center();
+                dungeon.corridor(level, bottom, to, rng.coin());
+            }
+            if level + 1 < params.levels {
+                let upper = rooms.start + rng.below(rooms.len());
+                stair_foot = Some((dungeon.stair(upper), dungeon.layout.stairs.len() - 1));
+            }
+        }
+        dungeon
+    }
+
+    #[must_use]
+    pub fn layout(&self) -> &DungeonLayout {
+        &self.layout
+    }
+
+    /// Floor y of `level`.
+    fn floor_y(&self, level: u32) -> i32 {
+        self.params.origin.y - level.cast_signed() * self.params.level_spacing
+    }
+
+    /// Split the footprint into BSP leaves and place a room in each.
+    fn place_rooms(&mut self, level: u32, rng: &mut MapRng) {
+        let p = self.params;
+        let min_leaf = p.min_room + 2;
+        let origin = IVec2::new(p.origin.x, p.origin.z);
+        let mut leaves = vec![(origin, origin + p.footprint - 1)];
+        while leaves.len() < p.rooms_per_level as usize {
+            let splittable =
+                |(lo, hi): &(IVec2, IVec2)| (*hi - *lo + 1).max_element() >= 2 * min_leaf;
+            let Some(index) = (0..leaves.len())
+                .filter(|&i| splittable(&leaves[i]))
+                .max_by_key(|&i| {
+                    let size = leaves[i].1 - leaves[i].0 + 1;
+                    (size.x * size.y, std::cmp::Reverse(i))
+                })
+            else {
+                break;
+            };
+            let (lo, hi) = leaves[index];
+            let size = hi - lo + 1;
+            let axis = usize::from(size.y > size.x || (size.y == size.x && rng.coin()));
+            let cut = lo[axis] + rng.range(min_leaf, size[axis] - min_leaf);
+            let (mut first_hi, mut second_lo) = (hi, lo);
+            first_hi[axis] = cut - 1;
+            second_lo[axis] = cut;
+            leaves[index] = (lo, first_hi);
+            leaves.insert(index + 1, (second_lo, hi));
+        }
+
+        let floor = self.floor_y(level);
+        for (lo, hi) in leaves {
+            // One voxel of margin on each side keeps neighbouring rooms apart.
+            let room_size =
+                |extent: i32, rng: &mut MapRng| rng.range(p.min_room, p.max_room.min(extent - 2));
+            let extent = hi - lo + 1;
+            let size = IVec2::new(room_size(extent.x, rng), room_size(extent.y, rng));
+            let offset = IVec2::new(
+                rng.range(1, extent.x - 1 - size.x),
+                rng.range(1, extent.y - 1 - size.y),
+            );
+            let min = lo + offset;
+            let max = min + size - 1;
+            let room = Room {
+                level,
+                min: IVec3::new(min.x, floor, min.y),
+                max: IVec3::new(max.x, floor + ROOM_HEIGHT - 1, max.y),
+            };
+            self.spaces.push(Span::new(room.min, room.max));
+            self.layout.rooms.push(room);
+        }
+    }
+
+    /// The room in `candidates` whose center is nearest `p`.
+    fn nearest_room(&self, candidates: std::ops::Range<usize>, p: IVec3) -> usize {
+        let start = candidates.start;
+        candidates
+            .min_by_key(|&i| (self.layout.rooms[i].center() - p).length_squared())
+            .unwrap_or(start)
+    }
+
+    /// Carve an L-shaped corridor between two floor voxels on `level`,
+    /// along x first when `x_first`, and hang doors where it enters rooms.
+    fn corridor(&mut self, level: u32, from: IVec3, to: IVec3, x_first: bool) {
+        let floor = self.floor_y(level);
+        let corner = if x_first {
+            IVec3::new(to.x, floor, from.z)
+        } else {
+            IVec3::new(from.x, floor, to.z)
+        };
+        let (from, to) = (from.with_y(floor), to.with_y(floor));
+        let headroom = IVec3::Y * (PASSAGE_HEIGHT - 1);
+        for (a, b) in [(from, corner), (corner, to)] {
+            self.spaces.push(Span::new(a, b + headroom));
+        }
+
+        let path = leg(from, corner).chain(leg(corner, to).skip(1));
+        let cells: Vec<IVec3> = path.collect();
+        for pair in cells.windows(2) {
+            for room in self.layout.rooms.iter().filter(|r| r.level == level) {
+                let wall = |p: IVec3| {
+                    !room.contains(p)
+                        && Span::new(room.min, room.max)
+                            .grow(1)
+                            .clip(Span::new(p, p))
+                            .is_some()
+                };
+                for (outside, inside) in [(pair[0], pair[1]), (pair[1], pair[0])] {
+                    if wall(outside)
+                        && room.contains(inside)
+                        && !self.layout.doors.contains(&outside)
+                    {
+                        self.layout.doors.push(outside);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Carve a stair descending one voxel per step from the center of
+    /// `upper_room` to the floor of the next level, heading away from the
+    /// nearer footprint edge. Returns the bottom step.
+    fn stair(&mut self, upper_room: usize) -> IVec3 {
+        let top = self.layout.rooms[upper_room].center();
+        let spacing = self.params.level_spacing;
+        let mid = self.params.origin.x + self.params.footprint.x / 2;
+        let dir = if top.x < mid { 1 } else { -1 };
+        for step in 0..=spacing {
+            let floor = IVec3::new(top.x + dir * step, top.y - step, top.z);
+            // One extra voxel of headroom for stepping down from above.
+            self.spaces
+                .push(Span::new(floor, floor + IVec3::Y * PASSAGE_HEIGHT));
+        }
+        let bottom = IVec3::new(top.x + dir * spacing, top.y - spacing, top.z);
+        self.layout.stairs.push(Stair {
+            upper_room,
+            lower_room: upper_room,
+            top,
+            bottom,
+        });
+        bottom
+    }
+}
+
+/// Voxels from `a` to `b` along one axis, inclusive.
+fn leg(a: IVec3, b: IVec3) -> impl Iterator<Item = IVec3> {
+    let step = (b - a).signum();
+    let len = (b - a).abs().max_element();
+    (0..=len).map(move |i| a + step * i)
+}
+
+impl MapFeature for Dungeon {
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    fn apply(&self, chunk: &mut Chunk, chunk_coord: IVec3) {
+        let cs = CHUNK_SIZE as i32;
+        let bounds = Span::new(chunk_coord * cs, chunk_coord * cs + cs - 1);
+        let origin = bounds.min;
+        let mut fill = |span: Span, voxel: u32| {
+            if let Some(span) = span.clip(bounds) {
+                for z in span.min.z..=span.max.z {
+                    for y in span.min.y..=span.max.y {
+                        for x in span.min.x..=span.max.x {
+                            let local = (IVec3::new(x, y, z) - origin).as_uvec3();
+                            chunk.set_voxel(
+                                local.x as usize,
+                                local.y as usize,
+                                local.z as usize,
+                                voxel,
+                            );
+                        }
+                    }
+                }
+            }
+        };
+        for space in &self.spaces {
+            fill(space.grow(1), WALL_VOXEL);
+        }
+        for space in &self.spaces {
+            fill(*space, pack_voxel(MAT_AIR, 0, 0, 0));
+        }
+        for &door in &self.layout.doors {
+            fill(
+                Span::new(door, door + IVec3::Y * (PASSAGE_HEIGHT - 1)),
+                DOOR_VOXEL,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxel::{MAT_GRASS, flags, material_id};
+
+    fn small() -> DungeonParams {
+        DungeonParams {
+            origin: IVec3::new(0, 10, 0),
+            footprint: IVec2::new(48, 40),
+            levels: 2,
+            level_spacing: 8,
+            min_room: 4,
+            max_room: 9,
+            rooms_per_level: 5,
+        }
+    }
+
+    /// The world voxel at `p` after applying `dungeon` to solid stone.
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    fn voxel(dungeon: &Dungeon, p: IVec3) -> u32 {
+        let cs = CHUNK_SIZE as i32;
+        let coord = p.div_euclid(IVec3::splat(cs));
+        let mut chunk = Chunk::uniform(pack_voxel(MAT_GRASS, 0, 0, 0));
+        dungeon.apply(&mut chunk, coord);
+        let local = p.rem_euclid(IVec3::splat(cs)).as_uvec3();
+        chunk.voxel_at(local.x as usize, local.y as usize, local.z as usize)
+    }
+
+    /// Whether every room on each level is reachable through corridors.
+    fn connected(layout: &DungeonLayout, level: u32) -> bool {
+        let rooms: Vec<usize> = layout.rooms_on_level(level).collect();
+        let mut reached = vec![rooms[0]];
+        let mut grew = true;
+        while grew {
+            grew = false;
+            for &(a, b) in &layout.connections {
+                for (from, to) in [(a, b), (b, a)] {
+                    if reached.contains(&from) && !reached.contains(&to) {
+                        reached.push(to);
+                        grew = true;
+                    }
+                }
+            }
+        }
+        rooms.iter().all(|r| reached.contains(r))
+    }
+
+    #[test]
+    fn generation_is_deterministic_per_seed() {
+        let a = Dungeon::generate(7, small());
+        assert_eq!(a.layout(), Dungeon::generate(7, small()).layout());
+        assert_ne!(a.layout(), Dungeon::generate(8, small()).layout());
+    }
+
+    #[test]
+    fn rooms_fit_the_footprint_without_overlapping() {
+        let dungeon = Dungeon::generate(3, small());
+        let layout = dungeon.layout();
+        assert_eq!(layout.rooms_on_level(0).count(), 5);
+        assert_eq!(layout.rooms_on_level(1).count(), 5);
+        for (i, a) in layout.rooms.iter().enumerate() {
+            let size = a.max - a.min + 1;
+            assert!(size.x >= 4 && size.x <= 9 && size.z >= 4 && size.z <= 9);
+            assert!(a.min.x > 0 && a.min.z > 0 && a.max.x < 47 && a.max.z < 39);
+            assert_eq!(a.min.y, 10 - a.level.cast_signed() * 8);
+            for b in &layout.rooms[i + 1..] {
+                let walls = Span::new(b.min, b.max).grow(1);
+                assert!(
+                    Span::new(a.min, a.max).clip(walls).is_none(),
+                    "{a:?} overlaps {b:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn levels_are_connected_and_joined_by_stairs() {
+        let dungeon = Dungeon::generate(11, small());
+        let layout = dungeon.layout();
+        assert!(connected(layout, 0) && connected(layout, 1));
+        assert_eq!(layout.stairs.len(), 1);
+        let stair = layout.stairs[0];
+        assert_eq!(layout.rooms[stair.upper_room].level, 0);
+        assert_eq!(layout.rooms[stair.lower_room].level, 1);
+        assert_eq!(stair.top.y - stair.bottom.y, 8);
+        assert!(!layout.doors.is_empty());
+    }
+
+    #[test]
+    fn carves_rooms_and_hangs_doors_in_walls() {
+        let dungeon = Dungeon::generate(5, small());
+        let layout = dungeon.layout();
+        let room = layout.rooms[0];
+        assert_eq!(material_id(voxel(&dungeon, room.center())), MAT_AIR);
+        assert_eq!(
+            material_id(voxel(&dungeon, room.center() - IVec3::Y)),
+            MAT_STONE
+        );
+        assert_eq!(material_id(voxel(&dungeon, room.max + IVec3::Y)), MAT_STONE);
+        let door = layout.doors[0];
+        assert_ne!(flags(voxel(&dungeon, door)) & FLAG_DOOR, 0);
+        // Far from the dungeon the terrain is untouched.
+        assert_eq!(
+            material_id(voxel(&dungeon, IVec3::new(200, 10, 0))),
+            MAT_GRASS
+        );
+    }
+
+    #[test]
+    fn default_dungeon_clears_the_spawn_area() {
+        let dungeon = Dungeon::generate(0, DungeonParams::default());
+        assert!(dungeon.spaces.iter().all(|s| s.min.x >= 64));
+    }
+
+    #[test]
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    fn doors_survive_the_shaded_feature_pipeline() {
+        use crate::map_features::MapConfig;
+        use crate::voxel::{FLAG_OPEN, is_passable, with_flags};
+        let config = MapConfig::with_seed(9).with_dungeon(DungeonParams::default());
+        let layout = config.dungeon.as_ref().unwrap().layout();
+        let cs = IVec3::splat(CHUNK_SIZE as i32);
+        let mut chunks = std::collections::HashMap::new();
+        for &door in &layout.doors {
+            let coord = door.div_euclid(cs);
+            let chunk = chunks
+                .entry(coord)
+                .or_insert_with(|| config.generate_chunk(coord));
+            let local = door.rem_euclid(cs).as_uvec3();
+            let v = chunk.voxel_at(local.x as usize, local.y as usize, local.z as usize);
+            assert_eq!(material_id(v), MAT_DIRT, "door at {door} was overwritten");
+            assert_ne!(flags(v) & FLAG_DOOR, 0, "door at {door} lost its flag");
+            assert!(is_passable(with_flags(v, flags(v) | FLAG_OPEN)));
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use glam::IVec3;
+
+use crate::voxel::Chunk;
+
+/// Per-chunk record of voxel edits that outlives atlas eviction.
+///
+/// Chunks evicted from the atlas are regenerated from scratch when they come
+/// back into view. The overlay stores every edited voxel (keyed by
+/// [`voxel_index`](crate::voxel::voxel_index)) so it can be replayed on top of
+/// the regenerated terrain. Edits the regenerated terrain already matches
+/// are dropped as they are replayed, so the overlay only keeps voxels that
+/// still differ from the generator's output.
+#[derive(Default)]
+pub struct EditOverlay {
+    chunks: HashMap<IVec3, HashMap<usize, u32>>,
+}
+
+impl EditOverlay {
+    /// Record that the voxel at `index` in chunk `coord` now holds `voxel`.
+    /// Later edits to the same voxel replace earlier ones.
+    pub fn record(&mut self, coord: IVec3, index: usize, voxel: u32) {
+        self.chunks.entry(coord).or_default().insert(index, voxel);
+    }
+
+    /// Replay all recorded edits for `coord` onto `chunk`, forgetting those
+    /// `chunk` already holds. Returns `true` if any voxel changed.
+    pub fn apply(&mut self, coord: IVec3, chunk: &mut Chunk) -> bool {
+        let Some(edits) = self.chunks.get_mut(&coord) else {
+            return false;
+        };
+        edits.retain(|&index, &mut voxel| chunk.get(index) != voxel);
+        for (&index, &voxel) in edits.iter() {
+            chunk.set(index, voxel);
+        }
+        let edited = !edits.is_empty();
+        if !edited {
+            self.chunks.remove(&coord);
+        }
+        edited
+    }
+
+    /// Number of edited voxels recorded for `coord`.
+    #[must_use]
+    pub fn edit_count(&self, coord: IVec3) -> usize {
+        self.chunks.get(&coord).map_or(0, HashMap::len)
+    }
+
+    /// Number of chunks with at least one recorded edit.
+    #[must_use]
+    pub fn edited_chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxel::{MAT_STONE, material_id, pack_voxel, voxel_index};
+
+    fn stone_chunk() -> Chunk {
+        Chunk::uniform(pack_voxel(MAT_STONE, 0, 0, 0))
+    }
+
+    #[test]
+    fn apply_without_edits_is_noop() {
+        let mut overlay = EditOverlay::default();
+        let mut chunk = stone_chunk();
+        assert!(!overlay.apply(IVec3::ZERO, &mut chunk));
+        assert_eq!(material_id(chunk.voxel_at(0, 0, 0)), MAT_STONE);
+    }
+
+    #[test]
+    fn apply_replays_recorded_edit() {
+        let mut overlay = EditOverlay::default();
+        overlay.record(IVec3::ZERO, voxel_index(4, 5, 6), 0);
+        let mut chunk = stone_chunk();
+        assert!(overlay.apply(IVec3::ZERO, &mut chunk));
+        assert_eq!(chunk.voxel_at(4, 5, 6), 0);
+        assert_eq!(material_id(chunk.voxel_at(4, 5, 7)), MAT_STONE);
+    }
+
+    #[test]
+    fn edits_are_scoped_to_their_chunk() {
+        let mut overlay = EditOverlay::default();
+        overlay.record(IVec3::new(1, 0, 0), voxel_index(0, 0, 0), 0);
+        let mut chunk = stone_chunk();
+        assert!(!overlay.apply(IVec3::ZERO, &mut chunk));
+        assert_eq!(material_id(chunk.voxel_at(0, 0, 0)), MAT_STONE);
+    }
+
+    #[test]
+    fn later_edit_replaces_earlier() {
+        let mut overlay = EditOverlay::default();
+        let idx = voxel_index(1, 1, 1);
+        overlay.record(IVec3::ZERO, idx, 0);
+        overlay.record(IVec3::ZERO, idx, pack_voxel(2, 0, 0, 0));
+        assert_eq!(overlay.edit_count(IVec3::ZERO), 1);
+        let mut chunk = stone_chunk();
+        overlay.apply(IVec3::ZERO, &mut chunk);
+        assert_eq!(material_id(chunk.voxel_at(1, 1, 1)), 2);
+    }
+
+    #[test]
+    fn edits_matching_regenerated_terrain_are_pruned() {
+        let mut overlay = EditOverlay::default();
+        let stone = pack_voxel(MAT_STONE, 0, 0, 0);
+        overlay.record(IVec3::ZERO, voxel_index(1, 1, 1), stone);
+        overlay.record(IVec3::ZERO, voxel_index(2, 2, 2), 0);
+        let mut chunk = stone_chunk();
+        assert!(overlay.apply(IVec3::ZERO, &mut chunk));
+        assert_eq!(overlay.edit_count(IVec3::ZERO), 1);
+        assert_eq!(chunk.voxel_at(2, 2, 2), 0);
+
+        // Restoring the last differing voxel leaves nothing to keep.
+        overlay.record(IVec3::ZERO, voxel_index(2, 2, 2), stone);
+        let mut chunk = stone_chunk();
+        assert!(!overlay.apply(IVec3::ZERO, &mut chunk));
+        assert_eq!(overlay.edited_chunk_count(), 0);
+    }
+}
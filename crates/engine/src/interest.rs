@@ -0,0 +1,72 @@
+use glam::{IVec3, Vec3};
+
+use crate::view_volume::ViewVolume;
+use crate::voxel::CHUNK_SIZE;
+
+/// A point besides the camera that chunks are streamed around, such as a
+/// player, an NPC or a cinematic target.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InterestPoint {
+    /// World position the volume is centered on.
+    pub position: Vec3,
+    /// Chunks streamed around `position`.
+    pub volume: ViewVolume,
+    /// Load priority multiplier. At 1 the point's chunks rank like the
+    /// camera's at the same distance; larger values defer them.
+    pub weight: f32,
+}
+
+/// An inclusive box of chunk coordinates that is loaded within the tick
+/// budget and never evicted while pinned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PinnedRegion {
+    pub min: IVec3,
+    pub max: IVec3,
+}
+
+impl PinnedRegion {
+    /// The region spanning the chunks at `a` and `b`, in either order.
+    #[must_use]
+    pub fn new(a: IVec3, b: IVec3) -> Self {
+        Self {
+            min: a.min(b),
+            max: a.max(b),
+        }
+    }
+
+    /// Whether the chunk at `coord` is in the region.
+    #[must_use]
+    pub fn contains(&self, coord: IVec3) -> bool {
+        coord.cmpge(self.min).all() && coord.cmple(self.max).all()
+    }
+
+    /// Chunks in the region, X fastest.
+    pub fn chunks(&self) -> impl Iterator<Item = IVec3> + use<> {
+        let (min, max) = (self.min, self.max);
+        (min.z..=max.z).flat_map(move |z| {
+            (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| IVec3::new(x, y, z)))
+        })
+    }
+
+    /// World position of the region's center.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max + IVec3::ONE).as_vec3() * 0.5 * CHUNK_SIZE as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pinned_region_orders_corners() {
+        let region = PinnedRegion::new(IVec3::new(2, 0, -1), IVec3::new(0, 1, 1));
+        assert_eq!(region.min, IVec3::new(0, 0, -1));
+        assert_eq!(region.chunks().count(), 3 * 2 * 3);
+        assert!(region.chunks().all(|c| region.contains(c)));
+        assert!(!region.contains(IVec3::new(3, 0, 0)));
+        assert_eq!(region.center(), Vec3::new(48.0, 32.0, 16.0));
+    }
+}
@@ -8,14 +8,28 @@ use web_sys::OffscreenCanvas;
 #[cfg(feature = "wasm")]
 use camera::{CameraIntent, EasingKind};
 
+pub mod biome;
 pub mod camera;
+pub mod chunk_codec;
 pub mod chunk_manager;
+pub mod chunk_payload;
+pub mod chunk_source;
 pub mod collision;
+pub mod dungeon;
+pub mod edit_overlay;
 pub mod error;
+pub mod interest;
+pub mod load_priority;
 pub mod map_features;
+pub mod map_rng;
+pub mod material;
+pub mod mutation;
+pub mod palette;
 pub mod particle_system;
 pub mod render;
+pub mod structures;
 pub mod terrain_grid;
+pub mod view_volume;
 pub mod voxel;
 
 #[cfg(feature = "wasm")]
@@ -121,12 +135,52 @@ pub fn animate_camera(
     );
 }
 
+/// Stream chunks from the chunk server at `url`; an empty `url` reverts to
+/// local generation.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn set_chunk_server(url: &str) {
+    with_renderer!(|renderer| renderer.set_chunk_server((!url.is_empty()).then_some(url)));
+}
+
 #[cfg(feature = "wasm")]
 #[wasm_bindgen]
 pub fn preload_view(x: f32, y: f32, z: f32) {
     with_renderer!(|renderer| renderer.preload_view(x, y, z));
 }
 
+/// Stream a `radius`-chunk cube around `(x, y, z)` under `name`, with load
+/// priority scaled by `weight` (1 ranks like the camera).
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn set_interest_point(name: &str, x: f32, y: f32, z: f32, radius: u32, weight: f32) {
+    with_renderer!(|renderer| renderer.set_interest_point(name, x, y, z, radius, weight));
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn remove_interest_point(name: &str) {
+    with_renderer!(|renderer| renderer.remove_interest_point(name));
+}
+
+/// Keep the chunks between two chunk coordinates (inclusive) loaded under
+/// `name` until [`unpin_chunk_region`] releases them.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn pin_chunk_region(name: &str, ax: i32, ay: i32, az: i32, bx: i32, by: i32, bz: i32) {
+    with_renderer!(|renderer| renderer.pin_chunk_region(
+        name,
+        glam::IVec3::new(ax, ay, az),
+        glam::IVec3::new(bx, by, bz)
+    ));
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn unpin_chunk_region(name: &str) {
+    with_renderer!(|renderer| renderer.unpin_chunk_region(name));
+}
+
 #[cfg(feature = "wasm")]
 #[wasm_bindgen]
 pub fn set_look_delta(dyaw: f32, dpitch: f32) {
@@ -182,6 +236,32 @@ pub fn is_chunk_loaded_at(cx: i32, cy: i32, cz: i32) -> bool {
     query_renderer!(|renderer| renderer.is_chunk_loaded(cx, cy, cz))
 }
 
+/// Rooms of the map's dungeon, seven values each: `[level, min_x, min_y,
+/// min_z, max_x, max_y, max_z]` (inclusive interior, `min_y` is the floor).
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+#[must_use]
+pub fn dungeon_rooms() -> Vec<i32> {
+    RENDERER.with(|r| {
+        r.borrow()
+            .as_ref()
+            .map_or_else(Vec::new, render::Renderer::dungeon_rooms)
+    })
+}
+
+/// Corridors of the map's dungeon as pairs of indices into
+/// [`dungeon_rooms`].
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+#[must_use]
+pub fn dungeon_connections() -> Vec<u32> {
+    RENDERER.with(|r| {
+        r.borrow()
+            .as_ref()
+            .map_or_else(Vec::new, render::Renderer::dungeon_connections)
+    })
+}
+
 /// Whether the voxel at the given world-space position is solid.
 /// Returns `false` for unloaded chunks or air.
 #[cfg(feature = "wasm")]
@@ -204,6 +284,20 @@ pub fn get_terrain_grid(cx: i32, cy: i32, cz: i32) -> Option<Vec<u8>> {
     })
 }
 
+/// Drains the chunks whose terrain grids changed after load because the
+/// chunk above or below them was loaded or mutated. Returns a flat
+/// `[cx, cy, cz, ...]` list; re-fetch each with [`get_terrain_grid`].
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+#[must_use]
+pub fn take_dirty_terrain_chunks() -> Vec<i32> {
+    RENDERER.with(|r| {
+        r.borrow_mut()
+            .as_mut()
+            .map_or_else(Vec::new, render::Renderer::take_dirty_terrain)
+    })
+}
+
 /// Updates the visibility mask used by the shader to dim tiles outside LOS.
 ///
 /// `origin_x` / `origin_z` are the world-space coordinates of the mask's
@@ -215,12 +309,79 @@ pub fn update_visibility_mask(origin_x: i32, origin_z: i32, grid_size: u32, data
     with_renderer!(|renderer| renderer.update_visibility_mask(origin_x, origin_z, grid_size, data));
 }
 
+/// Defines or replaces a voxel material at runtime.
+///
+/// `data` layout: `[r, g, b, solid, walkable, movement_cost, opacity, emissive]`
+/// (booleans are non-zero for true). Returns `false` if the layout is short,
+/// `id` is 0 (air), or the renderer is not initialized.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+#[must_use]
+pub fn define_material(id: u8, name: &str, data: &[f32]) -> bool {
+    RENDERER.with(|r| {
+        r.borrow_mut()
+            .as_mut()
+            .is_some_and(|renderer| renderer.define_material(id, name, data))
+    })
+}
+
+/// Returns the name, walkability and movement cost of every defined
+/// material, serialized as described in
+/// [`MaterialRegistry::terrain_table_bytes`](material::MaterialRegistry::terrain_table_bytes).
+/// Empty if the renderer is not initialized.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+#[must_use]
+pub fn material_terrain_table() -> Vec<u8> {
+    RENDERER.with(|r| {
+        r.borrow()
+            .as_ref()
+            .map_or_else(Vec::new, render::Renderer::material_terrain_table)
+    })
+}
+
 /// Mutate voxels in loaded chunks from a flat `i32` slice.
 /// Each group of 4 values is `[world_x, world_y, world_z, material_id]`.
+/// Returns the changed chunk coordinates as a flat `[cx, cy, cz, ...]` list.
 #[cfg(feature = "wasm")]
 #[wasm_bindgen]
-pub fn mutate_voxels(data: &[i32]) {
-    with_renderer!(|renderer| renderer.mutate_voxels(data));
+#[must_use]
+pub fn mutate_voxels(data: &[i32]) -> Vec<i32> {
+    RENDERER.with(|r| {
+        r.borrow_mut()
+            .as_mut()
+            .map_or_else(Vec::new, |renderer| renderer.mutate_voxels(data))
+    })
+}
+
+/// Set voxel gameplay flags (door, open, climbable, hazard, indestructible,
+/// liquid) from a flat `i32` slice. Each group of 4 values is
+/// `[world_x, world_y, world_z, flags]`. Returns the changed chunk
+/// coordinates as a flat `[cx, cy, cz, ...]` list.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+#[must_use]
+pub fn set_voxel_flags(data: &[i32]) -> Vec<i32> {
+    RENDERER.with(|r| {
+        r.borrow_mut()
+            .as_mut()
+            .map_or_else(Vec::new, |renderer| renderer.set_voxel_flags(data))
+    })
+}
+
+/// Fill a box (0), sphere (1), line (2) or voxel list (3) with `material_id`.
+/// See [`render::Renderer::mutate_region`] for the `params` layout of each
+/// shape. Returns the changed chunk coordinates as a flat `[cx, cy, cz, ...]`
+/// list.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+#[must_use]
+pub fn mutate_region(shape: u32, params: &[i32], material_id: u8) -> Vec<i32> {
+    RENDERER.with(|r| {
+        r.borrow_mut().as_mut().map_or_else(Vec::new, |renderer| {
+            renderer.mutate_region(shape, params, material_id)
+        })
+    })
 }
 
 /// Updates the dynamic light list from a flat f32 slice.
@@ -0,0 +1,185 @@
+use glam::{IVec3, Vec3};
+
+use crate::camera::Camera;
+use crate::voxel::CHUNK_SIZE;
+
+/// Radius of the sphere enclosing a chunk, in world units.
+#[allow(clippy::cast_precision_loss)]
+const CHUNK_RADIUS: f32 = CHUNK_SIZE as f32 * 0.866_025_4;
+
+/// Priority multiplier for chunks outside the view volume.
+pub const OFF_SCREEN_PENALTY: f32 = 8.0;
+/// Priority multiplier for chunks that are probably all air.
+pub const SKY_PENALTY: f32 = 4.0;
+/// How far ahead along the camera's velocity loading is centered, in ticks.
+pub const LEAD_TICKS: f32 = 30.0;
+
+/// How the camera projects the world onto the screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    /// Vertical field of view in radians.
+    Perspective { fov: f32 },
+    /// Half the view height in world units.
+    Orthographic { half_height: f32 },
+}
+
+/// The camera's view volume, used to load on-screen chunks first.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LoadView {
+    pub forward: Vec3,
+    pub right: Vec3,
+    pub up: Vec3,
+    pub projection: Projection,
+    /// Viewport width over height.
+    pub aspect: f32,
+}
+
+impl LoadView {
+    /// The perspective view of `camera`.
+    #[must_use]
+    pub fn perspective(camera: &Camera, aspect: f32) -> Self {
+        Self::with_projection(camera, aspect, Projection::Perspective { fov: camera.fov })
+    }
+
+    /// The orthographic view of `camera`, `half_height` world units tall
+    /// above and below the view axis.
+    #[must_use]
+    pub fn orthographic(camera: &Camera, aspect: f32, half_height: f32) -> Self {
+        Self::with_projection(camera, aspect, Projection::Orthographic { half_height })
+    }
+
+    fn with_projection(camera: &Camera, aspect: f32, projection: Projection) -> Self {
+        let (forward, right, up) = camera.orientation_vectors();
+        Self {
+            forward,
+            right,
+            up,
+            projection,
+            aspect,
+        }
+    }
+
+    /// Whether a sphere at `center` with `radius` overlaps the view volume
+    /// of a camera at `eye`. Conservative: spheres near the frustum corners
+    /// may be reported visible.
+    #[must_use]
+    pub fn contains_sphere(&self, eye: Vec3, center: Vec3, radius: f32) -> bool {
+        let v = center - eye;
+        let (x, y, z) = (v.dot(self.right), v.dot(self.up), v.dot(self.forward));
+        if z < -radius {
+            return false;
+        }
+        match self.projection {
+            Projection::Perspective { fov } => {
+                let tan_y = (fov * 0.5).tan();
+                let tan_x = tan_y * self.aspect;
+                let outside = |offset: f32, tan: f32| {
+                    (offset.abs() - z * tan) / (1.0 + tan * tan).sqrt() > radius
+                };
+                !outside(x, tan_x) && !outside(y, tan_y)
+            }
+            Projection::Orthographic { half_height } => {
+                x.abs() <= half_height * self.aspect + radius && y.abs() <= half_height + radius
+            }
+        }
+    }
+}
+
+/// Where loading is focused during one streaming tick.
+#[derive(Clone, Copy, Debug)]
+pub struct LoadFocus {
+    /// Camera position.
+    pub eye: Vec3,
+    /// Offset from `eye` toward where the camera is heading.
+    pub lead: Vec3,
+    /// View volume; without one every chunk counts as on screen.
+    pub view: Option<LoadView>,
+}
+
+impl LoadFocus {
+    /// Load priority of the chunk at `coord`; lower loads first.
+    ///
+    /// The base is the squared distance, in chunks, from the chunk center
+    /// to the led camera position, scaled up for chunks off screen or
+    /// `likely_sky`.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn priority(&self, coord: IVec3, likely_sky: bool) -> f32 {
+        let chunk = CHUNK_SIZE as f32;
+        let center = (coord.as_vec3() + 0.5) * chunk;
+        let mut score = ((center - self.eye - self.lead) / chunk).length_squared();
+        if let Some(view) = &self.view
+            && !view.contains_sphere(self.eye, center, CHUNK_RADIUS)
+        {
+            score *= OFF_SCREEN_PENALTY;
+        }
+        if likely_sky {
+            score *= SKY_PENALTY;
+        }
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Camera at the origin looking down -Z.
+    fn camera() -> Camera {
+        Camera {
+            position: Vec3::ZERO,
+            yaw: 0.0,
+            pitch: 0.0,
+            fov: 60.0_f32.to_radians(),
+        }
+    }
+
+    #[test]
+    fn perspective_view_rejects_chunks_behind_and_beside() {
+        let view = LoadView::perspective(&camera(), 1.0);
+        let r = CHUNK_RADIUS;
+        assert!(view.contains_sphere(Vec3::ZERO, Vec3::new(0.0, 0.0, -100.0), r));
+        assert!(!view.contains_sphere(Vec3::ZERO, Vec3::new(0.0, 0.0, 100.0), r));
+        assert!(!view.contains_sphere(Vec3::ZERO, Vec3::new(200.0, 0.0, -100.0), r));
+        // The camera's own chunk always overlaps.
+        assert!(view.contains_sphere(Vec3::ZERO, Vec3::new(0.0, 0.0, 10.0), r));
+    }
+
+    #[test]
+    fn orthographic_view_is_a_box() {
+        let view = LoadView::orthographic(&camera(), 2.0, 50.0);
+        let r = 1.0;
+        assert!(view.contains_sphere(Vec3::ZERO, Vec3::new(90.0, 0.0, -500.0), r));
+        assert!(!view.contains_sphere(Vec3::ZERO, Vec3::new(110.0, 0.0, -500.0), r));
+        assert!(!view.contains_sphere(Vec3::ZERO, Vec3::new(0.0, 60.0, -10.0), r));
+    }
+
+    #[test]
+    fn on_screen_chunks_outrank_closer_ones_behind() {
+        let focus = LoadFocus {
+            eye: Vec3::splat(16.0),
+            lead: Vec3::ZERO,
+            view: Some(LoadView::perspective(
+                &Camera {
+                    position: Vec3::splat(16.0),
+                    ..camera()
+                },
+                1.0,
+            )),
+        };
+        let ahead = focus.priority(IVec3::new(0, 0, -2), false);
+        let behind = focus.priority(IVec3::new(0, 0, 2), false);
+        assert!(ahead < behind);
+        assert!(focus.priority(IVec3::new(0, 0, -2), true) > ahead);
+    }
+
+    #[test]
+    fn lead_favors_chunks_in_the_direction_of_travel() {
+        let focus = LoadFocus {
+            eye: Vec3::splat(16.0),
+            lead: Vec3::new(64.0, 0.0, 0.0),
+            view: None,
+        };
+        assert!(focus.priority(IVec3::X, false) < focus.priority(IVec3::NEG_X, false));
+    }
+}
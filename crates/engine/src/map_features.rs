@@ -1,44 +1,148 @@
+use std::sync::Arc;
+
 use glam::{IVec3, Vec3};
+use noise::{NoiseFn, Perlin};
 
+use crate::biome::BiomeMap;
+use crate::dungeon::{Dungeon, DungeonParams};
+use crate::map_rng::{FEATURE_SHADE_VARIATION, MapRng};
+use crate::structures::{StructurePlacer, stamp_structures};
+use crate::view_volume::SurfaceHeight;
 use crate::voxel::{
-    CHUNK_SIZE, Chunk, MAT_AIR, MAT_STONE, TEST_GRID_SEED, material_id, pack_voxel,
-    terrain_material, voxel_index,
+    CHUNK_SIZE, Chunk, MAT_AIR, MAT_STONE, MAT_WATER, MAX_WETNESS, TEST_GRID_SEED, VARIANT_COUNT,
+    flags, material_id, pack_brightness, pack_param1, pack_voxel, terrain_height, terrain_material,
+    voxel_index,
 };
 
 /// A composable post-processing transform applied to a chunk after terrain generation.
-pub trait MapFeature: Send {
+pub trait MapFeature: Send + Sync {
     fn apply(&self, chunk: &mut Chunk, chunk_coord: IVec3);
 }
 
-/// Configuration for map generation: seed, composable features, and default camera.
+/// Shared features, such as a [`Dungeon`] whose layout is also queried by
+/// the game.
+impl<T: MapFeature + ?Sized> MapFeature for Arc<T> {
+    fn apply(&self, chunk: &mut Chunk, chunk_coord: IVec3) {
+        (**self).apply(chunk, chunk_coord);
+    }
+}
+
+/// Configuration for map generation: seed, base terrain, structures,
+/// composable features, and default camera.
 pub struct MapConfig {
     pub seed: u32,
+    /// Base terrain from biomes; `None` generates the single-Perlin terrain
+    /// of [`Chunk::new_terrain_at`].
+    pub biomes: Option<BiomeMap>,
+    /// The dungeon among `features`, kept for layout queries. Set by
+    /// [`with_dungeon`](Self::with_dungeon).
+    pub dungeon: Option<Arc<Dungeon>>,
+    /// Structures stamped into the base terrain before `features` run. Added
+    /// by [`with_structures`](Self::with_structures).
+    pub structures: Vec<Box<dyn StructurePlacer>>,
     pub features: Vec<Box<dyn MapFeature>>,
     pub default_camera_position: Vec3,
     pub default_look_target: Vec3,
 }
 
 impl MapConfig {
-    /// Generate a chunk at the given coordinate by running terrain generation
-    /// followed by each feature in order.
+    /// The default feature set and camera, generated from `seed`.
+    #[must_use]
+    pub fn with_seed(seed: u32) -> Self {
+        Self {
+            seed,
+            biomes: None,
+            dungeon: None,
+            structures: vec![],
+            features: vec![
+                Box::new(FlattenNearOrigin),
+                Box::new(PlaceWalls),
+                Box::new(ShadeVariation { seed }),
+            ],
+            default_camera_position: Vec3::new(-8.0, 55.0, -8.0),
+            default_look_target: Vec3::new(16.0, 24.0, 16.0),
+        }
+    }
+
+    /// Carve [`CarveCaves::new`] tunnels and caverns under the terrain.
+    #[must_use]
+    pub fn with_caves(mut self) -> Self {
+        let caves = CarveCaves::new(self.seed);
+        self.insert_feature(Box::new(caves));
+        self
+    }
+
+    /// Build a [`Dungeon`] shaped by `params` into the terrain and keep it
+    /// on [`dungeon`](Self::dungeon) for layout queries.
+    #[must_use]
+    pub fn with_dungeon(mut self, params: DungeonParams) -> Self {
+        let dungeon = Arc::new(Dungeon::generate(self.seed, params));
+        self.dungeon = Some(Arc::clone(&dungeon));
+        self.insert_feature(Box::new(dungeon));
+        self
+    }
+
+    /// Stamp the structures of each placer in `placers` into the base
+    /// terrain, after any already configured.
+    #[must_use]
+    pub fn with_structures(mut self, placers: Vec<Box<dyn StructurePlacer>>) -> Self {
+        self.structures.extend(placers);
+        self
+    }
+
+    /// Add `feature` ahead of the last feature, the default
+    /// [`ShadeVariation`] pass, so the voxels it places are shaded too.
+    fn insert_feature(&mut self, feature: Box<dyn MapFeature>) {
+        let at = self.features.len().saturating_sub(1);
+        self.features.insert(at, feature);
+    }
+
+    /// Generate the base terrain from `biomes` instead of a single Perlin
+    /// height field.
+    #[must_use]
+    pub fn with_biomes(mut self, biomes: BiomeMap) -> Self {
+        self.biomes = Some(biomes);
+        self
+    }
+
+    /// Estimated surface height (world y) at a world `(x, z)` column: the
+    /// base terrain height, ignoring feature edits such as the flattened
+    /// spawn area.
+    #[must_use]
+    pub fn surface_height(&self) -> Box<SurfaceHeight> {
+        if let Some(biomes) = self.biomes.clone() {
+            return Box::new(move |x, z| biomes.surface_height(x, z));
+        }
+        let perlin = Perlin::new(self.seed);
+        Box::new(move |x, z| terrain_height(&perlin, x, z))
+    }
+
+    /// Generate a chunk at the given coordinate by running terrain generation,
+    /// stamping in every structure that overlaps it, then running each
+    /// feature in order.
     #[must_use]
     pub fn generate_chunk(&self, coord: IVec3) -> Chunk {
-        let mut chunk = Chunk::new_terrain_at(self.seed, coord);
+        let mut chunk = match &self.biomes {
+            Some(biomes) => biomes.generate_chunk(coord),
+            None => Chunk::new_terrain_at(self.seed, coord),
+        };
+        if !self.structures.is_empty() {
+            let surface = self.surface_height();
+            for placer in &self.structures {
+                stamp_structures(placer.as_ref(), self.seed, &*surface, &mut chunk, coord);
+            }
+        }
         for feature in &self.features {
             feature.apply(&mut chunk, coord);
         }
+        chunk.compact();
         chunk
     }
 }
 
 impl Default for MapConfig {
     fn default() -> Self {
-        Self {
-            seed: TEST_GRID_SEED,
-            features: vec![Box::new(FlattenNearOrigin), Box::new(PlaceWalls)],
-            default_camera_position: Vec3::new(-8.0, 55.0, -8.0),
-            default_look_target: Vec3::new(16.0, 24.0, 16.0),
-        }
+        Self::with_seed(TEST_GRID_SEED)
     }
 }
 
@@ -74,10 +178,10 @@ impl FlattenNearOrigin {
             let world_y = y_offset + y as i32;
             let idx = voxel_index(x, y, z);
             if world_y > target_world_y {
-                chunk.voxels[idx] = pack_voxel(MAT_AIR, 0, 0, 0);
+                chunk.set(idx, pack_voxel(MAT_AIR, 0, 0, 0));
             } else {
                 let mat = terrain_material(world_y, target_world_y);
-                chunk.voxels[idx] = pack_voxel(mat, 0, 0, 0);
+                chunk.set(idx, pack_voxel(mat, 0, 0, 0));
             }
         }
     }
@@ -205,10 +309,220 @@ impl MapFeature for PlaceWalls {
     }
 }
 
+/// World-space frequency of the noise fields whose shared zero band forms
+/// [`CarveCaves`] tunnels.
+const TUNNEL_FREQUENCY: f64 = 1.0 / 40.0;
+
+/// World-space frequency of the noise whose peaks form cavern chambers.
+const CAVERN_FREQUENCY: f64 = 1.0 / 28.0;
+
+/// Vertical stretch of the cave noise; tunnels run mostly horizontally.
+const CAVE_VERTICAL_STRETCH: f64 = 2.0;
+
+/// Voxels over which caves narrow to nothing at the ceiling and floor.
+const CAVE_TAPER: f64 = 8.0;
+
+/// Seed offsets for the three cave noise fields.
+const CAVE_SEEDS: [u32; 3] = [0xCA7E_0001, 0xCA7E_0002, 0xCA7E_0003];
+
+/// Carves winding tunnels and caverns out of solid terrain with 3D noise.
+///
+/// Tunnels follow the curves where two noise fields are both near zero;
+/// caverns open where a third peaks. Noise is sampled in world space, so
+/// caves continue seamlessly across chunk borders. Nothing is carved at or
+/// above `ceiling`, below `floor`, or within `BLEND_RADIUS` of the origin,
+/// keeping the spawn platform intact.
+pub struct CarveCaves {
+    pub seed: u32,
+    /// How much of the rock between `floor` and `ceiling` is carved, from 0
+    /// (none) to 1 (about 40% hollow). 0.3 gives a sparse tunnel network
+    /// carving about 3%.
+    pub density: f64,
+    /// World y at and above which nothing is carved.
+    pub ceiling: i32,
+    /// World y below which nothing is carved.
+    pub floor: i32,
+}
+
+impl CarveCaves {
+    /// Sparse tunnels from 8 voxels below the flattened spawn height down
+    /// to y = -96.
+    #[must_use]
+    pub fn new(seed: u32) -> Self {
+        Self {
+            seed,
+            density: 0.3,
+            ceiling: FLATTEN_HEIGHT - 8,
+            floor: -96,
+        }
+    }
+
+    /// Whether the voxel at world `p` is carved.
+    #[must_use]
+    pub fn is_cave(&self, p: IVec3, noise: &[Perlin; 3]) -> bool {
+        if p.y >= self.ceiling
+            || p.y < self.floor
+            || f64::from(p.x.abs().max(p.z.abs())) <= BLEND_RADIUS
+        {
+            return false;
+        }
+        let taper = (f64::from(self.ceiling - p.y) / CAVE_TAPER)
+            .min(f64::from(p.y - self.floor + 1) / CAVE_TAPER)
+            .min(1.0);
+        let density = self.density.clamp(0.0, 1.0) * taper;
+        if density <= 0.0 {
+            return false;
+        }
+        let sample = |perlin: &Perlin, frequency: f64| {
+            perlin.get([
+                f64::from(p.x) * frequency,
+                f64::from(p.y) * frequency * CAVE_VERTICAL_STRETCH,
+                f64::from(p.z) * frequency,
+            ])
+        };
+        let [a, b, c] = noise;
+        let tunnel_width = 0.25 * density;
+        let tunnel = sample(a, TUNNEL_FREQUENCY).abs() < tunnel_width
+            && sample(b, TUNNEL_FREQUENCY).abs() < tunnel_width;
+        tunnel || sample(c, CAVERN_FREQUENCY) > 0.9 - 0.6 * density
+    }
+
+    /// The noise fields [`is_cave`](Self::is_cave) samples.
+    #[must_use]
+    pub fn noise(&self) -> [Perlin; 3] {
+        CAVE_SEEDS.map(|offset| Perlin::new(self.seed ^ offset))
+    }
+}
+
+impl MapFeature for CarveCaves {
+    #[allow(clippy::cast_possible_wrap)]
+    fn apply(&self, chunk: &mut Chunk, chunk_coord: IVec3) {
+        let cs = CHUNK_SIZE as i32;
+        let origin = chunk_coord * cs;
+        if origin.y >= self.ceiling || origin.y + cs <= self.floor {
+            return;
+        }
+        let noise = self.noise();
+        for z in 0..CHUNK_SIZE {
+            for y in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    if material_id(chunk.voxel_at(x, y, z)) == MAT_AIR {
+                        continue;
+                    }
+                    let world = origin + IVec3::new(x as i32, y as i32, z as i32);
+                    if self.is_cave(world, &noise) {
+                        chunk.set_voxel(x, y, z, pack_voxel(MAT_AIR, 0, 0, 0));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// World-space frequency of the brightness patches laid down by
+/// [`ShadeVariation`].
+const SHADE_PATCH_FREQUENCY: f64 = 0.08;
+
+/// Peak brightness offset of the noise patches.
+const SHADE_PATCH_AMPLITUDE: f64 = 40.0;
+
+/// Peak brightness offset of the per-voxel jitter added on top.
+const SHADE_JITTER: i32 = 12;
+
+/// Brightness offsets a shaded voxel may take. Patches plus jitter snap to
+/// the nearest one, so a chunk holds few distinct voxel values and stays
+/// palette-compressed.
+const SHADE_LEVELS: [i8; 3] = [-24, 0, 24];
+
+/// Breaks up flat palette colors by writing per-voxel shading parameters:
+/// a brightness offset from low-frequency noise plus per-voxel jitter,
+/// snapped to one of `SHADE_LEVELS`, a random variant index, and full
+/// wetness on voxels touching water within the chunk. Material ids and flags
+/// are left untouched. Runs last so it sees the final terrain.
+pub struct ShadeVariation {
+    pub seed: u32,
+}
+
+impl ShadeVariation {
+    fn touches_water(chunk: &Chunk, x: usize, y: usize, z: usize) -> bool {
+        let last = CHUNK_SIZE - 1;
+        let neighbors = [
+            (x > 0).then(|| (x - 1, y, z)),
+            (x < last).then(|| (x + 1, y, z)),
+            (y > 0).then(|| (x, y - 1, z)),
+            (y < last).then(|| (x, y + 1, z)),
+            (z > 0).then(|| (x, y, z - 1)),
+            (z < last).then(|| (x, y, z + 1)),
+        ];
+        neighbors
+            .into_iter()
+            .flatten()
+            .any(|(nx, ny, nz)| material_id(chunk.voxel_at(nx, ny, nz)) == MAT_WATER)
+    }
+}
+
+/// The entry of [`SHADE_LEVELS`] closest to `offset`.
+fn shade_level(offset: i32) -> i8 {
+    SHADE_LEVELS
+        .into_iter()
+        .min_by_key(|&level| (i32::from(level) - offset).abs())
+        .unwrap_or(0)
+}
+
+impl MapFeature for ShadeVariation {
+    #[allow(clippy::cast_possible_wrap)]
+    fn apply(&self, chunk: &mut Chunk, chunk_coord: IVec3) {
+        let perlin = Perlin::new(self.seed);
+        let origin = chunk_coord * CHUNK_SIZE as i32;
+
+        for z in 0..CHUNK_SIZE {
+            for y in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    let voxel = chunk.voxel_at(x, y, z);
+                    let mat = material_id(voxel);
+                    if mat == MAT_AIR || mat == MAT_WATER {
+                        continue;
+                    }
+                    let world = origin + IVec3::new(x as i32, y as i32, z as i32);
+                    let h = MapRng::hash(self.seed, FEATURE_SHADE_VARIATION, world);
+
+                    let patch = perlin.get([
+                        f64::from(world.x) * SHADE_PATCH_FREQUENCY,
+                        f64::from(world.y) * SHADE_PATCH_FREQUENCY,
+                        f64::from(world.z) * SHADE_PATCH_FREQUENCY,
+                    ]) * SHADE_PATCH_AMPLITUDE;
+                    let jitter = (h % (2 * SHADE_JITTER as u32 + 1)) as i32 - SHADE_JITTER;
+                    let brightness = shade_level(patch as i32 + jitter);
+
+                    let wetness = if Self::touches_water(chunk, x, y, z) {
+                        MAX_WETNESS
+                    } else {
+                        0
+                    };
+                    let variant = ((h >> 16) % u32::from(VARIANT_COUNT)) as u8;
+
+                    chunk.set_voxel(
+                        x,
+                        y,
+                        z,
+                        pack_voxel(
+                            mat,
+                            pack_brightness(brightness),
+                            pack_param1(wetness, 0, variant),
+                            flags(voxel),
+                        ),
+                    );
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::voxel::{CHUNK_SIZE, material_id, voxel_index};
+    use crate::structures::{ScatterBoulders, Structure, structures_in_region};
+    use crate::voxel::{CHUNK_SIZE, brightness_offset, material_id, variant, voxel_index, wetness};
 
     const FLAT_HEIGHT: i32 = 24;
 
@@ -221,11 +535,132 @@ mod tests {
         );
     }
 
+    #[test]
+    fn surface_height_matches_terrain_away_from_spawn() {
+        let config = MapConfig::default();
+        let height = config.surface_height();
+        // World column (500, 300): local (20, 12) in chunk column (15, 9).
+        let (lx, lz) = (20, 12);
+        let top = (0..2)
+            .flat_map(|cy| {
+                let chunk = config.generate_chunk(IVec3::new(15, cy, 9));
+                (0..CHUNK_SIZE)
+                    .filter(move |&y| chunk.voxel_at(lx, y, lz) != 0)
+                    .map(move |y| cy.unsigned_abs() as usize * CHUNK_SIZE + y)
+            })
+            .max();
+        assert_eq!(top, usize::try_from(height(500, 300)).ok());
+    }
+
+    #[test]
+    fn biome_terrain_replaces_perlin_terrain_away_from_spawn() {
+        let config = MapConfig::default().with_biomes(BiomeMap::standard(TEST_GRID_SEED));
+        let (x, z) = (1000, -700);
+        let surface_y = config.surface_height()(x, z);
+        let coord = IVec3::new(x.div_euclid(32), surface_y.div_euclid(32), z.div_euclid(32));
+        let chunk = config.generate_chunk(coord);
+        let local = |v: i32| usize::try_from(v.rem_euclid(32)).unwrap();
+        let top = material_id(chunk.voxel_at(local(x), local(surface_y), local(z)));
+        assert_eq!(top, config.biomes.as_ref().unwrap().biome_at(x, z).surface);
+    }
+
+    fn carved(caves: &CarveCaves, coord: IVec3) -> Chunk {
+        let mut chunk = Chunk::uniform(pack_voxel(MAT_STONE, 0, 0, 0));
+        caves.apply(&mut chunk, coord);
+        chunk
+    }
+
+    fn air_count(chunk: &Chunk) -> usize {
+        chunk.iter().filter(|&v| material_id(v) == MAT_AIR).count()
+    }
+
+    #[test]
+    #[allow(clippy::cast_possible_wrap)]
+    fn caves_are_seamless_across_chunk_borders() {
+        let caves = CarveCaves::new(TEST_GRID_SEED);
+        let noise = caves.noise();
+        let (left, right) = (IVec3::new(9, -2, 3), IVec3::new(10, -2, 3));
+        let (a, b) = (carved(&caves, left), carved(&caves, right));
+        assert!(air_count(&a) > 0 && air_count(&b) > 0);
+        // Both sides of the x = 320 border carve exactly the world-space caves.
+        for z in 0..CHUNK_SIZE {
+            for y in 0..CHUNK_SIZE {
+                let world = |cx: i32, lx: usize| {
+                    IVec3::new(cx * 32 + lx as i32, -64 + y as i32, 96 + z as i32)
+                };
+                let expect_air = |p| caves.is_cave(p, &noise);
+                let edge_a = material_id(a.voxel_at(CHUNK_SIZE - 1, y, z)) == MAT_AIR;
+                let edge_b = material_id(b.voxel_at(0, y, z)) == MAT_AIR;
+                assert_eq!(edge_a, expect_air(world(9, CHUNK_SIZE - 1)));
+                assert_eq!(edge_b, expect_air(world(10, 0)));
+            }
+        }
+    }
+
+    #[test]
+    fn caves_respect_depth_and_spawn_limits() {
+        let caves = CarveCaves::new(TEST_GRID_SEED);
+        let far = IVec3::new(12, -1, -7);
+        assert!(air_count(&carved(&caves, far)) > 0);
+        // Above the ceiling, below the floor and under spawn stay solid.
+        assert_eq!(air_count(&carved(&caves, IVec3::new(12, 1, -7))), 0);
+        assert_eq!(air_count(&carved(&caves, IVec3::new(12, -4, -7))), 0);
+        assert_eq!(air_count(&carved(&caves, IVec3::new(1, -1, -2))), 0);
+        let none = CarveCaves {
+            density: 0.0,
+            ..CarveCaves::new(TEST_GRID_SEED)
+        };
+        assert_eq!(air_count(&carved(&none, far)), 0);
+    }
+
+    #[test]
+    fn cave_density_scales_carved_volume() {
+        let carved_at = |density| {
+            let caves = CarveCaves {
+                density,
+                ..CarveCaves::new(TEST_GRID_SEED)
+            };
+            (5..9)
+                .map(|x| air_count(&carved(&caves, IVec3::new(x, -2, 4))))
+                .sum::<usize>()
+        };
+        assert!(carved_at(0.15) < carved_at(0.3));
+        assert!(carved_at(0.3) < carved_at(0.8));
+    }
+
+    #[test]
+    fn caves_are_opt_in() {
+        let plain = MapConfig::default();
+        let caves = MapConfig::default().with_caves();
+        assert_eq!(caves.features.len(), plain.features.len() + 1);
+        let more_air = (5..9).any(|x| {
+            let coord = IVec3::new(x, -2, 4);
+            air_count(&caves.generate_chunk(coord)) > air_count(&plain.generate_chunk(coord))
+        });
+        assert!(more_air, "with_caves should carve under the terrain");
+    }
+
+    #[test]
+    fn caves_give_terrain_columns_several_surfaces() {
+        use crate::material::MaterialRegistry;
+        use crate::terrain_grid::TerrainGrid;
+        let caves = CarveCaves::new(TEST_GRID_SEED);
+        let materials = MaterialRegistry::default();
+        let layered = (5..9).any(|x| {
+            let grid = TerrainGrid::from_chunk(&carved(&caves, IVec3::new(x, -2, 4)), &materials);
+            (0..CHUNK_SIZE).any(|z| (0..CHUNK_SIZE).any(|x| grid.surfaces_at(x, z).len() > 1))
+        });
+        assert!(
+            layered,
+            "cave floors should add walkable surfaces under the roof"
+        );
+    }
+
     #[test]
     fn generate_chunk_returns_32_cubed_voxels() {
         let config = MapConfig::default();
         let chunk = config.generate_chunk(IVec3::ZERO);
-        assert_eq!(chunk.voxels.len(), CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE);
+        assert_eq!(chunk.iter().count(), CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE);
     }
 
     #[test]
@@ -233,6 +668,9 @@ mod tests {
         let coord = IVec3::new(2, 0, 2);
         let config = MapConfig {
             seed: TEST_GRID_SEED,
+            biomes: None,
+            dungeon: None,
+            structures: vec![],
             features: vec![],
             default_camera_position: Vec3::ZERO,
             default_look_target: Vec3::ZERO,
@@ -240,7 +678,7 @@ mod tests {
         let generated = config.generate_chunk(coord);
         let raw = Chunk::new_terrain_at(TEST_GRID_SEED, coord);
         assert_eq!(
-            generated.voxels, raw.voxels,
+            generated, raw,
             "MapConfig with no features should produce identical voxels to raw terrain"
         );
     }
@@ -258,7 +696,7 @@ mod tests {
             for x in 0..CHUNK_SIZE {
                 let surface_y = (0..CHUNK_SIZE)
                     .rev()
-                    .find(|&y| material_id(chunk.voxels[voxel_index(x, y, z)]) != 0)
+                    .find(|&y| material_id(chunk.get(voxel_index(x, y, z))) != 0)
                     .expect("column should have solid voxels");
                 assert_eq!(
                     surface_y, FLAT_HEIGHT as usize,
@@ -277,10 +715,7 @@ mod tests {
         let far_coord = IVec3::new(3, 0, 3); // world x=96..128, well past blend
         let flattened = config.generate_chunk(far_coord);
         let raw = Chunk::new_terrain_at(config.seed, far_coord);
-        assert_eq!(
-            flattened.voxels, raw.voxels,
-            "chunks far from origin should be unchanged"
-        );
+        assert_eq!(flattened, raw, "chunks far from origin should be unchanged");
     }
 
     #[test]
@@ -298,10 +733,10 @@ mod tests {
             for x in 0..CHUNK_SIZE {
                 let raw_h = (0..CHUNK_SIZE)
                     .rev()
-                    .find(|&y| material_id(raw.voxels[voxel_index(x, y, z)]) != 0);
+                    .find(|&y| material_id(raw.get(voxel_index(x, y, z))) != 0);
                 let blended_h = (0..CHUNK_SIZE)
                     .rev()
-                    .find(|&y| material_id(blended.voxels[voxel_index(x, y, z)]) != 0);
+                    .find(|&y| material_id(blended.get(voxel_index(x, y, z))) != 0);
                 if raw_h != blended_h {
                     any_differs_from_raw = true;
                 }
@@ -331,7 +766,7 @@ mod tests {
         let (x, y, z) = (8_usize, 25_usize, 8_usize);
         let idx = voxel_index(x, y, z);
         assert_eq!(
-            material_id(chunk.voxels[idx]),
+            material_id(chunk.get(idx)),
             MAT_STONE,
             "wall voxel at world (8,25,8) should be MAT_STONE"
         );
@@ -351,8 +786,132 @@ mod tests {
         let chunk_with = with_walls.generate_chunk(far_coord);
         let chunk_without = without_walls.generate_chunk(far_coord);
         assert_eq!(
-            chunk_with.voxels, chunk_without.voxels,
+            chunk_with, chunk_without,
             "PlaceWalls should not modify chunks far from origin"
         );
     }
+
+    #[test]
+    fn shade_variation_keeps_materials_and_varies_params() {
+        let coord = IVec3::new(2, 0, 2);
+        let plain = MapConfig {
+            features: vec![],
+            ..MapConfig::default()
+        };
+        let shaded = MapConfig {
+            features: vec![Box::new(ShadeVariation {
+                seed: TEST_GRID_SEED,
+            })],
+            ..MapConfig::default()
+        };
+        let raw = plain.generate_chunk(coord);
+        let chunk = shaded.generate_chunk(coord);
+        let mut brightness = std::collections::HashSet::new();
+        let mut variants = std::collections::HashSet::new();
+        for (before, after) in raw.iter().zip(chunk.iter()) {
+            assert_eq!(material_id(before), material_id(after));
+            if material_id(after) == MAT_AIR {
+                assert_eq!(after, 0, "air must stay all-zero");
+            } else {
+                brightness.insert(brightness_offset(after));
+                variants.insert(variant(after));
+            }
+        }
+        assert_eq!(
+            brightness.len(),
+            SHADE_LEVELS.len(),
+            "brightness should vary across voxels"
+        );
+        assert!(variants.len() > 1, "variants should vary across voxels");
+        assert_eq!(chunk, shaded.generate_chunk(coord));
+    }
+
+    #[test]
+    fn shade_variation_wets_voxels_next_to_water() {
+        let mut chunk = Chunk::default();
+        chunk.set_voxel(4, 4, 4, pack_voxel(MAT_STONE, 0, 0, 0));
+        chunk.set_voxel(4, 5, 4, pack_voxel(MAT_WATER, 0, 0, 0));
+        chunk.set_voxel(9, 4, 4, pack_voxel(MAT_STONE, 0, 0, 0));
+        ShadeVariation { seed: 1 }.apply(&mut chunk, IVec3::ZERO);
+        assert_eq!(wetness(chunk.voxel_at(4, 4, 4)), MAX_WETNESS);
+        assert_eq!(wetness(chunk.voxel_at(9, 4, 4)), 0);
+        assert_eq!(chunk.voxel_at(4, 5, 4), pack_voxel(MAT_WATER, 0, 0, 0));
+    }
+
+    fn boulders() -> Vec<Box<dyn StructurePlacer>> {
+        vec![Box::new(ScatterBoulders { per_region: 3 })]
+    }
+
+    #[test]
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    fn boulders_are_whole_in_every_chunk_they_cross() {
+        let config = MapConfig {
+            features: vec![],
+            ..MapConfig::default().with_structures(boulders())
+        };
+        let surface = config.surface_height();
+        let cs = CHUNK_SIZE as i32;
+        let crosses = |s: &Structure| {
+            let (min, max) = s.bounds().unwrap();
+            min.div_euclid(IVec3::splat(cs)) != max.div_euclid(IVec3::splat(cs))
+        };
+        let boulder = (2..8)
+            .flat_map(|r| {
+                structures_in_region(
+                    &*config.structures[0],
+                    config.seed,
+                    glam::IVec2::new(r, 1),
+                    &*surface,
+                )
+            })
+            .find(crosses)
+            .expect("some boulder should cross a chunk border");
+        let mut chunks = std::collections::HashMap::new();
+        for &(pos, _) in boulder.blocks() {
+            let coord = pos.div_euclid(IVec3::splat(cs));
+            let chunk = chunks
+                .entry(coord)
+                .or_insert_with(|| config.generate_chunk(coord));
+            let local = (pos - coord * cs).as_uvec3();
+            let voxel = chunk.voxel_at(local.x as usize, local.y as usize, local.z as usize);
+            assert_eq!(
+                material_id(voxel),
+                MAT_STONE,
+                "missing boulder block at {pos}"
+            );
+        }
+        assert!(chunks.len() > 1);
+    }
+
+    #[test]
+    fn generation_does_not_depend_on_chunk_order() {
+        let coords = [
+            IVec3::new(3, 0, 2),
+            IVec3::new(-4, 0, 5),
+            IVec3::new(2, 0, 2),
+        ];
+        let full = || {
+            MapConfig::default()
+                .with_caves()
+                .with_dungeon(DungeonParams::default())
+                .with_structures(boulders())
+        };
+        let config = full();
+        let forward: Vec<Chunk> = coords.iter().map(|&c| config.generate_chunk(c)).collect();
+        let fresh = full();
+        for (&coord, chunk) in coords.iter().zip(&forward).rev() {
+            assert_eq!(&fresh.generate_chunk(coord), chunk, "chunk {coord} differs");
+        }
+    }
+
+    #[test]
+    fn default_config_chunks_stay_palette_compressed() {
+        let config = MapConfig::default();
+        for x in -1..=3 {
+            let surface = config.generate_chunk(IVec3::new(x * 4, 0, 3));
+            assert!(surface.storage().bits_per_voxel() <= 8, "surface chunk {x}");
+            let deep = config.generate_chunk(IVec3::new(x * 4, -2, 3));
+            assert!(deep.storage().bits_per_voxel() <= 4, "deep chunk {x}");
+        }
+    }
 }
@@ -0,0 +1,181 @@
+use glam::{IVec2, IVec3};
+
+/// Feature ids keying [`MapRng`] streams. Every source of map randomness has
+/// its own, so two features never draw correlated values at one position.
+pub const FEATURE_SHADE_VARIATION: u32 = 1;
+pub const FEATURE_BIOME_DECORATION: u32 = 2;
+pub const FEATURE_DUNGEON: u32 = 3;
+pub const FEATURE_BOULDERS: u32 = 4;
+
+/// Key domains, so a chunk coordinate, a region and a world position with
+/// equal components still give independent streams.
+const DOMAIN_FEATURE: u64 = 0;
+const DOMAIN_CHUNK: u64 = 1;
+const DOMAIN_REGION: u64 = 2;
+const DOMAIN_POSITION: u64 = 3;
+
+const GOLDEN_GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Deterministic random stream for map generation, keyed by the world seed,
+/// a feature id and optionally a chunk, region or world position.
+///
+/// A stream depends only on its key, never on which chunks were generated
+/// before, so the client, the chunk server and tests draw identical values
+/// whatever order chunks are requested in. Draws follow `SplitMix64`.
+#[derive(Clone, Debug)]
+pub struct MapRng(u64);
+
+impl MapRng {
+    /// The stream of a whole feature, for layouts generated once per map.
+    #[must_use]
+    pub fn new(seed: u32, feature: u32) -> Self {
+        Self::keyed(seed, feature, DOMAIN_FEATURE, IVec3::ZERO)
+    }
+
+    /// The stream of one chunk.
+    #[must_use]
+    pub fn for_chunk(seed: u32, feature: u32, chunk_coord: IVec3) -> Self {
+        Self::keyed(seed, feature, DOMAIN_CHUNK, chunk_coord)
+    }
+
+    /// The stream of an x/z region, in region coordinates.
+    #[must_use]
+    pub fn for_region(seed: u32, feature: u32, region: IVec2) -> Self {
+        Self::keyed(
+            seed,
+            feature,
+            DOMAIN_REGION,
+            IVec3::new(region.x, 0, region.y),
+        )
+    }
+
+    /// The stream of one world voxel position.
+    #[must_use]
+    pub fn for_position(seed: u32, feature: u32, pos: IVec3) -> Self {
+        Self::keyed(seed, feature, DOMAIN_POSITION, pos)
+    }
+
+    /// A single hash of a world voxel position, for per-voxel jitter
+    /// without building a stream.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn hash(seed: u32, feature: u32, pos: IVec3) -> u32 {
+        (Self::for_position(seed, feature, pos).next_u64() >> 32) as u32
+    }
+
+    fn keyed(seed: u32, feature: u32, domain: u64, key: IVec3) -> Self {
+        let mut h = mix(u64::from(seed) << 32 | u64::from(feature));
+        let [x, y, z] = key.to_array().map(|c| u64::from(c.cast_unsigned()));
+        for word in [domain, x, y, z] {
+            h = mix(h.wrapping_add(GOLDEN_GAMMA) ^ word);
+        }
+        Self(h)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(GOLDEN_GAMMA);
+        mix(self.0)
+    }
+
+    /// Uniform in `[0, 1)`.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn coin(&mut self) -> bool {
+        self.next_u64() & 1 == 0
+    }
+
+    /// Uniform in `0..n`; `0` when `n` is 0.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            return 0;
+        }
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Uniform in `lo..=hi`; `lo` when the range is empty.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    pub fn range(&mut self, lo: i32, hi: i32) -> i32 {
+        if hi <= lo {
+            return lo;
+        }
+        // The span of the full i32 range needs 33 bits.
+        let span = (i64::from(hi) - i64::from(lo) + 1).unsigned_abs();
+        (i64::from(lo) + (self.next_u64() % span) as i64) as i32
+    }
+}
+
+/// `SplitMix64` finalizer.
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first(mut rng: MapRng) -> u64 {
+        rng.next_u64()
+    }
+
+    #[test]
+    fn streams_depend_only_on_their_key() {
+        let chunk = IVec3::new(-2, 1, 7);
+        assert_eq!(
+            first(MapRng::for_chunk(5, FEATURE_DUNGEON, chunk)),
+            first(MapRng::for_chunk(5, FEATURE_DUNGEON, chunk))
+        );
+        let draws = [
+            first(MapRng::for_chunk(5, FEATURE_DUNGEON, chunk)),
+            first(MapRng::for_chunk(6, FEATURE_DUNGEON, chunk)),
+            first(MapRng::for_chunk(5, FEATURE_BOULDERS, chunk)),
+            first(MapRng::for_chunk(5, FEATURE_DUNGEON, IVec3::new(7, 1, -2))),
+            first(MapRng::for_position(5, FEATURE_DUNGEON, chunk)),
+            first(MapRng::for_region(5, FEATURE_DUNGEON, IVec2::new(-2, 7))),
+            first(MapRng::new(5, FEATURE_DUNGEON)),
+        ];
+        for (i, a) in draws.iter().enumerate() {
+            assert!(draws[i + 1..].iter().all(|b| a != b), "draw {i} repeats");
+        }
+    }
+
+    #[test]
+    fn ranges_stay_in_bounds() {
+        let mut rng = MapRng::new(1, FEATURE_SHADE_VARIATION);
+        let mut seen = [false; 5];
+        for _ in 0..200 {
+            let v = rng.range(-2, 2);
+            assert!((-2..=2).contains(&v));
+            seen[(v + 2).unsigned_abs() as usize] = true;
+            assert!((0.0..1.0).contains(&rng.next_f64()));
+        }
+        assert!(seen.iter().all(|&s| s));
+        assert_eq!(rng.range(3, 3), 3);
+    }
+
+    #[test]
+    fn degenerate_and_extreme_ranges_do_not_panic() {
+        let mut rng = MapRng::new(1, FEATURE_SHADE_VARIATION);
+        assert_eq!(rng.below(0), 0);
+        assert_eq!(rng.range(5, -5), 5);
+        for _ in 0..100 {
+            rng.range(i32::MIN, i32::MAX);
+            assert!(rng.range(i32::MAX - 1, i32::MAX) >= i32::MAX - 1);
+            assert!(rng.range(i32::MIN, i32::MIN + 1) <= i32::MIN + 1);
+        }
+    }
+
+    #[test]
+    fn position_hashes_are_roughly_uniform() {
+        let above_half = (0..32)
+            .flat_map(|x| (0..32).map(move |z| IVec3::new(x, 0, z)))
+            .filter(|&p| MapRng::hash(9, FEATURE_BIOME_DECORATION, p) > u32::MAX / 2)
+            .count();
+        assert!((440..=584).contains(&above_half), "{above_half} of 1024");
+    }
+}
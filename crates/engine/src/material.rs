@@ -0,0 +1,446 @@
+use crate::voxel::{
+    MAT_AIR, MAT_CRYSTAL, MAT_DIRT, MAT_GLASS, MAT_GLOWSTONE, MAT_GRASS, MAT_ICE, MAT_LAVA,
+    MAT_STONE, MAT_WATER, is_passable, material_id,
+};
+
+/// Number of entries in each half of the GPU palette. See
+/// [`MaterialRegistry::gpu_palette`].
+pub const PALETTE_SIZE: usize = 256;
+
+/// Number of floats in the flat material layout accepted by
+/// [`MaterialDef::from_flat`].
+pub const MATERIAL_FLOATS: usize = 8;
+
+/// Properties of a single voxel material.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MaterialDef {
+    pub name: String,
+    /// Linear RGB base color.
+    pub color: [f32; 3],
+    /// Blocks movement and forms walkable surfaces when topped by non-solid
+    /// voxels.
+    pub solid: bool,
+    /// Whether game entities may stand on a surface of this material.
+    pub walkable: bool,
+    /// Pathfinding cost of crossing a surface of this material.
+    pub movement_cost: u8,
+    /// `1.0` is fully opaque, `0.0` fully transparent.
+    pub opacity: f32,
+    /// Self-illumination intensity; `0.0` for non-emissive materials.
+    pub emissive: f32,
+}
+
+impl MaterialDef {
+    /// An opaque, solid, walkable material with unit movement cost.
+    #[must_use]
+    pub fn solid(name: &str, color: [f32; 3]) -> Self {
+        Self {
+            name: name.to_string(),
+            color,
+            solid: true,
+            walkable: true,
+            movement_cost: 1,
+            opacity: 1.0,
+            emissive: 0.0,
+        }
+    }
+
+    /// Decode a material from the flat layout used by the wasm API:
+    /// `[r, g, b, solid, walkable, movement_cost, opacity, emissive]`.
+    /// Booleans are non-zero for `true`. Returns `None` if `data` is shorter
+    /// than [`MATERIAL_FLOATS`].
+    #[must_use]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn from_flat(name: &str, data: &[f32]) -> Option<Self> {
+        let d = data.get(..MATERIAL_FLOATS)?;
+        Some(Self {
+            name: name.to_string(),
+            color: [d[0], d[1], d[2]],
+            solid: d[3] != 0.0,
+            walkable: d[4] != 0.0,
+            movement_cost: d[5].clamp(0.0, 255.0) as u8,
+            opacity: d[6].clamp(0.0, 1.0),
+            emissive: d[7].max(0.0),
+        })
+    }
+
+    /// A translucent liquid: not solid, so rays, entities and collision pass
+    /// through it.
+    #[must_use]
+    pub fn liquid(name: &str, color: [f32; 3], opacity: f32) -> Self {
+        Self {
+            name: name.to_string(),
+            color,
+            solid: false,
+            walkable: false,
+            movement_cost: 255,
+            opacity,
+            emissive: 0.0,
+        }
+    }
+
+    /// Whether rays continue through this material.
+    #[must_use]
+    pub fn is_translucent(&self) -> bool {
+        self.opacity < 1.0
+    }
+
+    fn air() -> Self {
+        Self {
+            name: "air".to_string(),
+            color: [0.0, 0.0, 0.0],
+            solid: false,
+            walkable: false,
+            movement_cost: 255,
+            opacity: 0.0,
+            emissive: 0.0,
+        }
+    }
+}
+
+/// Table of up to 256 materials indexed by voxel `material_id`.
+///
+/// Id 0 is always air. Ids without a definition behave like a plain solid
+/// block rendered black, matching how unknown voxels were treated before
+/// materials were data-driven.
+#[derive(Clone, Debug)]
+pub struct MaterialRegistry {
+    defs: Vec<Option<MaterialDef>>,
+    /// Cached `solid` flags so per-voxel scans avoid touching `defs`.
+    solid: [bool; 256],
+    /// Cached `emissive` intensities, for the same reason.
+    emissive: [f32; 256],
+}
+
+impl Default for MaterialRegistry {
+    /// The built-in materials: air, grass, dirt, stone, water, glass, ice,
+    /// lava, glowstone and crystal.
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.define(MAT_GRASS, MaterialDef::solid("grass", [0.3, 0.7, 0.2]));
+        registry.define(MAT_DIRT, MaterialDef::solid("dirt", [0.5, 0.3, 0.1]));
+        registry.define(MAT_STONE, MaterialDef::solid("stone", [0.5, 0.5, 0.5]));
+        registry.define(
+            MAT_WATER,
+            MaterialDef::liquid("water", [0.15, 0.35, 0.7], 0.35),
+        );
+        registry.define(
+            MAT_GLASS,
+            MaterialDef {
+                opacity: 0.15,
+                ..MaterialDef::solid("glass", [0.85, 0.9, 0.95])
+            },
+        );
+        registry.define(
+            MAT_ICE,
+            MaterialDef {
+                movement_cost: 2,
+                opacity: 0.6,
+                ..MaterialDef::solid("ice", [0.7, 0.85, 0.95])
+            },
+        );
+        registry.define(
+            MAT_LAVA,
+            MaterialDef {
+                emissive: 1.5,
+                ..MaterialDef::liquid("lava", [1.0, 0.35, 0.05], 1.0)
+            },
+        );
+        registry.define(
+            MAT_GLOWSTONE,
+            MaterialDef {
+                emissive: 1.0,
+                ..MaterialDef::solid("glowstone", [1.0, 0.85, 0.5])
+            },
+        );
+        registry.define(
+            MAT_CRYSTAL,
+            MaterialDef {
+                opacity: 0.5,
+                emissive: 0.6,
+                ..MaterialDef::solid("crystal", [0.6, 0.4, 1.0])
+            },
+        );
+        registry
+    }
+}
+
+impl MaterialRegistry {
+    /// A registry containing only air.
+    #[must_use]
+    pub fn empty() -> Self {
+        let mut defs = vec![None; 256];
+        defs[usize::from(MAT_AIR)] = Some(MaterialDef::air());
+        let mut solid = [true; 256];
+        solid[usize::from(MAT_AIR)] = false;
+        Self {
+            defs,
+            solid,
+            emissive: [0.0; 256],
+        }
+    }
+
+    /// Define or replace the material at `id`. Air (id 0) cannot be
+    /// redefined; returns `false` in that case.
+    pub fn define(&mut self, id: u8, def: MaterialDef) -> bool {
+        if id == MAT_AIR {
+            return false;
+        }
+        self.solid[usize::from(id)] = def.solid;
+        self.emissive[usize::from(id)] = def.emissive;
+        self.defs[usize::from(id)] = Some(def);
+        true
+    }
+
+    /// The definition for `id`, if one exists.
+    #[must_use]
+    pub fn get(&self, id: u8) -> Option<&MaterialDef> {
+        self.defs[usize::from(id)].as_ref()
+    }
+
+    /// Look up a material id by name.
+    #[must_use]
+    pub fn id_by_name(&self, name: &str) -> Option<u8> {
+        self.defs
+            .iter()
+            .position(|d| d.as_ref().is_some_and(|d| d.name == name))
+            .map(|i| i as u8)
+    }
+
+    /// Whether voxels of material `id` block movement.
+    #[inline]
+    #[must_use]
+    pub fn is_solid(&self, id: u8) -> bool {
+        self.solid[usize::from(id)]
+    }
+
+    /// Whether a packed voxel blocks movement: its material is solid and its
+    /// flags do not make it passable (open door or liquid).
+    #[inline]
+    #[must_use]
+    pub fn is_solid_voxel(&self, voxel: u32) -> bool {
+        self.is_solid(material_id(voxel)) && !is_passable(voxel)
+    }
+
+    /// Self-illumination intensity of material `id` (`0.0` if not emissive).
+    #[inline]
+    #[must_use]
+    pub fn emissive(&self, id: u8) -> f32 {
+        self.emissive[usize::from(id)]
+    }
+
+    /// Whether any defined material is emissive.
+    #[must_use]
+    pub fn has_emissive(&self) -> bool {
+        self.emissive.iter().any(|&e| e > 0.0)
+    }
+
+    /// Whether entities may stand on material `id`.
+    #[must_use]
+    pub fn is_walkable(&self, id: u8) -> bool {
+        self.get(id).map_or(id != MAT_AIR, |d| d.walkable)
+    }
+
+    /// Movement cost of material `id` (`1` for undefined solids).
+    #[must_use]
+    pub fn movement_cost(&self, id: u8) -> u8 {
+        self.get(id).map_or(1, |d| d.movement_cost)
+    }
+
+    /// Serializes the name, walkability and movement cost of every defined
+    /// material for the game worker's terrain table.
+    ///
+    /// Format: for each defined id in ascending order:
+    /// `[id, walkable, movement_cost, name_len, name (UTF-8) x name_len]`.
+    /// Names longer than 255 bytes are truncated at a character boundary.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn terrain_table_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for (id, def) in self.defs.iter().enumerate() {
+            let Some(def) = def else { continue };
+            let mut len = def.name.len().min(255);
+            while !def.name.is_char_boundary(len) {
+                len -= 1;
+            }
+            bytes.extend([
+                id as u8,
+                u8::from(def.walkable),
+                def.movement_cost,
+                len as u8,
+            ]);
+            bytes.extend_from_slice(&def.name.as_bytes()[..len]);
+        }
+        bytes
+    }
+
+    /// GPU palette: 256 RGBA entries with opacity in alpha.
+    #[must_use]
+    pub fn palette(&self) -> Vec<[f32; 4]> {
+        self.defs
+            .iter()
+            .map(|d| match d {
+                Some(d) => [d.color[0], d.color[1], d.color[2], d.opacity],
+                None => [0.0, 0.0, 0.0, 1.0],
+            })
+            .collect()
+    }
+
+    /// Full GPU palette: the [`palette`](Self::palette) followed by
+    /// [`PALETTE_SIZE`] emission entries of `[r, g, b, intensity]`, where
+    /// `rgb` is the base color pre-multiplied by the emissive intensity.
+    #[must_use]
+    pub fn gpu_palette(&self) -> Vec<[f32; 4]> {
+        let mut out = self.palette();
+        out.extend(self.defs.iter().map(|d| match d {
+            Some(d) if d.emissive > 0.0 => {
+                let [r, g, b] = d.color.map(|c| c * d.emissive);
+                [r, g, b, d.emissive]
+            }
+            _ => [0.0; 4],
+        }));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: &[f32], b: &[f32]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(x, y)| (x - y).abs() < 1e-6)
+    }
+
+    #[test]
+    fn builtins_match_legacy_palette() {
+        let palette = MaterialRegistry::default().palette();
+        assert_eq!(palette.len(), 256);
+        assert!(approx(
+            &palette[usize::from(MAT_GRASS)],
+            &[0.3, 0.7, 0.2, 1.0]
+        ));
+        assert!(approx(
+            &palette[usize::from(MAT_DIRT)],
+            &[0.5, 0.3, 0.1, 1.0]
+        ));
+        assert!(approx(
+            &palette[usize::from(MAT_STONE)],
+            &[0.5, 0.5, 0.5, 1.0]
+        ));
+        assert!(approx(&palette[200], &[0.0, 0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn translucent_builtins_carry_opacity() {
+        let registry = MaterialRegistry::default();
+        let palette = registry.palette();
+        for id in [MAT_WATER, MAT_GLASS, MAT_ICE] {
+            assert!(registry.get(id).unwrap().is_translucent());
+            let alpha = palette[usize::from(id)][3];
+            assert!(alpha > 0.0 && alpha < 1.0);
+        }
+        assert!(!registry.get(MAT_STONE).unwrap().is_translucent());
+    }
+
+    #[test]
+    fn liquids_are_not_solid_but_glass_and_ice_are() {
+        let registry = MaterialRegistry::default();
+        assert!(!registry.is_solid(MAT_WATER));
+        assert!(!registry.is_walkable(MAT_WATER));
+        assert!(registry.is_solid(MAT_GLASS));
+        assert!(registry.is_solid(MAT_ICE));
+        assert_eq!(registry.movement_cost(MAT_ICE), 2);
+    }
+
+    #[test]
+    fn gpu_palette_appends_emission() {
+        let registry = MaterialRegistry::default();
+        let palette = registry.gpu_palette();
+        assert_eq!(palette.len(), 2 * PALETTE_SIZE);
+        assert!(approx(
+            &palette[PALETTE_SIZE + usize::from(MAT_GLOWSTONE)],
+            &[1.0, 0.85, 0.5, 1.0]
+        ));
+        assert!(approx(
+            &palette[PALETTE_SIZE + usize::from(MAT_STONE)],
+            &[0.0; 4]
+        ));
+        assert!(registry.has_emissive());
+        assert!(!MaterialRegistry::empty().has_emissive());
+    }
+
+    #[test]
+    fn redefining_clears_cached_emission() {
+        let mut registry = MaterialRegistry::default();
+        assert!(registry.emissive(MAT_LAVA) > 0.0);
+        registry.define(MAT_LAVA, MaterialDef::solid("basalt", [0.2; 3]));
+        assert!(approx(&[registry.emissive(MAT_LAVA)], &[0.0]));
+    }
+
+    #[test]
+    fn passable_flags_override_solid_material() {
+        use crate::voxel::{FLAG_DOOR, FLAG_LIQUID, FLAG_OPEN, pack_voxel};
+        let registry = MaterialRegistry::default();
+        assert!(registry.is_solid_voxel(pack_voxel(MAT_STONE, 0, 0, FLAG_DOOR)));
+        assert!(!registry.is_solid_voxel(pack_voxel(MAT_STONE, 0, 0, FLAG_DOOR | FLAG_OPEN)));
+        assert!(!registry.is_solid_voxel(pack_voxel(MAT_STONE, 0, 0, FLAG_LIQUID)));
+        assert!(!registry.is_solid_voxel(0));
+    }
+
+    #[test]
+    fn air_is_not_solid_and_cannot_be_redefined() {
+        let mut registry = MaterialRegistry::default();
+        assert!(!registry.is_solid(MAT_AIR));
+        assert!(!registry.define(MAT_AIR, MaterialDef::solid("rock", [1.0; 3])));
+        assert!(!registry.is_solid(MAT_AIR));
+    }
+
+    #[test]
+    fn undefined_ids_are_solid() {
+        let registry = MaterialRegistry::default();
+        assert!(registry.get(77).is_none());
+        assert!(registry.is_solid(77));
+        assert!(registry.is_walkable(77));
+    }
+
+    #[test]
+    fn define_overrides_solidity() {
+        let mut registry = MaterialRegistry::default();
+        let mut swamp = MaterialDef::solid("swamp", [0.2, 0.3, 0.1]);
+        swamp.solid = false;
+        swamp.walkable = false;
+        registry.define(10, swamp);
+        assert!(!registry.is_solid(10));
+        assert!(!registry.is_walkable(10));
+        assert_eq!(registry.id_by_name("swamp"), Some(10));
+    }
+
+    #[test]
+    fn from_flat_decodes_layout() {
+        let def =
+            MaterialDef::from_flat("lava", &[1.0, 0.4, 0.0, 0.0, 0.0, 9.0, 0.8, 2.5]).unwrap();
+        assert!(approx(&def.color, &[1.0, 0.4, 0.0]));
+        assert!(!def.solid);
+        assert!(!def.walkable);
+        assert_eq!(def.movement_cost, 9);
+        assert!(approx(&[def.opacity, def.emissive], &[0.8, 2.5]));
+        assert!(MaterialDef::from_flat("short", &[1.0; 7]).is_none());
+    }
+
+    #[test]
+    fn terrain_table_lists_defined_materials() {
+        let mut registry = MaterialRegistry::empty();
+        registry.define(
+            MAT_ICE,
+            MaterialDef {
+                movement_cost: 2,
+                ..MaterialDef::solid("ice", [1.0; 3])
+            },
+        );
+        let mut table = vec![MAT_AIR, 0, 255, 3];
+        table.extend_from_slice(b"air");
+        table.extend([MAT_ICE, 1, 2, 3]);
+        table.extend_from_slice(b"ice");
+        assert_eq!(registry.terrain_table_bytes(), table);
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use glam::{IVec3, UVec3};
+
+use crate::voxel::{voxel_index, world_ivec_to_chunk};
+
+/// Most voxels a single [`VoxelRegion`] may cover: a 128-voxel cube, or
+/// 64 chunks' worth.
+pub const MAX_REGION_VOXELS: u64 = 1 << 21;
+
+/// A [`VoxelRegion`] covers more than [`MAX_REGION_VOXELS`] voxels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegionTooLarge {
+    /// Upper bound on the voxels the region covers.
+    pub voxels: u64,
+}
+
+impl fmt::Display for RegionTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "region covers up to {} voxels, more than the {MAX_REGION_VOXELS} allowed",
+            self.voxels
+        )
+    }
+}
+
+impl std::error::Error for RegionTooLarge {}
+
+/// A set of world-space voxels targeted by a batched mutation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VoxelRegion {
+    /// Axis-aligned box, inclusive on both corners. Corners may be given in
+    /// any order.
+    Box { min: IVec3, max: IVec3 },
+    /// All voxels whose integer distance from `center` is at most `radius`.
+    /// A negative radius selects nothing.
+    Sphere { center: IVec3, radius: i32 },
+    /// A one-voxel-thick line from `from` to `to`, both endpoints included.
+    Line { from: IVec3, to: IVec3 },
+    /// An arbitrary list of voxels. Duplicates are allowed.
+    List(Vec<IVec3>),
+}
+
+impl VoxelRegion {
+    /// Upper bound on the voxels the region covers, computed without
+    /// enumerating them: exact for boxes, lines and lists, the bounding
+    /// cube for spheres.
+    #[must_use]
+    pub fn max_voxels(&self) -> u64 {
+        let extent = |a: IVec3, b: IVec3| (a.as_i64vec3() - b.as_i64vec3()).abs() + 1;
+        let volume = |size: glam::I64Vec3| {
+            size.x
+                .unsigned_abs()
+                .saturating_mul(size.y.unsigned_abs())
+                .saturating_mul(size.z.unsigned_abs())
+        };
+        match self {
+            Self::Box { min, max } => volume(extent(*min, *max)),
+            Self::Sphere { radius, .. } if *radius < 0 => 0,
+            Self::Sphere { radius, .. } => volume(glam::I64Vec3::splat(2 * i64::from(*radius) + 1)),
+            Self::Line { from, to } => extent(*from, *to).max_element().unsigned_abs(),
+            Self::List(list) => list.len() as u64,
+        }
+    }
+
+    /// Enumerate the world-space voxel positions covered by this region.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RegionTooLarge`] without enumerating anything if
+    /// [`max_voxels`](Self::max_voxels) exceeds [`MAX_REGION_VOXELS`].
+    pub fn voxels(&self) -> Result<Vec<IVec3>, RegionTooLarge> {
+        let voxels = self.max_voxels();
+        if voxels > MAX_REGION_VOXELS {
+            return Err(RegionTooLarge { voxels });
+        }
+        Ok(match self {
+            Self::Box { min, max } => {
+                let lo = min.min(*max);
+                let hi = min.max(*max);
+                let mut out = Vec::new();
+                for z in lo.z..=hi.z {
+                    for y in lo.y..=hi.y {
+                        for x in lo.x..=hi.x {
+                            out.push(IVec3::new(x, y, z));
+                        }
+                    }
+                }
+                out
+            }
+            Self::Sphere { center, radius } => {
+                let r = *radius;
+                let mut out = Vec::new();
+                for dz in -r..=r {
+                    for dy in -r..=r {
+                        for dx in -r..=r {
+                            let d = IVec3::new(dx, dy, dz);
+                            if d.length_squared() <= r * r {
+                                out.push(*center + d);
+                            }
+                        }
+                    }
+                }
+                out
+            }
+            Self::Line { from, to } => line_voxels(*from, *to),
+            Self::List(list) => list.clone(),
+        })
+    }
+}
+
+/// Voxels along the segment `from`..=`to`, stepping once per unit along the
+/// dominant axis so consecutive voxels always touch.
+fn line_voxels(from: IVec3, to: IVec3) -> Vec<IVec3> {
+    let delta = to - from;
+    let steps = delta.abs().max_element();
+    if steps == 0 {
+        return vec![from];
+    }
+    let n = f64::from(steps);
+    (0..=steps)
+        .map(|i| {
+            let t = f64::from(i) / n;
+            from + IVec3::new(
+                (f64::from(delta.x) * t).round() as i32,
+                (f64::from(delta.y) * t).round() as i32,
+                (f64::from(delta.z) * t).round() as i32,
+            )
+        })
+        .collect()
+}
+
+/// Inclusive chunk-local bounding box of the voxels touched by a mutation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DirtyBox {
+    pub min: UVec3,
+    pub max: UVec3,
+}
+
+impl DirtyBox {
+    /// A box covering the single voxel at `p`.
+    #[must_use]
+    pub fn from_point(p: UVec3) -> Self {
+        Self { min: p, max: p }
+    }
+
+    /// Grow the box to include `p`.
+    pub fn include(&mut self, p: UVec3) {
+        self.min = self.min.min(p);
+        self.max = self.max.max(p);
+    }
+
+    /// Extent of the box in voxels along each axis.
+    #[must_use]
+    pub fn size(&self) -> UVec3 {
+        self.max - self.min + UVec3::ONE
+    }
+}
+
+/// Group world-space voxel writes by the chunk they fall in.
+///
+/// Each entry maps a chunk coordinate to `(voxel_index, voxel)` pairs in the
+/// order they were supplied, so later writes to the same voxel win when
+/// applied in sequence.
+#[must_use]
+pub fn group_by_chunk(
+    writes: impl IntoIterator<Item = (IVec3, u32)>,
+) -> HashMap<IVec3, Vec<(usize, u32)>> {
+    let mut grouped: HashMap<IVec3, Vec<(usize, u32)>> = HashMap::new();
+    for (world_pos, voxel) in writes {
+        let (chunk_coord, (lx, ly, lz)) = world_ivec_to_chunk(world_pos);
+        grouped
+            .entry(chunk_coord)
+            .or_default()
+            .push((voxel_index(lx, ly, lz), voxel));
+    }
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn box_is_inclusive_and_order_independent() {
+        let a = VoxelRegion::Box {
+            min: IVec3::ZERO,
+            max: IVec3::new(1, 2, 3),
+        };
+        let b = VoxelRegion::Box {
+            min: IVec3::new(1, 2, 3),
+            max: IVec3::ZERO,
+        };
+        assert_eq!(a.voxels().unwrap().len(), 2 * 3 * 4);
+        assert_eq!(a.voxels().unwrap(), b.voxels().unwrap());
+    }
+
+    #[test]
+    fn sphere_radius_zero_is_single_voxel() {
+        let s = VoxelRegion::Sphere {
+            center: IVec3::new(5, 5, 5),
+            radius: 0,
+        };
+        assert_eq!(s.voxels().unwrap(), vec![IVec3::new(5, 5, 5)]);
+    }
+
+    #[test]
+    fn sphere_radius_one_is_plus_shape() {
+        let s = VoxelRegion::Sphere {
+            center: IVec3::ZERO,
+            radius: 1,
+        };
+        assert_eq!(s.voxels().unwrap().len(), 7);
+    }
+
+    #[test]
+    fn negative_sphere_is_empty() {
+        let s = VoxelRegion::Sphere {
+            center: IVec3::ZERO,
+            radius: -1,
+        };
+        assert!(s.voxels().unwrap().is_empty());
+    }
+
+    #[test]
+    fn oversized_regions_are_refused_before_enumerating() {
+        let huge_sphere = VoxelRegion::Sphere {
+            center: IVec3::ZERO,
+            radius: i32::MAX,
+        };
+        let world_box = VoxelRegion::Box {
+            min: IVec3::MIN,
+            max: IVec3::MAX,
+        };
+        let long_line = VoxelRegion::Line {
+            from: IVec3::new(i32::MIN, 0, 0),
+            to: IVec3::new(i32::MAX, 0, 0),
+        };
+        for region in [huge_sphere, world_box, long_line] {
+            let err = region.voxels().unwrap_err();
+            assert!(err.voxels > MAX_REGION_VOXELS, "{region:?}: {err}");
+        }
+
+        let cube = VoxelRegion::Box {
+            min: IVec3::ZERO,
+            max: IVec3::splat(127),
+        };
+        assert_eq!(cube.max_voxels(), MAX_REGION_VOXELS);
+        assert_eq!(cube.voxels().unwrap().len() as u64, MAX_REGION_VOXELS);
+    }
+
+    #[test]
+    fn line_includes_endpoints_and_is_connected() {
+        let from = IVec3::new(0, 0, 0);
+        let to = IVec3::new(10, -3, 4);
+        let voxels = VoxelRegion::Line { from, to }.voxels().unwrap();
+        assert_eq!(voxels.first(), Some(&from));
+        assert_eq!(voxels.last(), Some(&to));
+        assert_eq!(voxels.len(), 11);
+        for pair in voxels.windows(2) {
+            assert!((pair[1] - pair[0]).abs().max_element() <= 1);
+        }
+    }
+
+    #[test]
+    fn dirty_box_grows_to_include_points() {
+        let mut b = DirtyBox::from_point(UVec3::new(4, 4, 4));
+        assert_eq!(b.size(), UVec3::ONE);
+        b.include(UVec3::new(2, 6, 4));
+        assert_eq!(b.min, UVec3::new(2, 4, 4));
+        assert_eq!(b.max, UVec3::new(4, 6, 4));
+        assert_eq!(b.size(), UVec3::new(3, 3, 1));
+    }
+
+    #[test]
+    fn group_by_chunk_splits_across_boundary() {
+        let writes = [(IVec3::new(31, 0, 0), 1), (IVec3::new(32, 0, 0), 2)];
+        let grouped = group_by_chunk(writes);
+        assert_eq!(grouped.len(), 2);
+        assert_eq!(grouped[&IVec3::ZERO], vec![(voxel_index(31, 0, 0), 1)]);
+        assert_eq!(grouped[&IVec3::X], vec![(voxel_index(0, 0, 0), 2)]);
+    }
+
+    #[test]
+    fn group_by_chunk_handles_negative_coords() {
+        let grouped = group_by_chunk([(IVec3::new(-1, -1, -1), 7)]);
+        assert_eq!(
+            grouped[&IVec3::splat(-1)],
+            vec![(voxel_index(31, 31, 31), 7)]
+        );
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+
+/// Palette sizes above this switch a growing palette to direct storage, so
+/// bulk writes (map features stamping per-voxel params) stay O(1) per voxel
+/// instead of scanning an ever longer palette. [`PaletteStorage::compact`]
+/// packs the result back down afterwards.
+const MAX_GROWING_PALETTE: usize = 256;
+
+/// Fixed-length voxel storage that keeps a palette of the distinct values
+/// and stores per-voxel palette indices at the smallest power-of-two bit
+/// width that fits (1, 2, 4, 8 or 16 bits). A storage holding one value
+/// needs no index data at all.
+///
+/// Writes of a value not yet in the palette append to it, widening the
+/// indices when needed; entries are never removed until
+/// [`compact`](Self::compact) rebuilds the palette.
+#[derive(Clone, Debug)]
+pub struct PaletteStorage {
+    len: usize,
+    repr: Repr,
+}
+
+#[derive(Clone, Debug)]
+enum Repr {
+    /// Every voxel holds the same value.
+    Uniform(u32),
+    /// `bits`-wide palette indices packed into `u64` words, low bits first.
+    /// Indices never straddle a word since `bits` divides 64.
+    Indexed {
+        palette: Vec<u32>,
+        bits: u32,
+        words: Vec<u64>,
+    },
+    /// Plain values, used while a palette is too large to search cheaply.
+    Direct(Vec<u32>),
+}
+
+/// Smallest supported index width able to address `palette_len` entries.
+fn bits_for(palette_len: usize) -> u32 {
+    match palette_len {
+        0..=2 => 1,
+        3..=4 => 2,
+        5..=16 => 4,
+        17..=256 => 8,
+        _ => 16,
+    }
+}
+
+fn word_count(len: usize, bits: u32) -> usize {
+    len.div_ceil(64 / bits as usize)
+}
+
+#[inline]
+fn read_index(words: &[u64], bits: u32, i: usize) -> usize {
+    let per_word = 64 / bits as usize;
+    let shift = (i % per_word) as u32 * bits;
+    ((words[i / per_word] >> shift) & ((1u64 << bits) - 1)) as usize
+}
+
+#[inline]
+fn write_index(words: &mut [u64], bits: u32, i: usize, index: usize) {
+    let per_word = 64 / bits as usize;
+    let shift = (i % per_word) as u32 * bits;
+    let mask = ((1u64 << bits) - 1) << shift;
+    let word = &mut words[i / per_word];
+    *word = (*word & !mask) | ((index as u64) << shift);
+}
+
+fn pack_indices(indices: impl Iterator<Item = usize>, len: usize, bits: u32) -> Vec<u64> {
+    let mut words = vec![0u64; word_count(len, bits)];
+    for (i, index) in indices.enumerate() {
+        write_index(&mut words, bits, i, index);
+    }
+    words
+}
+
+impl PaletteStorage {
+    /// A storage of `len` voxels all set to `value`.
+    #[must_use]
+    pub fn uniform(len: usize, value: u32) -> Self {
+        Self {
+            len,
+            repr: Repr::Uniform(value),
+        }
+    }
+
+    /// Compress a slice of voxel values.
+    #[must_use]
+    pub fn from_values(values: &[u32]) -> Self {
+        let mut palette = Vec::new();
+        let mut lookup = HashMap::new();
+        let indices: Vec<usize> = values
+            .iter()
+            .map(|&v| {
+                *lookup.entry(v).or_insert_with(|| {
+                    palette.push(v);
+                    palette.len() - 1
+                })
+            })
+            .collect();
+        let len = values.len();
+        if palette.len() <= 1 {
+            return Self::uniform(len, palette.first().copied().unwrap_or(0));
+        }
+        let bits = bits_for(palette.len());
+        Self {
+            len,
+            repr: Repr::Indexed {
+                words: pack_indices(indices.into_iter(), len, bits),
+                palette,
+                bits,
+            },
+        }
+    }
+
+    /// Number of voxels stored.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the storage holds no voxels.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The value at `i`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= len()`.
+    #[inline]
+    #[must_use]
+    pub fn get(&self, i: usize) -> u32 {
+        assert!(i < self.len, "voxel index {i} out of bounds");
+        match &self.repr {
+            Repr::Uniform(v) => *v,
+            Repr::Indexed {
+                palette,
+                bits,
+                words,
+            } => palette[read_index(words, *bits, i)],
+            Repr::Direct(values) => values[i],
+        }
+    }
+
+    /// Write `value` at `i`, growing the palette if `value` is new.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i >= len()`.
+    pub fn set(&mut self, i: usize, value: u32) {
+        assert!(i < self.len, "voxel index {i} out of bounds");
+        match &mut self.repr {
+            Repr::Uniform(v) => {
+                if *v == value {
+                    return;
+                }
+                let mut words = vec![0u64; word_count(self.len, 1)];
+                write_index(&mut words, 1, i, 1);
+                self.repr = Repr::Indexed {
+                    palette: vec![*v, value],
+                    bits: 1,
+                    words,
+                };
+            }
+            Repr::Indexed {
+                palette,
+                bits,
+                words,
+            } => {
+                if let Some(index) = palette.iter().position(|&p| p == value) {
+                    write_index(words, *bits, i, index);
+                    return;
+                }
+                if palette.len() >= MAX_GROWING_PALETTE {
+                    let mut values: Vec<u32> = (0..self.len)
+                        .map(|j| palette[read_index(words, *bits, j)])
+                        .collect();
+                    values[i] = value;
+                    self.repr = Repr::Direct(values);
+                    return;
+                }
+                palette.push(value);
+                let needed = bits_for(palette.len());
+                if needed != *bits {
+                    *words = pack_indices(
+                        (0..self.len).map(|j| read_index(words, *bits, j)),
+                        self.len,
+                        needed,
+                    );
+                    *bits = needed;
+                }
+                write_index(words, *bits, i, palette.len() - 1);
+            }
+            Repr::Direct(values) => values[i] = value,
+        }
+    }
+
+    /// The single value held by every voxel, if the storage is uniform.
+    ///
+    /// Only reports the uniform representation; an indexed storage whose
+    /// voxels happen to agree returns `None` until [`compact`](Self::compact)
+    /// is called.
+    #[must_use]
+    pub fn uniform_value(&self) -> Option<u32> {
+        match self.repr {
+            Repr::Uniform(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// The distinct values this storage may hold. Stale entries left behind
+    /// by overwrites are included until [`compact`](Self::compact). Returns
+    /// `None` for direct storage.
+    #[must_use]
+    pub fn palette(&self) -> Option<&[u32]> {
+        match &self.repr {
+            Repr::Uniform(v) => Some(std::slice::from_ref(v)),
+            Repr::Indexed { palette, .. } => Some(palette),
+            Repr::Direct(_) => None,
+        }
+    }
+
+    /// Bits stored per voxel: 0 when uniform, the index width when indexed
+    /// and 32 for direct storage.
+    #[must_use]
+    pub fn bits_per_voxel(&self) -> u32 {
+        match self.repr {
+            Repr::Uniform(_) => 0,
+            Repr::Indexed { bits, .. } => bits,
+            Repr::Direct(_) => 32,
+        }
+    }
+
+    /// Heap memory held by this storage in bytes.
+    #[must_use]
+    pub fn heap_bytes(&self) -> usize {
+        match &self.repr {
+            Repr::Uniform(_) => 0,
+            Repr::Indexed { palette, words, .. } => {
+                palette.capacity() * size_of::<u32>() + words.capacity() * size_of::<u64>()
+            }
+            Repr::Direct(values) => values.capacity() * size_of::<u32>(),
+        }
+    }
+
+    /// Iterate over all values in index order.
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        (0..self.len).map(|i| self.get(i))
+    }
+
+    /// Expand into one `u32` per voxel.
+    #[must_use]
+    pub fn to_vec(&self) -> Vec<u32> {
+        match &self.repr {
+            Repr::Uniform(v) => vec![*v; self.len],
+            Repr::Indexed { .. } => self.iter().collect(),
+            Repr::Direct(values) => values.clone(),
+        }
+    }
+
+    /// Rebuild the palette from the values actually present, dropping stale
+    /// entries and choosing the narrowest representation.
+    pub fn compact(&mut self) {
+        if matches!(self.repr, Repr::Uniform(_)) {
+            return;
+        }
+        *self = Self::from_values(&self.to_vec());
+    }
+}
+
+impl PartialEq for PaletteStorage {
+    /// Storages are equal when they hold the same values, whatever their
+    /// representation.
+    fn eq(&self, other: &Self) -> bool {
+        if self.len != other.len {
+            return false;
+        }
+        match (&self.repr, &other.repr) {
+            (Repr::Uniform(a), Repr::Uniform(b)) => a == b,
+            _ => self.iter().eq(other.iter()),
+        }
+    }
+}
+
+impl Eq for PaletteStorage {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEN: usize = 32 * 32 * 32;
+
+    #[test]
+    fn uniform_storage_has_no_index_data() {
+        let storage = PaletteStorage::uniform(LEN, 3);
+        assert_eq!(storage.get(LEN - 1), 3);
+        assert_eq!(storage.bits_per_voxel(), 0);
+        assert_eq!(storage.heap_bytes(), 0);
+        assert_eq!(storage.uniform_value(), Some(3));
+    }
+
+    #[test]
+    fn set_widens_indices_as_palette_grows() {
+        let mut storage = PaletteStorage::uniform(LEN, 0);
+        storage.set(5, 1);
+        assert_eq!(storage.bits_per_voxel(), 1);
+        storage.set(6, 2);
+        assert_eq!(storage.bits_per_voxel(), 2);
+        for v in 3..=16 {
+            storage.set(v as usize * 100, v);
+        }
+        assert_eq!(storage.bits_per_voxel(), 8);
+        assert_eq!(storage.get(5), 1);
+        assert_eq!(storage.get(6), 2);
+        assert_eq!(storage.get(1600), 16);
+        assert_eq!(storage.get(7), 0);
+    }
+
+    #[test]
+    fn large_palette_falls_back_to_direct_then_compacts() {
+        let mut storage = PaletteStorage::uniform(LEN, 0);
+        for i in 0..1000 {
+            storage.set(i, i as u32 + 1);
+        }
+        assert_eq!(storage.bits_per_voxel(), 32);
+        assert_eq!(storage.get(999), 1000);
+        storage.compact();
+        assert_eq!(storage.bits_per_voxel(), 16);
+        assert_eq!(storage.get(999), 1000);
+        assert_eq!(storage.get(1000), 0);
+    }
+
+    #[test]
+    fn compact_drops_stale_entries() {
+        let mut storage = PaletteStorage::uniform(LEN, 0);
+        storage.set(10, 7);
+        storage.set(10, 0);
+        assert_eq!(storage.palette(), Some(&[0, 7][..]));
+        storage.compact();
+        assert_eq!(storage.uniform_value(), Some(0));
+    }
+
+    #[test]
+    fn from_values_round_trips_and_equality_ignores_representation() {
+        let values: Vec<u32> = (0..LEN as u32).map(|i| i % 5).collect();
+        let storage = PaletteStorage::from_values(&values);
+        assert_eq!(storage.bits_per_voxel(), 4);
+        assert_eq!(storage.to_vec(), values);
+        assert!(storage.heap_bytes() < LEN);
+
+        let mut direct = PaletteStorage::uniform(LEN, 0);
+        for i in 0..300 {
+            direct.set(i, 1000 + i as u32);
+        }
+        for (i, &v) in values.iter().enumerate() {
+            direct.set(i, v);
+        }
+        assert_eq!(direct.bits_per_voxel(), 32);
+        assert_eq!(direct, storage);
+    }
+}
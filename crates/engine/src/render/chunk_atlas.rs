@@ -1,14 +1,13 @@
-use bytemuck::{Pod, Zeroable};
 use glam::{IVec3, UVec3};
 use wgpu::util::DeviceExt;
 
-use crate::voxel::{CHUNK_SIZE, Chunk};
+use super::static_lights::{STATIC_LIGHT_SLOT_FLOATS, StaticLight, pack_static_lights};
+use crate::mutation::DirtyBox;
+use crate::voxel::{CHUNK_SIZE, Chunk, voxel_index};
 
-/// Per-slot metadata stored in the chunk index GPU buffer.
-/// Matches the WGSL `ChunkSlot` struct layout (16 bytes).
-#[repr(C)]
-#[derive(Clone, Copy, Pod, Zeroable)]
-pub struct ChunkSlotGpu {
+/// CPU-side record of what an atlas slot holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChunkSlot {
     pub world_pos: IVec3,
     pub flags: u32,
 }
@@ -31,59 +30,158 @@ pub fn slot_to_atlas_origin(slot: u32, slots_per_axis: UVec3) -> UVec3 {
     )
 }
 
-/// Compute the atlas slot index for a world chunk coordinate using modular
-/// arithmetic. The slot is deterministic from the world coordinate alone,
-/// so chunks keep their slot assignment as the camera view shifts.
+/// A slot handed out by [`SlotAllocator::allocate`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Allocation {
+    pub slot: u32,
+    /// The chunk that held the slot before and must be dropped by the caller.
+    pub evicted: Option<IVec3>,
+}
+
+/// Assigns atlas slots to world chunks independently of their coordinates.
 ///
-/// Formula: `slot = (z % sz) * sx * sy + (y % sy) * sx + (x % sx)`
-/// where `%` is Euclidean modulo (always non-negative).
+/// Free slots are handed out lowest first. Once the atlas is full, the slot
+/// whose owner was [touched](Self::touch) least recently is reclaimed, so
+/// the atlas can be sized to the working set rather than to a modular
+/// window around the camera.
+pub struct SlotAllocator {
+    /// Unowned slots, popped from the back.
+    free: Vec<u32>,
+    owners: Vec<Option<IVec3>>,
+    last_used: Vec<u64>,
+}
+
+impl SlotAllocator {
+    #[must_use]
+    pub fn new(total_slots: u32) -> Self {
+        Self {
+            free: (0..total_slots).rev().collect(),
+            owners: vec![None; total_slots as usize],
+            last_used: vec![0; total_slots as usize],
+        }
+    }
+
+    /// Give `coord` a slot: a free one if any, otherwise the least recently
+    /// used slot whose owner is not `protected`. Returns `None` if every
+    /// owned slot is protected.
+    pub fn allocate(
+        &mut self,
+        coord: IVec3,
+        protected: impl Fn(IVec3) -> bool,
+    ) -> Option<Allocation> {
+        let (slot, evicted) = if let Some(slot) = self.free.pop() {
+            (slot, None)
+        } else {
+            let (slot, owner) = self
+                .owners
+                .iter()
+                .enumerate()
+                .filter_map(|(slot, owner)| owner.map(|o| (slot, o)))
+                .filter(|&(_, owner)| !protected(owner))
+                .min_by_key(|&(slot, _)| self.last_used[slot])?;
+            (slot as u32, Some(owner))
+        };
+        self.owners[slot as usize] = Some(coord);
+        Some(Allocation { slot, evicted })
+    }
+
+    /// Record that the owner of `slot` was used at time `now`.
+    pub fn touch(&mut self, slot: u32, now: u64) {
+        self.last_used[slot as usize] = now;
+    }
+
+    /// Return `slot` to the free list.
+    pub fn release(&mut self, slot: u32) {
+        if self.owners[slot as usize].take().is_some() {
+            self.free.push(slot);
+        }
+    }
+
+    /// The chunk currently holding `slot`.
+    #[must_use]
+    pub fn owner(&self, slot: u32) -> Option<IVec3> {
+        self.owners[slot as usize]
+    }
+
+    /// Number of unowned slots.
+    #[must_use]
+    pub fn free_count(&self) -> usize {
+        self.free.len()
+    }
+}
+
+/// Copy the voxels inside `dirty` out of `chunk` into a tightly packed
+/// X-fastest buffer matching the `write_texture` layout for that extent.
 #[must_use]
-pub fn world_to_slot(coord: IVec3, atlas_slots: UVec3) -> u32 {
-    let slots = atlas_slots.as_ivec3();
-    let wrapped = IVec3::new(
-        coord.x.rem_euclid(slots.x),
-        coord.y.rem_euclid(slots.y),
-        coord.z.rem_euclid(slots.z),
-    );
-    (wrapped.z * slots.x * slots.y + wrapped.y * slots.x + wrapped.x).cast_unsigned()
+pub fn extract_sub_box(chunk: &Chunk, dirty: DirtyBox) -> Vec<u32> {
+    let size = dirty.size();
+    let mut out = Vec::with_capacity((size.x * size.y * size.z) as usize);
+    for z in dirty.min.z..=dirty.max.z {
+        for y in dirty.min.y..=dirty.max.y {
+            let row = voxel_index(dirty.min.x as usize, y as usize, z as usize);
+            out.extend((row..row + size.x as usize).map(|i| chunk.get(i)));
+        }
+    }
+    out
 }
 
-/// A 3D texture atlas holding multiple voxel chunks, plus a GPU-side index
-/// buffer mapping each slot to its world chunk coordinate.
+/// A 3D texture atlas holding multiple voxel chunks, plus a GPU-side lookup
+/// buffer mapping each chunk of the render grid to the slot holding it.
+///
+/// The lookup covers the box set by [`set_grid`](Self::set_grid), indexed
+/// X-fastest by the chunk's offset from the grid origin. Entries store
+/// `slot + 1`; 0 means no uploaded chunk.
 pub struct ChunkAtlas {
     atlas_texture: wgpu::Texture,
     atlas_view: wgpu::TextureView,
-    index_buffer: wgpu::Buffer,
+    lookup_buffer: wgpu::Buffer,
+    lookup: Vec<u32>,
+    grid_origin: IVec3,
+    grid_size: UVec3,
     occupancy_buffer: wgpu::Buffer,
     occupancy_masks: Vec<u64>,
-    pub slots: Vec<ChunkSlotGpu>,
+    static_light_buffer: wgpu::Buffer,
+    pub slots: Vec<ChunkSlot>,
     slots_per_axis: UVec3,
 }
 
 impl ChunkAtlas {
-    /// Creates a new `ChunkAtlas` with the given slot dimensions.
+    /// Creates a new `ChunkAtlas` with the given slot dimensions, whose
+    /// lookup can cover a grid of up to as many chunks as there are slots.
     ///
     /// The 3D atlas texture is sized to `slots_per_axis * CHUNK_SIZE` texels
-    /// along each axis. The index buffer holds one `ChunkSlotGpu` per slot,
-    /// all initialized to empty (flags == 0).
+    /// along each axis. All slots start empty (flags == 0).
     #[must_use]
     pub fn new(device: &wgpu::Device, slots_per_axis: UVec3) -> Self {
+        let total_slots = slots_per_axis.x * slots_per_axis.y * slots_per_axis.z;
+        Self::with_grid_capacity(device, slots_per_axis, total_slots)
+    }
+
+    /// Like [`new`](Self::new), but sizes the lookup for grids of up to
+    /// `grid_capacity` chunks.
+    #[must_use]
+    pub fn with_grid_capacity(
+        device: &wgpu::Device,
+        slots_per_axis: UVec3,
+        grid_capacity: u32,
+    ) -> Self {
         let total_slots = (slots_per_axis.x * slots_per_axis.y * slots_per_axis.z) as usize;
 
         let atlas_texture = Self::create_atlas_texture(device, slots_per_axis);
         let atlas_view = atlas_texture.create_view(&wgpu::TextureViewDescriptor::default());
 
         let slots = vec![
-            ChunkSlotGpu {
+            ChunkSlot {
                 world_pos: IVec3::ZERO,
                 flags: 0,
             };
             total_slots
         ];
 
-        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Chunk Index"),
-            contents: bytemuck::cast_slice(&slots),
+        // Storage bindings must not be empty.
+        let lookup_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Chunk Lookup"),
+            contents: bytemuck::cast_slice(&vec![0u32; grid_capacity.max(1) as usize]),
             usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
         });
 
@@ -94,25 +192,113 @@ impl ChunkAtlas {
             usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
         });
 
+        let static_lights = vec![0.0f32; total_slots * STATIC_LIGHT_SLOT_FLOATS];
+        let static_light_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Static Lights"),
+            contents: bytemuck::cast_slice(&static_lights),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        });
+
         Self {
             atlas_texture,
             atlas_view,
-            index_buffer,
+            lookup_buffer,
+            lookup: Vec::new(),
+            grid_origin: IVec3::ZERO,
+            grid_size: UVec3::ZERO,
             occupancy_buffer,
             occupancy_masks,
+            static_light_buffer,
             slots,
             slots_per_axis,
         }
     }
 
+    /// Point the lookup at the grid of `size` chunks starting at `origin`,
+    /// rebuilding it from the occupied slots if the grid changed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the grid holds more chunks than the lookup capacity.
+    pub fn set_grid(&mut self, queue: &wgpu::Queue, origin: IVec3, size: UVec3) {
+        if origin == self.grid_origin && size == self.grid_size {
+            return;
+        }
+        let cells = (size.x * size.y * size.z) as usize;
+        let capacity = (self.lookup_buffer.size() / size_of::<u32>() as u64) as usize;
+        assert!(
+            cells <= capacity,
+            "grid {size} exceeds the lookup capacity of {capacity} chunks"
+        );
+        self.grid_origin = origin;
+        self.grid_size = size;
+        self.lookup.clear();
+        self.lookup.resize(cells, 0);
+        for (slot, entry) in self.slots.iter().enumerate() {
+            if entry.flags == 1
+                && let Some(index) = self.grid_index(entry.world_pos)
+            {
+                self.lookup[index] = slot as u32 + 1;
+            }
+        }
+        if cells > 0 {
+            queue.write_buffer(&self.lookup_buffer, 0, bytemuck::cast_slice(&self.lookup));
+        }
+    }
+
+    /// The slot holding the uploaded chunk at `coord`, if it is inside the
+    /// current grid.
+    #[must_use]
+    pub fn lookup_slot(&self, coord: IVec3) -> Option<u32> {
+        let entry = self.lookup[self.grid_index(coord)?];
+        entry.checked_sub(1)
+    }
+
+    /// Flat lookup index of `coord`, or `None` outside the grid.
+    fn grid_index(&self, coord: IVec3) -> Option<usize> {
+        let local = coord - self.grid_origin;
+        let size = self.grid_size.as_ivec3();
+        if local.cmplt(IVec3::ZERO).any() || local.cmpge(size).any() {
+            return None;
+        }
+        let (local, size) = (local.as_uvec3(), self.grid_size);
+        Some((local.x + local.y * size.x + local.z * size.x * size.y) as usize)
+    }
+
+    /// Store `entry` for `coord` in the lookup, if it lies in the grid.
+    fn write_lookup(&mut self, queue: &wgpu::Queue, coord: IVec3, entry: u32) {
+        if let Some(index) = self.grid_index(coord) {
+            self.lookup[index] = entry;
+            queue.write_buffer(
+                &self.lookup_buffer,
+                (index * size_of::<u32>()) as u64,
+                bytemuck::bytes_of(&entry),
+            );
+        }
+    }
+
     /// Upload a chunk's voxel data into the given atlas slot and update
-    /// the index buffer entry.
+    /// its lookup entry.
     pub fn upload_chunk(
         &mut self,
         queue: &wgpu::Queue,
         slot: u32,
         chunk: &Chunk,
         world_coord: IVec3,
+    ) {
+        self.upload_precomputed(queue, slot, chunk, world_coord, chunk.occupancy_mask());
+    }
+
+    /// Like [`upload_chunk`](Self::upload_chunk), but takes the chunk's
+    /// occupancy mask from the caller (e.g. a server payload) instead of
+    /// scanning the voxels for it.
+    pub fn upload_precomputed(
+        &mut self,
+        queue: &wgpu::Queue,
+        slot: u32,
+        chunk: &Chunk,
+        world_coord: IVec3,
+        mask: u64,
     ) {
         let chunk_u32 = CHUNK_SIZE as u32;
         let origin = slot_to_atlas_origin(slot, self.slots_per_axis);
@@ -128,7 +314,7 @@ impl ChunkAtlas {
                 },
                 aspect: wgpu::TextureAspect::All,
             },
-            bytemuck::cast_slice(&chunk.voxels),
+            bytemuck::cast_slice(&chunk.to_voxels()),
             wgpu::TexelCopyBufferLayout {
                 offset: 0,
                 bytes_per_row: Some(chunk_u32 * 4),
@@ -141,17 +327,12 @@ impl ChunkAtlas {
             },
         );
 
-        self.slots[slot as usize] = ChunkSlotGpu {
+        self.slots[slot as usize] = ChunkSlot {
             world_pos: world_coord,
             flags: 1,
         };
-        queue.write_buffer(
-            &self.index_buffer,
-            u64::from(slot) * size_of::<ChunkSlotGpu>() as u64,
-            bytemuck::bytes_of(&self.slots[slot as usize]),
-        );
+        self.write_lookup(queue, world_coord, slot + 1);
 
-        let mask = chunk.occupancy_mask();
         self.occupancy_masks[slot as usize] = mask;
         queue.write_buffer(
             &self.occupancy_buffer,
@@ -160,14 +341,74 @@ impl ChunkAtlas {
         );
     }
 
-    /// Mark a slot as empty in the index buffer and clear its occupancy mask.
-    pub fn clear_slot(&mut self, queue: &wgpu::Queue, slot: u32) {
-        self.slots[slot as usize].flags = 0;
+    /// Re-upload only the voxels inside `dirty` for a chunk already resident
+    /// in `slot`, and refresh the occupancy bits of the 8x8x8 sub-regions it
+    /// overlaps. Much cheaper than [`upload_chunk`](Self::upload_chunk) for
+    /// small edits; the lookup entry is left untouched.
+    pub fn upload_sub_box(
+        &mut self,
+        queue: &wgpu::Queue,
+        slot: u32,
+        chunk: &Chunk,
+        dirty: DirtyBox,
+    ) {
+        let origin = slot_to_atlas_origin(slot, self.slots_per_axis) + dirty.min;
+        let size = dirty.size();
+        let data = extract_sub_box(chunk, dirty);
+
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &self.atlas_texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: origin.x,
+                    y: origin.y,
+                    z: origin.z,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            bytemuck::cast_slice(&data),
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(size.x * 4),
+                rows_per_image: Some(size.y),
+            },
+            wgpu::Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: size.z,
+            },
+        );
+
+        let old_mask = self.occupancy_masks[slot as usize];
+        let mask = chunk.update_occupancy_mask(old_mask, dirty.min, dirty.max);
+        if mask != old_mask {
+            self.occupancy_masks[slot as usize] = mask;
+            queue.write_buffer(
+                &self.occupancy_buffer,
+                u64::from(slot) * size_of::<u64>() as u64,
+                bytemuck::bytes_of(&mask),
+            );
+        }
+    }
+
+    /// Replace the static (emissive) lights registered for `slot`.
+    pub fn upload_static_lights(&self, queue: &wgpu::Queue, slot: u32, lights: &[StaticLight]) {
         queue.write_buffer(
-            &self.index_buffer,
-            u64::from(slot) * size_of::<ChunkSlotGpu>() as u64,
-            bytemuck::bytes_of(&self.slots[slot as usize]),
+            &self.static_light_buffer,
+            Self::static_light_offset(slot),
+            bytemuck::cast_slice(&pack_static_lights(lights)),
         );
+    }
+
+    /// Mark a slot as empty, drop it from the lookup and clear its occupancy
+    /// mask and static lights.
+    pub fn clear_slot(&mut self, queue: &wgpu::Queue, slot: u32) {
+        let entry = self.slots[slot as usize];
+        self.slots[slot as usize].flags = 0;
+        if entry.flags == 1 {
+            self.write_lookup(queue, entry.world_pos, 0);
+        }
 
         self.occupancy_masks[slot as usize] = 0;
         queue.write_buffer(
@@ -175,6 +416,17 @@ impl ChunkAtlas {
             u64::from(slot) * size_of::<u64>() as u64,
             bytemuck::bytes_of(&0u64),
         );
+
+        // Zeroing the header is enough: the shader reads no lights past `count`.
+        queue.write_buffer(
+            &self.static_light_buffer,
+            Self::static_light_offset(slot),
+            bytemuck::bytes_of(&0u32),
+        );
+    }
+
+    fn static_light_offset(slot: u32) -> u64 {
+        u64::from(slot) * (STATIC_LIGHT_SLOT_FLOATS * size_of::<f32>()) as u64
     }
 
     /// Returns a reference to the atlas texture view.
@@ -183,10 +435,10 @@ impl ChunkAtlas {
         &self.atlas_view
     }
 
-    /// Returns a reference to the index buffer.
+    /// Returns a reference to the grid-to-slot lookup buffer.
     #[must_use]
-    pub fn index_buffer(&self) -> &wgpu::Buffer {
-        &self.index_buffer
+    pub fn lookup_buffer(&self) -> &wgpu::Buffer {
+        &self.lookup_buffer
     }
 
     /// Returns a reference to the occupancy bitmask GPU buffer.
@@ -195,6 +447,12 @@ impl ChunkAtlas {
         &self.occupancy_buffer
     }
 
+    /// Returns a reference to the per-slot static light buffer.
+    #[must_use]
+    pub fn static_light_buffer(&self) -> &wgpu::Buffer {
+        &self.static_light_buffer
+    }
+
     /// Returns the CPU-side occupancy masks (one `u64` per atlas slot).
     #[must_use]
     pub fn occupancy_masks(&self) -> &[u64] {
@@ -243,13 +501,6 @@ mod tests {
     use super::*;
     use crate::voxel::{CHUNK_SIZE, build_test_grid};
 
-    #[test]
-    fn atlas_slot_gpu_layout_matches_wgsl() {
-        assert_eq!(std::mem::offset_of!(ChunkSlotGpu, world_pos), 0);
-        assert_eq!(std::mem::offset_of!(ChunkSlotGpu, flags), 12);
-        assert_eq!(size_of::<ChunkSlotGpu>(), 16);
-    }
-
     #[test]
     fn slot_to_atlas_origin_maps_correctly() {
         let chunk = CHUNK_SIZE as u32;
@@ -267,36 +518,79 @@ mod tests {
     }
 
     #[test]
-    fn world_to_slot_origin() {
-        let slots = UVec3::new(8, 2, 8);
-        assert_eq!(world_to_slot(IVec3::ZERO, slots), 0);
+    fn allocator_hands_out_free_slots_lowest_first() {
+        let mut slots = SlotAllocator::new(2);
+        let a = slots.allocate(IVec3::X, |_| false).unwrap();
+        let b = slots.allocate(IVec3::Y, |_| false).unwrap();
+        assert_eq!((a.slot, a.evicted), (0, None));
+        assert_eq!((b.slot, b.evicted), (1, None));
+        assert_eq!(slots.free_count(), 0);
+
+        slots.release(0);
+        let c = slots.allocate(IVec3::Z, |_| false).unwrap();
+        assert_eq!((c.slot, c.evicted), (0, None));
+        assert_eq!(slots.owner(0), Some(IVec3::Z));
     }
 
     #[test]
-    fn world_to_slot_positive_coords() {
-        let slots = UVec3::new(8, 2, 8);
-        assert_eq!(world_to_slot(IVec3::new(1, 0, 0), slots), 1);
-        assert_eq!(world_to_slot(IVec3::new(0, 1, 0), slots), 8);
-        assert_eq!(world_to_slot(IVec3::new(0, 0, 1), slots), 16);
-        assert_eq!(
-            world_to_slot(IVec3::new(3, 1, 3), slots),
-            3 * 16 + 1 * 8 + 3
-        );
+    fn allocator_evicts_least_recently_used_unprotected_owner() {
+        let mut slots = SlotAllocator::new(3);
+        for (i, x) in (0..3).enumerate() {
+            let slot = slots.allocate(IVec3::new(x, 0, 0), |_| false).unwrap().slot;
+            slots.touch(slot, i as u64);
+        }
+        slots.touch(0, 5);
+        // Slot 1 is now the oldest.
+        let next = slots.allocate(IVec3::new(3, 0, 0), |_| false).unwrap();
+        assert_eq!(next.slot, 1);
+        assert_eq!(next.evicted, Some(IVec3::new(1, 0, 0)));
+
+        // Protected owners are skipped even when older.
+        let next = slots
+            .allocate(IVec3::new(4, 0, 0), |c| c == IVec3::new(2, 0, 0))
+            .unwrap();
+        assert_eq!(next.evicted, Some(IVec3::new(3, 0, 0)));
+        assert!(slots.allocate(IVec3::new(5, 0, 0), |_| true).is_none());
     }
 
     #[test]
-    fn world_to_slot_wraps_at_atlas_boundary() {
-        let slots = UVec3::new(8, 2, 8);
-        assert_eq!(world_to_slot(IVec3::new(8, 0, 0), slots), 0);
-        assert_eq!(world_to_slot(IVec3::new(9, 0, 0), slots), 1);
+    fn extract_sub_box_packs_x_fastest() {
+        let mut chunk = Chunk::default();
+        chunk.set_voxel(3, 4, 5, 1);
+        chunk.set_voxel(4, 4, 5, 2);
+        chunk.set_voxel(3, 5, 5, 3);
+        chunk.set_voxel(4, 5, 6, 4);
+        let dirty = DirtyBox {
+            min: UVec3::new(3, 4, 5),
+            max: UVec3::new(4, 5, 6),
+        };
+        assert_eq!(extract_sub_box(&chunk, dirty), vec![1, 2, 3, 0, 0, 0, 0, 4]);
     }
 
     #[test]
-    fn world_to_slot_negative_coords() {
-        let slots = UVec3::new(8, 2, 8);
-        assert_eq!(world_to_slot(IVec3::new(-1, 0, 0), slots), 7);
-        assert_eq!(world_to_slot(IVec3::new(-8, 0, 0), slots), 0);
-        assert_eq!(world_to_slot(IVec3::new(-1, -1, -1), slots), 127);
+    fn sub_box_upload_updates_occupancy_incrementally() {
+        let gpu =
+            pollster::block_on(crate::render::gpu::GpuContext::new_headless()).expect("GPU init");
+        let mut atlas = ChunkAtlas::new(&gpu.device, UVec3::new(8, 2, 8));
+        let grid = build_test_grid();
+        let (coord, chunk) = &grid[0];
+        let mut chunk = chunk.clone();
+        atlas.upload_chunk(&gpu.queue, 0, &chunk, *coord);
+        // Carve out the whole bottom-corner sub-region.
+        for z in 0..8 {
+            for y in 0..8 {
+                for x in 0..8 {
+                    chunk.set_voxel(x, y, z, 0);
+                }
+            }
+        }
+        let dirty = DirtyBox {
+            min: UVec3::ZERO,
+            max: UVec3::splat(7),
+        };
+        atlas.upload_sub_box(&gpu.queue, 0, &chunk, dirty);
+        assert_eq!(atlas.occupancy_masks()[0], chunk.occupancy_mask());
+        assert_eq!(atlas.occupancy_masks()[0] & 1, 0);
     }
 
     #[test]
@@ -351,6 +645,27 @@ mod tests {
         assert_eq!(atlas.occupancy_masks()[0], 0);
     }
 
+    #[test]
+    fn lookup_tracks_uploads_inside_the_grid() {
+        let gpu =
+            pollster::block_on(crate::render::gpu::GpuContext::new_headless()).expect("GPU init");
+        let mut atlas = ChunkAtlas::new(&gpu.device, UVec3::new(2, 2, 2));
+        let grid = build_test_grid();
+        let (_, chunk) = &grid[0];
+        atlas.set_grid(&gpu.queue, IVec3::new(-1, 0, 0), UVec3::new(2, 1, 1));
+        atlas.upload_chunk(&gpu.queue, 5, chunk, IVec3::ZERO);
+        atlas.upload_chunk(&gpu.queue, 6, chunk, IVec3::new(9, 0, 0));
+        assert_eq!(atlas.lookup_slot(IVec3::ZERO), Some(5));
+        assert_eq!(atlas.lookup_slot(IVec3::new(-1, 0, 0)), None);
+        assert_eq!(atlas.lookup_slot(IVec3::new(9, 0, 0)), None);
+
+        // Moving the grid picks up chunks uploaded while outside it.
+        atlas.set_grid(&gpu.queue, IVec3::new(8, 0, 0), UVec3::new(2, 1, 1));
+        assert_eq!(atlas.lookup_slot(IVec3::new(9, 0, 0)), Some(6));
+        atlas.clear_slot(&gpu.queue, 6);
+        assert_eq!(atlas.lookup_slot(IVec3::new(9, 0, 0)), None);
+    }
+
     #[test]
     fn atlas_upload_populates_index() {
         let gpu =
@@ -9,6 +9,7 @@ pub mod particle_pass;
 pub mod pipeline_helpers;
 pub mod raymarch_pass;
 pub mod sprite_pass;
+pub mod static_lights;
 
 #[cfg(feature = "wasm")]
 use blit_pass::BlitPass;
@@ -23,6 +24,8 @@ use sprite_pass::SpritePass;
 #[cfg(feature = "wasm")]
 use web_sys::OffscreenCanvas;
 
+#[cfg(feature = "wasm")]
+use crate::biome::BiomeMap;
 #[cfg(feature = "wasm")]
 use crate::camera::{
     Camera, CameraAnimation, CameraIntent, EasingKind, GridInfo, InputState, SPRINT_MULTIPLIER,
@@ -30,12 +33,28 @@ use crate::camera::{
 #[cfg(feature = "wasm")]
 use crate::chunk_manager::ChunkManager;
 #[cfg(feature = "wasm")]
+use crate::chunk_payload::WorldInfo;
+#[cfg(feature = "wasm")]
+use crate::chunk_source::{ChunkSource, HttpSource};
+#[cfg(feature = "wasm")]
 use crate::collision::CollisionMap;
 #[cfg(feature = "wasm")]
+use crate::dungeon::Dungeon;
+#[cfg(feature = "wasm")]
+use crate::interest::{InterestPoint, PinnedRegion};
+#[cfg(feature = "wasm")]
+use crate::load_priority::LoadView;
+#[cfg(feature = "wasm")]
 use crate::map_features::MapConfig;
 #[cfg(feature = "wasm")]
+use crate::mutation::VoxelRegion;
+#[cfg(feature = "wasm")]
 use crate::particle_system::ParticleSystem;
 #[cfg(feature = "wasm")]
+use crate::view_volume::{SurfaceBand, ViewShape, ViewVolume};
+#[cfg(feature = "wasm")]
+use crate::voxel::{TEST_GRID_SEED, pos_to_chunk_coord};
+#[cfg(feature = "wasm")]
 use glam::{IVec3, UVec3, Vec3};
 
 /// Layout indices for the `collect_stats()` return vector.
@@ -67,16 +86,16 @@ pub const STAT_SPRITE_COUNT: usize = 23;
 pub const STAT_LIGHT_COUNT: usize = 24;
 pub const STAT_RENDER_SCALE: usize = 25;
 pub const STAT_SHADER_PRESET: usize = 26;
-pub const STAT_VEC_LEN: usize = 27;
+pub const STAT_CHUNK_VOXEL_BYTES: usize = 27;
+pub const STAT_IN_FLIGHT_CHUNKS: usize = 28;
+pub const STAT_VEC_LEN: usize = 29;
 
-/// Material palette: 256 RGBA entries. Phase 2 uses 4 materials.
+/// GPU material palette for the built-in materials: 256 RGBA entries
+/// followed by 256 emission entries.
+/// See [`MaterialRegistry::gpu_palette`](crate::material::MaterialRegistry::gpu_palette).
 #[must_use]
 pub fn build_palette() -> Vec<[f32; 4]> {
-    let mut palette = vec![[0.0, 0.0, 0.0, 1.0]; 256];
-    palette[1] = [0.3, 0.7, 0.2, 1.0]; // grass
-    palette[2] = [0.5, 0.3, 0.1, 1.0]; // dirt
-    palette[3] = [0.5, 0.5, 0.5, 1.0]; // stone
-    palette
+    crate::material::MaterialRegistry::default().gpu_palette()
 }
 
 const PRESET_COUNT: u32 = 5;
@@ -160,8 +179,8 @@ impl ShaderFeatures {
     }
 }
 
-/// Atlas slot dimensions along each axis. Must be >= the test grid dimensions.
-/// The atlas texture is `ATLAS_SLOTS_* * CHUNK_SIZE` texels per axis.
+/// Atlas slot dimensions along each axis. The slots must hold the
+/// [`VIEW_VOLUME`]'s visible chunks; the rest cache recently seen ones. The atlas texture is `ATLAS_SLOTS_* * CHUNK_SIZE` texels per axis.
 #[cfg(feature = "wasm")]
 const ATLAS_SLOTS_X: u32 = 8;
 #[cfg(feature = "wasm")]
@@ -169,11 +188,22 @@ const ATLAS_SLOTS_Y: u32 = 8;
 #[cfg(feature = "wasm")]
 const ATLAS_SLOTS_Z: u32 = 8;
 
-/// View distance in chunks. The camera sees chunks within this radius.
+/// Chunks streamed around the camera: a wide cylinder kept to the chunks
+/// just above and below the terrain surface and around the camera (243
+/// chunks near the surface, at most 486).
 #[cfg(feature = "wasm")]
-const VIEW_DISTANCE: u32 = 3;
+const VIEW_VOLUME: ViewVolume = ViewVolume {
+    horizontal: 5,
+    vertical: 3,
+    shape: ViewShape::Cylinder,
+    surface_band: Some(SurfaceBand { below: 1, above: 1 }),
+};
 
-/// Maximum chunks loaded per frame. At 60fps, fills a 343-chunk view (~1.4s).
+/// Interest point name used by [`Renderer::preload_view`].
+#[cfg(feature = "wasm")]
+const PRELOAD_INTEREST: &str = "preload";
+
+/// Maximum chunks loaded per frame. At 60fps, fills a 243-chunk view (~1s).
 #[cfg(feature = "wasm")]
 const CHUNK_BUDGET_PER_TICK: u32 = 4;
 
@@ -189,14 +219,19 @@ pub struct Renderer {
     particle_system: ParticleSystem,
     _storage_texture: wgpu::Texture,
     chunk_manager: ChunkManager,
+    dungeon: Option<std::sync::Arc<Dungeon>>,
     light_buffer: light_buffer::LightBuffer,
     camera: Camera,
     grid_info: GridInfo,
     input: InputState,
     animation: Option<CameraAnimation>,
-    preload_position: Option<Vec3>,
     animation_just_completed: bool,
     tick_stats: Option<crate::chunk_manager::TickStats>,
+    /// World the local generator produces; a chunk server must match it.
+    world: WorldInfo,
+    /// Chunk of the last [`preload_view`](Self::preload_view) position;
+    /// its interest point is dropped once the camera gets there.
+    preload_target: Option<IVec3>,
     projection_mode: u32,
     ortho_size: f32,
     surface_width: u32,
@@ -235,17 +270,21 @@ impl Renderer {
         let storage_view = storage_texture.create_view(&wgpu::TextureViewDescriptor::default());
 
         let atlas_slots = UVec3::new(ATLAS_SLOTS_X, ATLAS_SLOTS_Y, ATLAS_SLOTS_Z);
-        let map_config = MapConfig::default();
+        let map_config = MapConfig::default().with_biomes(BiomeMap::standard(TEST_GRID_SEED));
+        let world = WorldInfo::of(&map_config);
+        let surface_height = map_config.surface_height();
+        let dungeon = map_config.dungeon.clone();
         let chunk_gen = Box::new(move |coord: IVec3| map_config.generate_chunk(coord));
         let mut chunk_manager =
-            ChunkManager::with_chunk_gen(&gpu.device, VIEW_DISTANCE, atlas_slots, chunk_gen);
+            ChunkManager::with_view_volume(&gpu.device, VIEW_VOLUME, atlas_slots, chunk_gen);
+        chunk_manager.set_surface_height(Some(surface_height));
 
         // Initial tick loads chunks around default camera position.
         let camera = Camera::default();
         let grid_info = chunk_manager.tick(&gpu.queue, camera.position);
 
         let camera_uniform = camera.to_uniform(render_width, render_height, &grid_info);
-        let palette = build_palette();
+        let palette = chunk_manager.materials().gpu_palette();
 
         let light_buffer = light_buffer::LightBuffer::new(&gpu.device, 64);
 
@@ -295,14 +334,16 @@ impl Renderer {
             particle_system,
             _storage_texture: storage_texture,
             chunk_manager,
+            dungeon,
             light_buffer,
             camera,
             grid_info,
             input: InputState::default(),
             animation: None,
-            preload_position: None,
             animation_just_completed: false,
             tick_stats: None,
+            world,
+            preload_target: None,
             projection_mode: 0,
             ortho_size: 0.0,
             surface_width: width,
@@ -357,6 +398,14 @@ impl Renderer {
             }
         }
 
+        #[allow(clippy::cast_precision_loss)]
+        let aspect = self.render_width as f32 / self.render_height as f32;
+        let view = if self.projection_mode == 1 {
+            LoadView::orthographic(&self.camera, aspect, self.ortho_size)
+        } else {
+            LoadView::perspective(&self.camera, aspect)
+        };
+        self.chunk_manager.set_view(Some(view));
         let tick_result = self.chunk_manager.tick_budgeted_with_prediction(
             &self.gpu.queue,
             self.camera.position,
@@ -365,13 +414,9 @@ impl Renderer {
         );
         self.grid_info = tick_result.grid_info;
         self.tick_stats = Some(tick_result.stats);
-
-        // Load chunks around preload position if set.
-        if let Some(preload) = self.preload_position {
-            let vd = self.chunk_manager.view_distance();
-            for coord in ChunkManager::compute_visible_set(preload, vd) {
-                self.chunk_manager.load_chunk(&self.gpu.queue, coord);
-            }
+        if self.preload_target == Some(pos_to_chunk_coord(self.camera.position)) {
+            self.chunk_manager.remove_interest_point(PRELOAD_INTEREST);
+            self.preload_target = None;
         }
 
         let mut camera_uniform =
@@ -490,10 +535,71 @@ impl Renderer {
         ));
     }
 
-    /// Hint that the camera will move to this position soon.
-    /// Chunks around this position will be loaded.
+    /// Stream chunks from the chunk server at `url`, or generate them
+    /// locally again when `url` is `None`. A server generating another
+    /// world is ignored and chunks keep being generated locally.
+    pub fn set_chunk_server(&mut self, url: Option<&str>) {
+        let world = self.world;
+        self.chunk_manager.set_source(
+            url.map(|url| Box::new(HttpSource::new(url, world)) as Box<dyn ChunkSource>),
+        );
+    }
+
+    /// Hint that the camera will move to this position soon. Chunks in
+    /// the camera's view volume around it load within the tick budget
+    /// until the camera reaches the position's chunk.
     pub fn preload_view(&mut self, x: f32, y: f32, z: f32) {
-        self.preload_position = Some(Vec3::new(x, y, z));
+        let point = InterestPoint {
+            position: Vec3::new(x, y, z),
+            volume: VIEW_VOLUME,
+            weight: 1.0,
+        };
+        if self
+            .chunk_manager
+            .set_interest_point(PRELOAD_INTEREST, point)
+        {
+            self.preload_target = Some(pos_to_chunk_coord(point.position));
+        }
+    }
+
+    /// Stream a `radius`-chunk cube around `(x, y, z)` under `name`. A
+    /// `weight` above 1 loads its chunks after camera chunks at the same
+    /// distance. Cubes larger than the chunk atlas are ignored.
+    pub fn set_interest_point(
+        &mut self,
+        name: &str,
+        x: f32,
+        y: f32,
+        z: f32,
+        radius: u32,
+        weight: f32,
+    ) {
+        let point = InterestPoint {
+            position: Vec3::new(x, y, z),
+            volume: ViewVolume::cube(radius),
+            weight,
+        };
+        if !self.chunk_manager.set_interest_point(name, point) {
+            log::warn!("interest point {name}: radius {radius} does not fit the chunk atlas");
+        }
+    }
+
+    /// Stop streaming around the interest point `name`.
+    pub fn remove_interest_point(&mut self, name: &str) {
+        self.chunk_manager.remove_interest_point(name);
+    }
+
+    /// Keep the chunks between chunk coordinates `a` and `b` loaded under
+    /// `name`. Regions larger than the chunk atlas are ignored.
+    pub fn pin_chunk_region(&mut self, name: &str, a: IVec3, b: IVec3) {
+        if !self.chunk_manager.pin_region(name, PinnedRegion::new(a, b)) {
+            log::warn!("pinned region {name}: {a}..={b} does not fit the chunk atlas");
+        }
+    }
+
+    /// Release the pinned region `name`.
+    pub fn unpin_chunk_region(&mut self, name: &str) {
+        self.chunk_manager.unpin_region(name);
     }
 
     /// Whether a camera animation is currently in progress.
@@ -526,12 +632,59 @@ impl Renderer {
         self.chunk_manager.is_loaded(glam::IVec3::new(cx, cy, cz))
     }
 
+    /// Rooms of the map's dungeon as a flat `[level, min_x, min_y, min_z,
+    /// max_x, max_y, max_z, ...]` list of inclusive interiors; empty when
+    /// the map has no dungeon.
+    #[must_use]
+    pub fn dungeon_rooms(&self) -> Vec<i32> {
+        self.dungeon.as_ref().map_or_else(Vec::new, |dungeon| {
+            dungeon
+                .layout()
+                .rooms
+                .iter()
+                .flat_map(|r| {
+                    let (min, max) = (r.min, r.max);
+                    [
+                        r.level.cast_signed(),
+                        min.x,
+                        min.y,
+                        min.z,
+                        max.x,
+                        max.y,
+                        max.z,
+                    ]
+                })
+                .collect()
+        })
+    }
+
+    /// Corridors of the map's dungeon as flat `[room_a, room_b, ...]` pairs
+    /// of indices into [`dungeon_rooms`](Self::dungeon_rooms).
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn dungeon_connections(&self) -> Vec<u32> {
+        self.dungeon.as_ref().map_or_else(Vec::new, |dungeon| {
+            dungeon
+                .layout()
+                .connections
+                .iter()
+                .flat_map(|&(a, b)| [a as u32, b as u32])
+                .collect()
+        })
+    }
+
     /// Whether the voxel at the given world position is solid.
     #[must_use]
     pub fn is_solid(&self, x: f32, y: f32, z: f32) -> bool {
         self.chunk_manager.is_solid(Vec3::new(x, y, z))
     }
 
+    /// Drain chunks whose terrain grid changed through vertical seam
+    /// stitching since the last call, as a flat `[cx, cy, cz, ...]` list.
+    pub fn take_dirty_terrain(&mut self) -> Vec<i32> {
+        flatten_coords(&self.chunk_manager.take_dirty_terrain())
+    }
+
     /// Returns the serialized terrain grid for the chunk at the given coordinate,
     /// or `None` if the chunk is not loaded.
     #[must_use]
@@ -570,17 +723,105 @@ impl Renderer {
         );
     }
 
+    /// The material terrain table; see
+    /// [`MaterialRegistry::terrain_table_bytes`](crate::material::MaterialRegistry::terrain_table_bytes).
+    #[must_use]
+    pub fn material_terrain_table(&self) -> Vec<u8> {
+        self.chunk_manager.materials().terrain_table_bytes()
+    }
+
+    /// Define or replace material `id` from the flat layout described in
+    /// [`MaterialDef::from_flat`](crate::material::MaterialDef::from_flat).
+    /// Updates the GPU palette and rebuilds collision/terrain for loaded
+    /// chunks. Returns `false` if `data` is too short or `id` is air.
+    pub fn define_material(&mut self, id: u8, name: &str, data: &[f32]) -> bool {
+        let Some(def) = crate::material::MaterialDef::from_flat(name, data) else {
+            return false;
+        };
+        if !self.chunk_manager.define_material(&self.gpu.queue, id, def) {
+            return false;
+        }
+        self.raymarch_pass.update_palette(
+            &self.gpu.queue,
+            &self.chunk_manager.materials().gpu_palette(),
+        );
+        true
+    }
+
     /// Mutate one or more voxels in loaded chunks.
     ///
     /// `data` is a flat `i32` slice where each group of 4 values is
-    /// `[world_x, world_y, world_z, material_id]`. Updates voxel data,
-    /// rebuilds collision/terrain, and re-uploads to the GPU atlas.
-    pub fn mutate_voxels(&mut self, data: &[i32]) {
-        for group in data.chunks_exact(4) {
-            let world_pos = IVec3::new(group[0], group[1], group[2]);
-            let material_id = group[3] as u8;
-            self.chunk_manager
-                .mutate_voxel(&self.gpu.queue, world_pos, material_id);
+    /// `[world_x, world_y, world_z, material_id]`. Each touched chunk rebuilds
+    /// collision/terrain and re-uploads to the GPU atlas once. Returns the
+    /// changed chunk coordinates as a flat `[cx, cy, cz, ...]` list.
+    pub fn mutate_voxels(&mut self, data: &[i32]) -> Vec<i32> {
+        let writes: Vec<(IVec3, u8)> = data
+            .chunks_exact(4)
+            .map(|g| (IVec3::new(g[0], g[1], g[2]), g[3] as u8))
+            .collect();
+        let changed = self.chunk_manager.mutate_voxels(&self.gpu.queue, &writes);
+        flatten_coords(&changed)
+    }
+
+    /// Set the gameplay flags byte of one or more voxels in loaded chunks,
+    /// e.g. to open or close doors or mark ladders and hazards.
+    ///
+    /// `data` is a flat `i32` slice where each group of 4 values is
+    /// `[world_x, world_y, world_z, flags]`. Returns the changed chunk
+    /// coordinates as a flat `[cx, cy, cz, ...]` list.
+    pub fn set_voxel_flags(&mut self, data: &[i32]) -> Vec<i32> {
+        let writes: Vec<(IVec3, u8)> = data
+            .chunks_exact(4)
+            .map(|g| (IVec3::new(g[0], g[1], g[2]), g[3] as u8))
+            .collect();
+        let changed = self.chunk_manager.set_voxel_flags(&self.gpu.queue, &writes);
+        flatten_coords(&changed)
+    }
+
+    /// Fill a region of voxels with `material_id`.
+    ///
+    /// `shape` selects how `params` is read:
+    /// - 0 (box): `[min_x, min_y, min_z, max_x, max_y, max_z]`
+    /// - 1 (sphere): `[center_x, center_y, center_z, radius]`
+    /// - 2 (line): `[from_x, from_y, from_z, to_x, to_y, to_z]`
+    /// - 3 (list): `[x, y, z, ...]`
+    ///
+    /// Unknown shapes, short `params` and regions larger than
+    /// [`MAX_REGION_VOXELS`](crate::mutation::MAX_REGION_VOXELS) change
+    /// nothing. Returns the changed chunk coordinates as a flat
+    /// `[cx, cy, cz, ...]` list.
+    pub fn mutate_region(&mut self, shape: u32, params: &[i32], material_id: u8) -> Vec<i32> {
+        let v = |i: usize| IVec3::new(params[i], params[i + 1], params[i + 2]);
+        let region = match (shape, params.len()) {
+            (0, 6..) => VoxelRegion::Box {
+                min: v(0),
+                max: v(3),
+            },
+            (1, 4..) => VoxelRegion::Sphere {
+                center: v(0),
+                radius: params[3],
+            },
+            (2, 6..) => VoxelRegion::Line {
+                from: v(0),
+                to: v(3),
+            },
+            (3, _) => VoxelRegion::List(
+                params
+                    .chunks_exact(3)
+                    .map(|c| IVec3::new(c[0], c[1], c[2]))
+                    .collect(),
+            ),
+            _ => return Vec::new(),
+        };
+        match self
+            .chunk_manager
+            .mutate_region(&self.gpu.queue, &region, material_id)
+        {
+            Ok(changed) => flatten_coords(&changed),
+            Err(e) => {
+                log::warn!("mutate_region: {e}");
+                Vec::new()
+            }
         }
     }
 
@@ -789,6 +1030,7 @@ impl Renderer {
             v[STAT_UNLOADED_THIS_TICK] = stats.unloaded_this_tick as f32;
             v[STAT_CHUNK_BUDGET] = stats.budget as f32;
             v[STAT_CACHED_CHUNKS] = stats.cached_count as f32;
+            v[STAT_IN_FLIGHT_CHUNKS] = stats.in_flight_count as f32;
         }
         let chunk_size = crate::voxel::CHUNK_SIZE as f32;
         v[STAT_CAMERA_CHUNK_X] = (self.camera.position.x / chunk_size).floor();
@@ -802,12 +1044,19 @@ impl Renderer {
         v[STAT_LIGHT_COUNT] = self.light_count as f32;
         v[STAT_RENDER_SCALE] = self.render_scale;
         v[STAT_SHADER_PRESET] = self.shader_preset as f32;
+        v[STAT_CHUNK_VOXEL_BYTES] = self.chunk_manager.voxel_memory_bytes() as f32;
         v
     }
 }
 
 // --- Resolution computation (pure math, no wasm gating) ---
 
+/// Flatten chunk coordinates into `[x, y, z, x, y, z, ...]` for JS interop.
+#[must_use]
+pub fn flatten_coords(coords: &[glam::IVec3]) -> Vec<i32> {
+    coords.iter().flat_map(|c| [c.x, c.y, c.z]).collect()
+}
+
 const PIXEL_BUDGET: f32 = 2_073_600.0; // 1920 * 1080
 const MIN_RENDER_W: u32 = 320;
 const MAX_RENDER_W: u32 = 1920;
@@ -869,6 +1118,12 @@ pub fn create_storage_texture(device: &wgpu::Device, width: u32, height: u32) ->
 mod tests {
     use super::*;
 
+    #[test]
+    fn flatten_coords_interleaves_axes() {
+        let coords = [glam::IVec3::new(1, 2, 3), glam::IVec3::new(-4, 5, -6)];
+        assert_eq!(flatten_coords(&coords), vec![1, 2, 3, -4, 5, -6]);
+    }
+
     #[test]
     fn render_dims_at_scale_1_0_small_surface() {
         assert_eq!(compute_render_dims(800, 600, 1.0), (800, 600));
@@ -73,6 +73,11 @@ impl RaymarchPass {
         queue.write_buffer(&self.camera_buffer, 0, bytemuck::bytes_of(uniform));
     }
 
+    /// Overwrite the material palette (256 RGBA entries).
+    pub fn update_palette(&self, queue: &wgpu::Queue, palette: &[[f32; 4]]) {
+        queue.write_buffer(&self.palette_buffer, 0, bytemuck::cast_slice(palette));
+    }
+
     /// Rebuilds the bind group to reference a new storage texture view after
     /// the window has been resized.
     pub fn rebuild_for_resize(
@@ -184,7 +189,7 @@ impl RaymarchPass {
         device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some(label),
             contents: bytemuck::cast_slice(data),
-            usage: wgpu::BufferUsages::STORAGE,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
         })
     }
 
@@ -315,7 +320,7 @@ impl RaymarchPass {
                     },
                     count: None,
                 },
-                // 3: chunk index buffer
+                // 3: grid-to-slot chunk lookup
                 read_only_storage(3),
                 // 4: material palette
                 read_only_storage(4),
@@ -336,6 +341,8 @@ impl RaymarchPass {
                 read_only_storage(7),
                 // 8: light buffer
                 read_only_storage(8),
+                // 9: per-slot static (emissive) lights
+                read_only_storage(9),
             ],
         })
     }
@@ -370,7 +377,7 @@ impl RaymarchPass {
                 },
                 wgpu::BindGroupEntry {
                     binding: 3,
-                    resource: atlas.index_buffer().as_entire_binding(),
+                    resource: atlas.lookup_buffer().as_entire_binding(),
                 },
                 wgpu::BindGroupEntry {
                     binding: 4,
@@ -392,6 +399,10 @@ impl RaymarchPass {
                     binding: 8,
                     resource: light_buffer.as_entire_binding(),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 9,
+                    resource: atlas.static_light_buffer().as_entire_binding(),
+                },
             ],
         })
     }
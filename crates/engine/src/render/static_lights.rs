@@ -0,0 +1,203 @@
+use glam::{IVec3, Vec3};
+
+use crate::material::MaterialRegistry;
+use crate::voxel::{CHUNK_SIZE, Chunk, material_id, voxel_coords};
+
+/// Static lights kept per chunk. Emissive voxels are clustered into at most
+/// this many lights so shading cost stays bounded however much lava a chunk
+/// holds.
+pub const MAX_STATIC_LIGHTS_PER_CHUNK: usize = 8;
+
+/// Upper bound on a static light's radius. Keeping it at half a chunk lets
+/// the shader find every light affecting a point by checking only the 2x2x2
+/// chunks nearest to it.
+pub const MAX_STATIC_LIGHT_RADIUS: f32 = 16.0;
+
+/// Per-slot layout: a `[count, pad, pad, pad]` header followed by
+/// [`MAX_STATIC_LIGHTS_PER_CHUNK`] entries of `[x, y, z, radius, r, g, b, pad]`.
+pub const STATIC_LIGHT_SLOT_FLOATS: usize = 4 + MAX_STATIC_LIGHTS_PER_CHUNK * 8;
+
+/// Edge length of the clustering cells, matching the occupancy sub-regions.
+const CELL: usize = 8;
+const CELLS_PER_AXIS: usize = CHUNK_SIZE / CELL;
+
+/// A light derived from emissive voxels when a chunk is uploaded. Unlike
+/// [`Light`](super::light_buffer::Light), static lights live in a per-slot
+/// GPU buffer and do not count against the dynamic light budget.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StaticLight {
+    pub position: Vec3,
+    pub radius: f32,
+    pub color: Vec3,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Cluster {
+    count: u32,
+    weight: f32,
+    position: Vec3,
+    color: Vec3,
+}
+
+/// Cluster the emissive voxels of `chunk` (at chunk coordinate `coord`) into
+/// static lights.
+///
+/// Voxels are binned into 8x8x8 cells; each non-empty cell becomes one light
+/// at its intensity-weighted centroid, colored by the average emission and
+/// with a radius that grows with the number of emitting voxels. The
+/// strongest [`MAX_STATIC_LIGHTS_PER_CHUNK`] cells are kept.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn chunk_static_lights(
+    chunk: &Chunk,
+    coord: IVec3,
+    materials: &MaterialRegistry,
+) -> Vec<StaticLight> {
+    if !materials.has_emissive() {
+        return Vec::new();
+    }
+    // Most chunks hold no emitting material at all; their palette says so
+    // without visiting every voxel.
+    if let Some(palette) = chunk.storage().palette()
+        && palette
+            .iter()
+            .all(|&v| materials.emissive(material_id(v)) <= 0.0)
+    {
+        return Vec::new();
+    }
+
+    let mut cells = [Cluster::default(); CELLS_PER_AXIS * CELLS_PER_AXIS * CELLS_PER_AXIS];
+    for (index, voxel) in chunk.iter().enumerate() {
+        let mat = material_id(voxel);
+        let emissive = materials.emissive(mat);
+        if emissive <= 0.0 {
+            continue;
+        }
+        let (x, y, z) = voxel_coords(index);
+        let cell = &mut cells
+            [x / CELL + (y / CELL) * CELLS_PER_AXIS + (z / CELL) * CELLS_PER_AXIS * CELLS_PER_AXIS];
+        let color = materials
+            .get(mat)
+            .map_or(Vec3::ONE, |d| Vec3::from(d.color));
+        cell.count += 1;
+        cell.weight += emissive;
+        cell.position += Vec3::new(x as f32, y as f32, z as f32) * emissive;
+        cell.color += color * emissive;
+    }
+
+    let mut ranked: Vec<(usize, Cluster)> = cells
+        .into_iter()
+        .enumerate()
+        .filter(|(_, c)| c.count > 0)
+        .collect();
+    // Strongest first; cell index breaks ties so the result is deterministic.
+    ranked.sort_by(|(ia, a), (ib, b)| b.weight.total_cmp(&a.weight).then(ia.cmp(ib)));
+
+    let origin = coord.as_vec3() * CHUNK_SIZE as f32;
+    ranked
+        .into_iter()
+        .take(MAX_STATIC_LIGHTS_PER_CHUNK)
+        .map(|(_, c)| StaticLight {
+            position: origin + c.position / c.weight + Vec3::splat(0.5),
+            radius: (4.0 + 2.0 * (c.count as f32).sqrt()).min(MAX_STATIC_LIGHT_RADIUS),
+            color: c.color / c.count as f32,
+        })
+        .collect()
+}
+
+/// Pack the lights for one atlas slot into [`STATIC_LIGHT_SLOT_FLOATS`]
+/// floats. Lights beyond [`MAX_STATIC_LIGHTS_PER_CHUNK`] are dropped.
+#[must_use]
+pub fn pack_static_lights(lights: &[StaticLight]) -> Vec<f32> {
+    let count = lights.len().min(MAX_STATIC_LIGHTS_PER_CHUNK);
+    let mut data = vec![0.0; STATIC_LIGHT_SLOT_FLOATS];
+    data[0] = f32::from_bits(count as u32);
+    for (i, light) in lights.iter().take(count).enumerate() {
+        let offset = 4 + i * 8;
+        data[offset..offset + 8].copy_from_slice(&[
+            light.position.x,
+            light.position.y,
+            light.position.z,
+            light.radius,
+            light.color.x,
+            light.color.y,
+            light.color.z,
+            0.0,
+        ]);
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxel::{MAT_GLOWSTONE, MAT_LAVA, MAT_STONE, pack_voxel};
+
+    fn empty_chunk() -> Chunk {
+        Chunk::default()
+    }
+
+    #[test]
+    fn non_emissive_chunk_has_no_lights() {
+        let mut chunk = empty_chunk();
+        chunk.set_voxel(1, 1, 1, pack_voxel(MAT_STONE, 0, 0, 0));
+        let lights = chunk_static_lights(&chunk, IVec3::ZERO, &MaterialRegistry::default());
+        assert!(lights.is_empty());
+    }
+
+    #[test]
+    fn stale_emissive_palette_entry_has_no_lights() {
+        let mut chunk = empty_chunk();
+        chunk.set_voxel(1, 1, 1, pack_voxel(MAT_GLOWSTONE, 0, 0, 0));
+        chunk.set_voxel(1, 1, 1, pack_voxel(MAT_STONE, 0, 0, 0));
+        let lights = chunk_static_lights(&chunk, IVec3::ZERO, &MaterialRegistry::default());
+        assert!(lights.is_empty());
+    }
+
+    #[test]
+    fn single_emitter_lights_its_voxel_center() {
+        let mut chunk = empty_chunk();
+        chunk.set_voxel(3, 4, 5, pack_voxel(MAT_GLOWSTONE, 0, 0, 0));
+        let lights =
+            chunk_static_lights(&chunk, IVec3::new(1, 0, -1), &MaterialRegistry::default());
+        assert_eq!(lights.len(), 1);
+        assert!((lights[0].position - Vec3::new(35.5, 4.5, -26.5)).length() < 1e-4);
+        assert!((lights[0].color - Vec3::new(1.0, 0.85, 0.5)).length() < 1e-4);
+        assert!(lights[0].radius > 0.0 && lights[0].radius <= MAX_STATIC_LIGHT_RADIUS);
+    }
+
+    #[test]
+    fn emitters_cluster_per_cell_and_cap_per_chunk() {
+        let mut chunk = empty_chunk();
+        let lava = pack_voxel(MAT_LAVA, 0, 0, 0);
+        for z in 0..CHUNK_SIZE {
+            for x in 0..CHUNK_SIZE {
+                chunk.set_voxel(x, 0, z, lava);
+            }
+        }
+        let lights = chunk_static_lights(&chunk, IVec3::ZERO, &MaterialRegistry::default());
+        assert_eq!(lights.len(), MAX_STATIC_LIGHTS_PER_CHUNK);
+        let again = chunk_static_lights(&chunk, IVec3::ZERO, &MaterialRegistry::default());
+        assert_eq!(lights, again);
+    }
+
+    #[test]
+    fn pack_writes_count_and_entries() {
+        let light = StaticLight {
+            position: Vec3::new(1.0, 2.0, 3.0),
+            radius: 6.0,
+            color: Vec3::new(0.5, 0.25, 0.125),
+        };
+        let data = pack_static_lights(&[light; MAX_STATIC_LIGHTS_PER_CHUNK + 3]);
+        assert_eq!(data.len(), STATIC_LIGHT_SLOT_FLOATS);
+        assert_eq!(data[0].to_bits(), MAX_STATIC_LIGHTS_PER_CHUNK as u32);
+        let first = &data[4..12];
+        let expected = [1.0, 2.0, 3.0, 6.0, 0.5, 0.25, 0.125, 0.0];
+        assert!(
+            first
+                .iter()
+                .zip(expected)
+                .all(|(a, b)| (a - b).abs() < 1e-6)
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use glam::{IVec2, IVec3};
+
+use crate::map_rng::{FEATURE_BOULDERS, MapRng};
+use crate::view_volume::SurfaceHeight;
+use crate::voxel::{CHUNK_SIZE, Chunk, MAT_STONE, pack_voxel};
+
+/// Voxels at world positions, emitted by a [`StructurePlacer`] and stamped
+/// into every chunk they overlap.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Structure {
+    blocks: Vec<(IVec3, u32)>,
+    min: IVec3,
+    max: IVec3,
+}
+
+impl Structure {
+    /// Set the voxel at world position `pos`. Later blocks overwrite
+    /// earlier ones at the same position.
+    pub fn set(&mut self, pos: IVec3, voxel: u32) {
+        if self.blocks.is_empty() {
+            (self.min, self.max) = (pos, pos);
+        } else {
+            self.min = self.min.min(pos);
+            self.max = self.max.max(pos);
+        }
+        self.blocks.push((pos, voxel));
+    }
+
+    #[must_use]
+    pub fn blocks(&self) -> &[(IVec3, u32)] {
+        &self.blocks
+    }
+
+    /// Inclusive world bounds, or `None` for an empty structure.
+    #[must_use]
+    pub fn bounds(&self) -> Option<(IVec3, IVec3)> {
+        (!self.blocks.is_empty()).then_some((self.min, self.max))
+    }
+
+    /// Write the blocks that fall inside the chunk at `chunk_coord`.
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    pub fn stamp(&self, chunk: &mut Chunk, chunk_coord: IVec3) {
+        let cs = CHUNK_SIZE as i32;
+        let chunk_min = chunk_coord * cs;
+        let chunk_max = chunk_min + cs - 1;
+        if self.blocks.is_empty()
+            || self.max.cmplt(chunk_min).any()
+            || self.min.cmpgt(chunk_max).any()
+        {
+            return;
+        }
+        for &(pos, voxel) in &self.blocks {
+            if pos.cmpge(chunk_min).all() && pos.cmple(chunk_max).all() {
+                let local = (pos - chunk_min).as_uvec3();
+                chunk.set_voxel(local.x as usize, local.y as usize, local.z as usize, voxel);
+            }
+        }
+    }
+}
+
+/// Places structures larger than a chunk, such as boulders, buildings or
+/// bridges.
+///
+/// The world is divided into square x/z regions; each region's structures
+/// come only from its own [`MapRng`], so every chunk that a structure
+/// overlaps rebuilds it identically, whichever chunk generates first.
+pub trait StructurePlacer: Send + Sync {
+    /// Feature id keying this placer's [`MapRng`] streams; see the
+    /// `FEATURE_*` constants in [`crate::map_rng`].
+    fn id(&self) -> u32;
+
+    /// Side of a placement region, in voxels.
+    fn region_size(&self) -> i32;
+
+    /// How far a structure may extend past the edges of its region, in
+    /// voxels. Blocks beyond it are not stamped.
+    fn reach(&self) -> i32;
+
+    /// Emit the structures of `region` (region coordinates along x and z),
+    /// drawing randomness only from `rng`.
+    fn place(
+        &self,
+        region: IVec2,
+        rng: &mut MapRng,
+        surface: &SurfaceHeight,
+        out: &mut Vec<Structure>,
+    );
+}
+
+/// The structures `placer` emits for `region` on a map seeded with `seed`.
+#[must_use]
+pub fn structures_in_region(
+    placer: &dyn StructurePlacer,
+    seed: u32,
+    region: IVec2,
+    surface: &SurfaceHeight,
+) -> Vec<Structure> {
+    let mut rng = MapRng::for_region(seed, placer.id(), region);
+    let mut out = Vec::new();
+    placer.place(region, &mut rng, surface, &mut out);
+    out
+}
+
+/// Stamp every structure of `placer` that overlaps the chunk at
+/// `chunk_coord`. Structures are stamped in region order (z, then x) and
+/// then emission order, so overlapping structures resolve the same way in
+/// every chunk.
+#[allow(clippy::cast_possible_wrap)]
+pub fn stamp_structures(
+    placer: &dyn StructurePlacer,
+    seed: u32,
+    surface: &SurfaceHeight,
+    chunk: &mut Chunk,
+    chunk_coord: IVec3,
+) {
+    let cs = CHUNK_SIZE as i32;
+    let size = placer.region_size();
+    let reach = placer.reach();
+    let lo = IVec2::new(chunk_coord.x, chunk_coord.z) * cs - reach;
+    let hi = IVec2::new(chunk_coord.x, chunk_coord.z) * cs + cs - 1 + reach;
+    let (lo, hi) = (
+        lo.div_euclid(IVec2::splat(size)),
+        hi.div_euclid(IVec2::splat(size)),
+    );
+    for rz in lo.y..=hi.y {
+        for rx in lo.x..=hi.x {
+            for structure in structures_in_region(placer, seed, IVec2::new(rx, rz), surface) {
+                structure.stamp(chunk, chunk_coord);
+            }
+        }
+    }
+}
+
+/// Side of a boulder placement region, in voxels.
+const BOULDER_REGION: i32 = 64;
+
+const MAX_BOULDER_RADIUS: i32 = 4;
+
+/// Chebyshev distance from the origin inside which no boulders are placed:
+/// `FlattenNearOrigin` rewrites those columns, which would cut them apart.
+const SPAWN_CLEARANCE: i32 = 72;
+
+/// Scatters stone boulders, half buried in the base terrain surface, up to
+/// `per_region` in each 64-voxel region.
+pub struct ScatterBoulders {
+    pub per_region: u32,
+}
+
+impl StructurePlacer for ScatterBoulders {
+    fn id(&self) -> u32 {
+        FEATURE_BOULDERS
+    }
+
+    fn region_size(&self) -> i32 {
+        BOULDER_REGION
+    }
+
+    fn reach(&self) -> i32 {
+        MAX_BOULDER_RADIUS
+    }
+
+    fn place(
+        &self,
+        region: IVec2,
+        rng: &mut MapRng,
+        surface: &SurfaceHeight,
+        out: &mut Vec<Structure>,
+    ) {
+        let origin = region * BOULDER_REGION;
+        for _ in 0..rng.range(0, self.per_region.cast_signed()) {
+            let x = origin.x + rng.range(0, BOULDER_REGION - 1);
+            let z = origin.y + rng.range(0, BOULDER_REGION - 1);
+            let radius = rng.range(2, MAX_BOULDER_RADIUS);
+            if x.abs().max(z.abs()) < SPAWN_CLEARANCE {
+                continue;
+            }
+            let center = IVec3::new(x, surface(x, z), z);
+            let mut boulder = Structure::default();
+            for dz in -radius..=radius {
+                for dy in -radius..=radius {
+                    for dx in -radius..=radius {
+                        let offset = IVec3::new(dx, dy, dz);
+                        if offset.length_squared() <= radius * radius + radius {
+                            boulder.set(center + offset, pack_voxel(MAT_STONE, 0, 0, 0));
+                        }
+                    }
+                }
+            }
+            out.push(boulder);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voxel::{MAT_AIR, material_id};
+
+    /// One stone bar per 16-voxel region, running 20 voxels along +x from
+    /// the region's corner, so every bar crosses region and chunk borders.
+    struct Bars;
+
+    impl StructurePlacer for Bars {
+        fn id(&self) -> u32 {
+            100
+        }
+
+        fn region_size(&self) -> i32 {
+            16
+        }
+
+        fn reach(&self) -> i32 {
+            4
+        }
+
+        fn place(
+            &self,
+            region: IVec2,
+            rng: &mut MapRng,
+            _surface: &SurfaceHeight,
+            out: &mut Vec<Structure>,
+        ) {
+            let start = IVec3::new(region.x * 16, rng.range(0, 31), region.y * 16);
+            let mut bar = Structure::default();
+            for i in 0..20 {
+                bar.set(start + IVec3::X * i, pack_voxel(MAT_STONE, 0, 0, 0));
+            }
+            out.push(bar);
+        }
+    }
+
+    fn flat(_: i32, _: i32) -> i32 {
+        0
+    }
+
+    #[test]
+    #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+    fn structures_are_stamped_into_every_chunk_they_cross() {
+        let cs = CHUNK_SIZE as i32;
+        let air = pack_voxel(MAT_AIR, 0, 0, 0);
+        let chunks: Vec<(IVec3, Chunk)> = [IVec3::new(-1, 0, 0), IVec3::new(0, 0, 0)]
+            .into_iter()
+            .map(|coord| {
+                let mut chunk = Chunk::uniform(air);
+                stamp_structures(&Bars, 9, &flat, &mut chunk, coord);
+                (coord, chunk)
+            })
+            .collect();
+        // The bar of region (-1, 0) spans x = -16..4, across the chunk border.
+        let bar = &structures_in_region(&Bars, 9, IVec2::new(-1, 0), &flat)[0];
+        let (min, max) = bar.bounds().unwrap();
+        assert!(min.x < 0 && max.x >= 0);
+        for &(pos, _) in bar.blocks() {
+            let (coord, chunk) = &chunks[usize::from(pos.x >= 0)];
+            let local = pos - *coord * cs;
+            let voxel = chunk.voxel_at(local.x as usize, local.y as usize, local.z as usize);
+            assert_eq!(material_id(voxel), MAT_STONE, "missing block at {pos}");
+        }
+    }
+
+    #[test]
+    fn boulders_keep_clear_of_spawn() {
+        let boulders = ScatterBoulders { per_region: 8 };
+        for rz in -1..1 {
+            for rx in -1..1 {
+                let region = IVec2::new(rx, rz);
+                assert!(structures_in_region(&boulders, 3, region, &flat).is_empty());
+            }
+        }
+        let far = structures_in_region(&boulders, 3, IVec2::new(4, 4), &flat);
+        assert!(
+            far.iter()
+                .all(|b| b.bounds().unwrap().0.y >= -MAX_BOULDER_RADIUS)
+        );
+    }
+}
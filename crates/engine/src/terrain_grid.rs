@@ -1,23 +1,50 @@
-use crate::voxel::{CHUNK_SIZE, Chunk, material_id};
+use crate::material::MaterialRegistry;
+use crate::voxel::{CHUNK_SIZE, Chunk, FLAG_CLIMBABLE, FLAG_HAZARD, flags, material_id};
+
+/// [`TileSurface::flags`] bit: the surface voxel or the voxel standing on
+/// it is climbable (ladder, vine).
+pub const TILE_CLIMBABLE: u8 = 1 << 0;
+/// [`TileSurface::flags`] bit: the surface voxel or the voxel standing on
+/// it is a damaging hazard.
+pub const TILE_HAZARD: u8 = 1 << 1;
+/// [`TileSurface::flags`] bit: the surface material is not walkable in the
+/// [`MaterialRegistry`].
+pub const TILE_BLOCKED: u8 = 1 << 2;
 
 /// A walkable surface detected in a voxel column.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct TileSurface {
     /// Surface height (y coordinate) within the chunk.
     pub y: u8,
-    /// Terrain type derived from the voxel's `material_id`.
+    /// The surface voxel's `material_id`. Its name and movement cost are
+    /// those of the [`MaterialRegistry`] entry.
     pub terrain_id: u8,
-    /// Number of air voxels above this surface before the next solid
-    /// (or `255` if the surface is at the top of the chunk).
+    /// Number of air voxels above this surface before the next solid, counted
+    /// into the chunk above once it has been stitched. `255` means the air
+    /// run reaches the top of the known world (the top of this chunk when the
+    /// chunk above is not loaded, or the top of the chunk above).
     pub headroom: u8,
+    /// Gameplay bits: [`TILE_CLIMBABLE`] and [`TILE_HAZARD`] from the voxel
+    /// flags of the surface voxel and the voxel directly above it, and
+    /// [`TILE_BLOCKED`] from the surface material.
+    pub flags: u8,
 }
 
-/// Maps a voxel `material_id` to a game-level terrain type.
-/// Currently a 1:1 passthrough; will diverge as terrain types are added.
-#[inline]
-#[must_use]
-pub const fn material_to_terrain(material_id: u8) -> u8 {
-    material_id
+impl TileSurface {
+    /// Whether entities may stand on this surface.
+    #[must_use]
+    pub const fn is_walkable(&self) -> bool {
+        self.flags & TILE_BLOCKED == 0
+    }
+}
+
+/// A [`TileSurface`] placed at its world-space height, as returned by
+/// [`ChunkManager::surfaces_at_world`](crate::chunk_manager::ChunkManager::surfaces_at_world).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WorldSurface {
+    /// World-space y of the solid voxel forming the surface.
+    pub y: i32,
+    pub surface: TileSurface,
 }
 
 /// A 32x32 grid of walkable-surface columns extracted from a single [`Chunk`].
@@ -28,57 +55,92 @@ pub const fn material_to_terrain(material_id: u8) -> u8 {
 pub struct TerrainGrid {
     /// One `Vec<TileSurface>` per column, indexed as `z * CHUNK_SIZE + x`.
     columns: Vec<Vec<TileSurface>>,
+    /// Per-column length of the air run at the bottom of the chunk directly
+    /// above (`0` = solid at y=0, `CHUNK_SIZE` = all air). `None` until
+    /// stitched against a loaded neighbour.
+    above_air: Option<Vec<u8>>,
 }
 
 impl TerrainGrid {
     /// Scans a chunk and extracts all walkable surfaces.
     ///
-    /// A surface exists wherever a solid voxel (`material_id` != 0) has air above
-    /// it, or is at the very top of the chunk (y = `CHUNK_SIZE` - 1).
+    /// A surface exists wherever a voxel whose material is solid in
+    /// `materials` has a non-solid voxel above it, or is at the very top of
+    /// the chunk (y = `CHUNK_SIZE` - 1).
     #[must_use]
-    #[allow(clippy::cast_possible_truncation)]
-    pub fn from_chunk(chunk: &Chunk) -> Self {
+    pub fn from_chunk(chunk: &Chunk, materials: &MaterialRegistry) -> Self {
         let mut columns = Vec::with_capacity(CHUNK_SIZE * CHUNK_SIZE);
+        for z in 0..CHUNK_SIZE {
+            for x in 0..CHUNK_SIZE {
+                columns.push(scan_column(chunk, x, z, None, materials));
+            }
+        }
+        Self {
+            columns,
+            above_air: None,
+        }
+    }
 
+    /// Rescan the single column at `(x, z)` after its voxels changed.
+    ///
+    /// Surfaces and headroom only depend on voxels in the same column (plus
+    /// the stitched air run above it), so this yields the same result as a
+    /// full rebuild for a fraction of the cost.
+    pub fn update_column(
+        &mut self,
+        x: usize,
+        z: usize,
+        chunk: &Chunk,
+        materials: &MaterialRegistry,
+    ) {
+        let col = z * CHUNK_SIZE + x;
+        let above = self.above_air.as_ref().map(|runs| runs[col]);
+        self.columns[col] = scan_column(chunk, x, z, above, materials);
+    }
+
+    /// Stitch this grid against the chunk directly above it across the
+    /// vertical seam, or un-stitch it when `above` is `None`.
+    ///
+    /// Drops top-of-chunk surfaces that are covered by solid voxels in
+    /// `above` and extends headroom into it. Only columns whose seam changed
+    /// are rescanned. Idempotent; returns `true` if any surface changed.
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn stitch_above(
+        &mut self,
+        chunk: &Chunk,
+        above: Option<&Chunk>,
+        materials: &MaterialRegistry,
+    ) -> bool {
+        let runs: Option<Vec<u8>> = above.map(|above| {
+            let mut runs = Vec::with_capacity(CHUNK_SIZE * CHUNK_SIZE);
+            for z in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    runs.push(count_headroom(above, x, 0, z, materials) as u8);
+                }
+            }
+            runs
+        });
+        if runs == self.above_air {
+            return false;
+        }
+        let mut changed = false;
         for z in 0..CHUNK_SIZE {
             for x in 0..CHUNK_SIZE {
-                let mut surfaces = Vec::new();
-
-                for y in 0..CHUNK_SIZE {
-                    let voxel = chunk.voxel_at(x, y, z);
-                    let mat = material_id(voxel);
-
-                    if mat == 0 {
-                        continue;
-                    }
-
-                    // Surface at top of chunk
-                    if y == CHUNK_SIZE - 1 {
-                        surfaces.push(TileSurface {
-                            y: y as u8,
-                            terrain_id: material_to_terrain(mat),
-                            headroom: 255,
-                        });
-                        continue;
-                    }
-
-                    // Surface where solid has air above
-                    let above = chunk.voxel_at(x, y + 1, z);
-                    if material_id(above) == 0 {
-                        let headroom = count_headroom(chunk, x, y + 1, z);
-                        surfaces.push(TileSurface {
-                            y: y as u8,
-                            terrain_id: material_to_terrain(mat),
-                            headroom: headroom as u8,
-                        });
-                    }
+                let col = z * CHUNK_SIZE + x;
+                let old = self.above_air.as_ref().map(|r| r[col]);
+                let new = runs.as_ref().map(|r| r[col]);
+                if old == new {
+                    continue;
+                }
+                let surfaces = scan_column(chunk, x, z, new, materials);
+                if surfaces != self.columns[col] {
+                    self.columns[col] = surfaces;
+                    changed = true;
                 }
-
-                columns.push(surfaces);
             }
         }
-
-        Self { columns }
+        self.above_air = runs;
+        changed
     }
 
     /// Returns the surfaces in the column at `(x, z)`, sorted bottom-to-top.
@@ -96,12 +158,12 @@ impl TerrainGrid {
     /// Serializes the grid for `postMessage` transfer.
     ///
     /// Format: for each of 32*32 columns in row-major (z-major) order:
-    /// `[count: u8, (y, terrain_id, headroom) x count]`
+    /// `[count: u8, (y, terrain_id, headroom, flags) x count]`
     #[must_use]
     #[allow(clippy::cast_possible_truncation)]
     pub fn to_bytes(&self) -> Vec<u8> {
         let total_surfaces: usize = self.columns.iter().map(Vec::len).sum();
-        let mut bytes = Vec::with_capacity(CHUNK_SIZE * CHUNK_SIZE + total_surfaces * 3);
+        let mut bytes = Vec::with_capacity(CHUNK_SIZE * CHUNK_SIZE + total_surfaces * 4);
 
         for col in &self.columns {
             bytes.push(col.len() as u8);
@@ -109,31 +171,163 @@ impl TerrainGrid {
                 bytes.push(s.y);
                 bytes.push(s.terrain_id);
                 bytes.push(s.headroom);
+                bytes.push(s.flags);
             }
         }
 
         bytes
     }
+
+    /// Parses bytes produced by [`to_bytes`](Self::to_bytes). The result is
+    /// unstitched. Returns `None` if the input is truncated, has trailing
+    /// bytes, or lists a column's surfaces out of order.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut columns = Vec::with_capacity(CHUNK_SIZE * CHUNK_SIZE);
+        let mut rest = bytes;
+        for _ in 0..CHUNK_SIZE * CHUNK_SIZE {
+            let (&count, tail) = rest.split_first()?;
+            let len = usize::from(count) * 4;
+            if tail.len() < len {
+                return None;
+            }
+            let (entries, tail) = tail.split_at(len);
+            let col: Vec<TileSurface> = entries
+                .chunks_exact(4)
+                .map(|e| TileSurface {
+                    y: e[0],
+                    terrain_id: e[1],
+                    headroom: e[2],
+                    flags: e[3],
+                })
+                .collect();
+            if col.iter().any(|s| usize::from(s.y) >= CHUNK_SIZE)
+                || col.windows(2).any(|w| w[0].y >= w[1].y)
+            {
+                return None;
+            }
+            columns.push(col);
+            rest = tail;
+        }
+        rest.is_empty().then_some(Self {
+            columns,
+            above_air: None,
+        })
+    }
+}
+
+/// Extracts the walkable surfaces of the column at `(x, z)`, bottom-to-top.
+///
+/// `above_air` is the air run at the bottom of the same column in the chunk
+/// above, if known. It hides a top-of-chunk surface covered by a solid voxel
+/// and extends the headroom of surfaces whose air reaches the seam.
+#[allow(clippy::cast_possible_truncation)]
+fn scan_column(
+    chunk: &Chunk,
+    x: usize,
+    z: usize,
+    above_air: Option<u8>,
+    materials: &MaterialRegistry,
+) -> Vec<TileSurface> {
+    let mut surfaces = Vec::new();
+
+    for y in 0..CHUNK_SIZE {
+        let voxel = chunk.voxel_at(x, y, z);
+        let mat = material_id(voxel);
+
+        if !materials.is_solid_voxel(voxel) {
+            continue;
+        }
+        let blocked = if materials.is_walkable(mat) {
+            0
+        } else {
+            TILE_BLOCKED
+        };
+
+        // Surface at top of chunk, unless the chunk above covers it.
+        if y == CHUNK_SIZE - 1 {
+            if above_air == Some(0) {
+                continue;
+            }
+            surfaces.push(TileSurface {
+                y: y as u8,
+                terrain_id: mat,
+                headroom: seam_headroom(0, above_air),
+                flags: tile_flags(voxel, 0) | blocked,
+            });
+            continue;
+        }
+
+        // Surface where solid has open space above
+        let above = chunk.voxel_at(x, y + 1, z);
+        if !materials.is_solid_voxel(above) {
+            let local = count_headroom(chunk, x, y + 1, z, materials);
+            let headroom = if y + 1 + local == CHUNK_SIZE {
+                match above_air {
+                    Some(_) => seam_headroom(local, above_air),
+                    None => local as u8,
+                }
+            } else {
+                local as u8
+            };
+            surfaces.push(TileSurface {
+                y: y as u8,
+                terrain_id: mat,
+                headroom,
+                flags: tile_flags(voxel, above) | blocked,
+            });
+        }
+    }
+
+    surfaces
+}
+
+/// Tile flags for a surface formed by `surface` with `above` standing on it.
+const fn tile_flags(surface: u32, above: u32) -> u8 {
+    let f = flags(surface) | flags(above);
+    let mut out = 0;
+    if f & FLAG_CLIMBABLE != 0 {
+        out |= TILE_CLIMBABLE;
+    }
+    if f & FLAG_HAZARD != 0 {
+        out |= TILE_HAZARD;
+    }
+    out
+}
+
+/// Headroom for an air run of `local` voxels that reaches the top of the
+/// chunk and continues for `above_air` voxels into the chunk above. An
+/// unknown or fully open chunk above yields `255`.
+#[allow(clippy::cast_possible_truncation)]
+fn seam_headroom(local: usize, above_air: Option<u8>) -> u8 {
+    match above_air {
+        Some(run) if usize::from(run) < CHUNK_SIZE => (local + usize::from(run)).min(254) as u8,
+        _ => 255,
+    }
 }
 
-/// Counts consecutive air voxels starting at `(x, start_y, z)` upward.
-fn count_headroom(chunk: &Chunk, x: usize, start_y: usize, z: usize) -> usize {
+/// Counts consecutive non-solid voxels starting at `(x, start_y, z)` upward.
+fn count_headroom(
+    chunk: &Chunk,
+    x: usize,
+    start_y: usize,
+    z: usize,
+    materials: &MaterialRegistry,
+) -> usize {
     (start_y..CHUNK_SIZE)
-        .take_while(|&y| material_id(chunk.voxel_at(x, y, z)) == 0)
+        .take_while(|&y| !materials.is_solid_voxel(chunk.voxel_at(x, y, z)))
         .count()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::voxel::{MAT_GRASS, MAT_STONE, pack_voxel};
+    use crate::voxel::{MAT_AIR, MAT_GRASS, MAT_STONE, pack_voxel};
     use glam::IVec3;
 
     /// Helper: creates a chunk filled entirely with air.
     fn air_chunk() -> Chunk {
-        Chunk {
-            voxels: vec![0; CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE],
-        }
+        Chunk::default()
     }
 
     /// Helper: sets a single voxel in a chunk.
@@ -151,7 +345,7 @@ mod tests {
             }
         }
 
-        let grid = TerrainGrid::from_chunk(&chunk);
+        let grid = TerrainGrid::from_chunk(&chunk, &MaterialRegistry::default());
 
         for z in 0..CHUNK_SIZE {
             for x in 0..CHUNK_SIZE {
@@ -185,7 +379,7 @@ mod tests {
             }
         }
 
-        let grid = TerrainGrid::from_chunk(&chunk);
+        let grid = TerrainGrid::from_chunk(&chunk, &MaterialRegistry::default());
 
         for z in 0..CHUNK_SIZE {
             for x in 0..CHUNK_SIZE {
@@ -218,7 +412,7 @@ mod tests {
             set_voxel(&mut chunk, 0, y, 0, MAT_STONE);
         }
 
-        let grid = TerrainGrid::from_chunk(&chunk);
+        let grid = TerrainGrid::from_chunk(&chunk, &MaterialRegistry::default());
         let surfaces = grid.surfaces_at(0, 0);
 
         assert_eq!(surfaces.len(), 1);
@@ -230,7 +424,7 @@ mod tests {
     #[test]
     fn empty_column_has_no_surfaces() {
         let chunk = air_chunk();
-        let grid = TerrainGrid::from_chunk(&chunk);
+        let grid = TerrainGrid::from_chunk(&chunk, &MaterialRegistry::default());
 
         for z in 0..CHUNK_SIZE {
             for x in 0..CHUNK_SIZE {
@@ -244,6 +438,77 @@ mod tests {
         assert_eq!(grid.surface_count(), 0);
     }
 
+    #[test]
+    fn update_column_matches_full_rebuild() {
+        let mut chunk = Chunk::new_terrain(42);
+        let mut grid = TerrainGrid::from_chunk(&chunk, &MaterialRegistry::default());
+        // Dig a shaft and build a ledge in one column.
+        for y in 0..10 {
+            set_voxel(&mut chunk, 7, y, 9, 0);
+        }
+        set_voxel(&mut chunk, 7, 28, 9, MAT_STONE);
+        grid.update_column(7, 9, &chunk, &MaterialRegistry::default());
+        let rebuilt = TerrainGrid::from_chunk(&chunk, &MaterialRegistry::default());
+        assert_eq!(grid.to_bytes(), rebuilt.to_bytes());
+    }
+
+    #[test]
+    fn stitch_drops_top_surface_covered_from_above() {
+        let mut below = air_chunk();
+        set_voxel(&mut below, 0, 31, 0, MAT_STONE);
+        set_voxel(&mut below, 1, 31, 0, MAT_STONE);
+        let mut above = air_chunk();
+        set_voxel(&mut above, 0, 0, 0, MAT_STONE);
+
+        let mut grid = TerrainGrid::from_chunk(&below, &MaterialRegistry::default());
+        assert_eq!(grid.surfaces_at(0, 0)[0].headroom, 255);
+        assert!(grid.stitch_above(&below, Some(&above), &MaterialRegistry::default()));
+        assert!(grid.surfaces_at(0, 0).is_empty());
+        // Column 1 is open all the way through the chunk above.
+        assert_eq!(grid.surfaces_at(1, 0)[0].headroom, 255);
+    }
+
+    #[test]
+    fn stitch_extends_headroom_into_chunk_above() {
+        let mut below = air_chunk();
+        set_voxel(&mut below, 0, 28, 0, MAT_GRASS);
+        let mut above = air_chunk();
+        set_voxel(&mut above, 0, 4, 0, MAT_STONE);
+
+        let mut grid = TerrainGrid::from_chunk(&below, &MaterialRegistry::default());
+        assert_eq!(grid.surfaces_at(0, 0)[0].headroom, 3);
+        grid.stitch_above(&below, Some(&above), &MaterialRegistry::default());
+        // y=29..=31 here plus y=0..=3 above.
+        assert_eq!(grid.surfaces_at(0, 0)[0].headroom, 7);
+    }
+
+    #[test]
+    fn stitch_is_idempotent_and_reversible() {
+        let mut below = air_chunk();
+        set_voxel(&mut below, 0, 31, 0, MAT_STONE);
+        let mut above = air_chunk();
+        set_voxel(&mut above, 0, 0, 0, MAT_STONE);
+
+        let mut grid = TerrainGrid::from_chunk(&below, &MaterialRegistry::default());
+        let original = grid.to_bytes();
+        assert!(grid.stitch_above(&below, Some(&above), &MaterialRegistry::default()));
+        assert!(!grid.stitch_above(&below, Some(&above), &MaterialRegistry::default()));
+        assert!(grid.stitch_above(&below, None, &MaterialRegistry::default()));
+        assert_eq!(grid.to_bytes(), original);
+    }
+
+    #[test]
+    fn update_column_keeps_stitched_seam() {
+        let mut below = air_chunk();
+        let mut above = air_chunk();
+        set_voxel(&mut above, 0, 0, 0, MAT_STONE);
+        let mut grid = TerrainGrid::from_chunk(&below, &MaterialRegistry::default());
+        grid.stitch_above(&below, Some(&above), &MaterialRegistry::default());
+        set_voxel(&mut below, 0, 31, 0, MAT_STONE);
+        grid.update_column(0, 0, &below, &MaterialRegistry::default());
+        assert!(grid.surfaces_at(0, 0).is_empty());
+    }
+
     #[test]
     fn to_bytes_round_trips_surface_data() {
         let mut chunk = air_chunk();
@@ -251,20 +516,22 @@ mod tests {
         set_voxel(&mut chunk, 0, 0, 0, MAT_GRASS);
         set_voxel(&mut chunk, 0, 5, 0, MAT_STONE);
 
-        let grid = TerrainGrid::from_chunk(&chunk);
+        let grid = TerrainGrid::from_chunk(&chunk, &MaterialRegistry::default());
         let bytes = grid.to_bytes();
 
-        // First column (0,0): count=2, then two surface triples
+        // First column (0,0): count=2, then two surface quads
         assert_eq!(bytes[0], 2); // count
         assert_eq!(bytes[1], 0); // y=0
         assert_eq!(bytes[2], MAT_GRASS); // terrain_id
         assert_eq!(bytes[3], 4); // headroom: y1..y4 = 4 air voxels
-        assert_eq!(bytes[4], 5); // y=5
-        assert_eq!(bytes[5], MAT_STONE); // terrain_id
-        assert_eq!(bytes[6], 26); // headroom: y6..y31 = 26 air voxels
+        assert_eq!(bytes[4], 0); // flags
+        assert_eq!(bytes[5], 5); // y=5
+        assert_eq!(bytes[6], MAT_STONE); // terrain_id
+        assert_eq!(bytes[7], 26); // headroom: y6..y31 = 26 air voxels
+        assert_eq!(bytes[8], 0); // flags
 
         // Remaining 1023 columns should each be [0] (no surfaces)
-        let mut offset = 7;
+        let mut offset = 9;
         for _ in 1..CHUNK_SIZE * CHUNK_SIZE {
             assert_eq!(bytes[offset], 0, "empty column should have count=0");
             offset += 1;
@@ -272,10 +539,57 @@ mod tests {
         assert_eq!(offset, bytes.len());
     }
 
+    #[test]
+    fn from_bytes_inverts_to_bytes() {
+        let chunk = Chunk::new_terrain_at(42, IVec3::ZERO);
+        let grid = TerrainGrid::from_chunk(&chunk, &MaterialRegistry::default());
+        let bytes = grid.to_bytes();
+        let parsed = TerrainGrid::from_bytes(&bytes).expect("well-formed grid");
+        assert_eq!(parsed.to_bytes(), bytes);
+        assert!(TerrainGrid::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert!(TerrainGrid::from_bytes(&trailing).is_none());
+    }
+
+    #[test]
+    fn ladder_and_hazard_flags_reach_tile_surfaces() {
+        let mut chunk = air_chunk();
+        set_voxel(&mut chunk, 0, 0, 0, MAT_STONE);
+        // A climbable (non-solid) vine voxel resting on the floor.
+        chunk.set_voxel(0, 1, 0, pack_voxel(MAT_AIR, 0, 0, FLAG_CLIMBABLE));
+        chunk.set_voxel(1, 0, 0, pack_voxel(MAT_STONE, 0, 0, FLAG_HAZARD));
+        set_voxel(&mut chunk, 2, 0, 0, MAT_STONE);
+
+        let grid = TerrainGrid::from_chunk(&chunk, &MaterialRegistry::default());
+        assert_eq!(grid.surfaces_at(0, 0)[0].flags, TILE_CLIMBABLE);
+        assert_eq!(grid.surfaces_at(1, 0)[0].flags, TILE_HAZARD);
+        assert_eq!(grid.surfaces_at(2, 0)[0].flags, 0);
+        assert_eq!(grid.surfaces_at(0, 0)[0].headroom, 31);
+    }
+
+    #[test]
+    fn open_door_adds_headroom_closed_door_blocks() {
+        use crate::voxel::{FLAG_DOOR, FLAG_OPEN};
+        let mut chunk = air_chunk();
+        set_voxel(&mut chunk, 0, 0, 0, MAT_STONE);
+        set_voxel(&mut chunk, 1, 0, 0, MAT_STONE);
+        chunk.set_voxel(0, 1, 0, pack_voxel(MAT_STONE, 0, 0, FLAG_DOOR));
+        chunk.set_voxel(1, 1, 0, pack_voxel(MAT_STONE, 0, 0, FLAG_DOOR | FLAG_OPEN));
+
+        let grid = TerrainGrid::from_chunk(&chunk, &MaterialRegistry::default());
+        // Closed door: floor is covered, the door top becomes the surface.
+        assert_eq!(grid.surfaces_at(0, 0).len(), 1);
+        assert_eq!(grid.surfaces_at(0, 0)[0].y, 1);
+        // Open door: the floor stays walkable under the doorway.
+        assert_eq!(grid.surfaces_at(1, 0)[0].y, 0);
+        assert_eq!(grid.surfaces_at(1, 0)[0].headroom, 31);
+    }
+
     #[test]
     fn perlin_terrain_has_sorted_surfaces() {
         let chunk = Chunk::new_terrain_at(42, IVec3::ZERO);
-        let grid = TerrainGrid::from_chunk(&chunk);
+        let grid = TerrainGrid::from_chunk(&chunk, &MaterialRegistry::default());
 
         // Must have at least one surface (terrain exists)
         assert!(
@@ -298,4 +612,27 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn surface_walkability_comes_from_the_registry() {
+        use crate::material::MaterialDef;
+        let mut chunk = air_chunk();
+        set_voxel(&mut chunk, 0, 0, 0, MAT_STONE);
+        set_voxel(&mut chunk, 1, 0, 0, 20);
+        let mut materials = MaterialRegistry::default();
+        materials.define(
+            20,
+            MaterialDef {
+                walkable: false,
+                ..MaterialDef::solid("spikes", [0.4; 3])
+            },
+        );
+
+        let grid = TerrainGrid::from_chunk(&chunk, &materials);
+        assert!(grid.surfaces_at(0, 0)[0].is_walkable());
+        let spikes = grid.surfaces_at(1, 0)[0];
+        assert_eq!(spikes.terrain_id, 20);
+        assert!(!spikes.is_walkable());
+        assert_eq!(spikes.flags, TILE_BLOCKED);
+    }
 }
@@ -0,0 +1,291 @@
+use glam::{IVec2, IVec3, UVec3};
+
+use crate::voxel::CHUNK_SIZE;
+
+/// Estimated terrain surface height (world y) at a world `(x, z)` column.
+pub type SurfaceHeight = dyn Fn(i32, i32) -> i32 + Send + Sync;
+
+/// Shape of the streamed region around the camera chunk.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ViewShape {
+    /// Every chunk within the radii on each axis.
+    #[default]
+    Box,
+    /// A round horizontal footprint, full height.
+    Cylinder,
+    /// An ellipsoid with the horizontal and vertical radii as semi-axes.
+    Sphere,
+}
+
+/// Restricts each chunk column to a band around the terrain surface, so
+/// deep stone and open sky are not streamed. The layers within
+/// [`CAMERA_LAYERS`] of the camera chunk are kept regardless, so a camera
+/// high in the sky or deep in a cave still has the chunks around it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SurfaceBand {
+    /// Chunks kept below the one holding the surface.
+    pub below: u32,
+    /// Chunks kept above the one holding the surface.
+    pub above: u32,
+}
+
+/// Chunk layers above and below the camera chunk that a [`SurfaceBand`]
+/// never clips away.
+pub const CAMERA_LAYERS: i32 = 1;
+
+/// The set of chunks streamed around the camera: radii in chunks, a shape,
+/// and an optional [`SurfaceBand`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ViewVolume {
+    /// Radius along X and Z.
+    pub horizontal: u32,
+    /// Radius along Y.
+    pub vertical: u32,
+    pub shape: ViewShape,
+    /// Applied only when a surface height estimate is available.
+    pub surface_band: Option<SurfaceBand>,
+}
+
+impl ViewVolume {
+    /// The classic `(2 * view_distance + 1)^3` cube.
+    #[must_use]
+    pub fn cube(view_distance: u32) -> Self {
+        Self {
+            horizontal: view_distance,
+            vertical: view_distance,
+            shape: ViewShape::Box,
+            surface_band: None,
+        }
+    }
+
+    /// Whether the horizontal offset `(dx, dz)` from the center column lies
+    /// in the footprint.
+    #[must_use]
+    pub fn contains_column(&self, dx: i32, dz: i32) -> bool {
+        let h = i64::from(self.horizontal);
+        let (dx, dz) = (i64::from(dx), i64::from(dz));
+        if dx.abs() > h || dz.abs() > h {
+            return false;
+        }
+        match self.shape {
+            ViewShape::Box => true,
+            ViewShape::Cylinder | ViewShape::Sphere => dx * dx + dz * dz <= h * h,
+        }
+    }
+
+    /// Whether `offset` from the center chunk lies in the shape, ignoring
+    /// the surface band.
+    #[must_use]
+    pub fn contains_offset(&self, offset: IVec3) -> bool {
+        let v = i64::from(self.vertical);
+        let dy = i64::from(offset.y);
+        if dy.abs() > v || !self.contains_column(offset.x, offset.z) {
+            return false;
+        }
+        match self.shape {
+            ViewShape::Box | ViewShape::Cylinder => true,
+            ViewShape::Sphere => {
+                // (dx² + dz²) / h² + dy² / v² <= 1, scaled to integers.
+                let h = i64::from(self.horizontal);
+                let (dx, dz) = (i64::from(offset.x), i64::from(offset.z));
+                (dx * dx + dz * dz) * v * v + dy * dy * h * h <= h * h * v * v
+            }
+        }
+    }
+
+    /// Chunks in the volume around `center`. With `surface`, each column
+    /// is further clipped to the [`SurfaceBand`] around its estimated
+    /// surface, sampled at the column's center, plus the layers within
+    /// [`CAMERA_LAYERS`] of `center`.
+    #[must_use]
+    pub fn chunks_around(&self, center: IVec3, surface: Option<&SurfaceHeight>) -> Vec<IVec3> {
+        let h = self.horizontal.cast_signed();
+        let v = self.vertical.cast_signed();
+        let band = self.surface_band.zip(surface);
+        let mut chunks = Vec::new();
+        for dz in -h..=h {
+            for dx in -h..=h {
+                if !self.contains_column(dx, dz) {
+                    continue;
+                }
+                let column = IVec2::new(center.x + dx, center.z + dz);
+                let kept = band.map(|(band, height)| {
+                    let sy = surface_chunk_y(height, column);
+                    sy - band.below.cast_signed()..=sy + band.above.cast_signed()
+                });
+                for y in center.y - v..=center.y + v {
+                    let near_camera = (y - center.y).abs() <= CAMERA_LAYERS;
+                    if !near_camera && kept.as_ref().is_some_and(|k| !k.contains(&y)) {
+                        continue;
+                    }
+                    let coord = IVec3::new(column.x, y, column.y);
+                    if self.contains_offset(coord - center) {
+                        chunks.push(coord);
+                    }
+                }
+            }
+        }
+        chunks.sort_by_key(|c| (c.z, c.y, c.x));
+        chunks
+    }
+
+    /// Upper bound on the number of chunks [`chunks_around`](Self::chunks_around)
+    /// returns, whatever the terrain.
+    #[must_use]
+    pub fn max_chunks(&self) -> u32 {
+        let h = self.horizontal.cast_signed();
+        let v = self.vertical.cast_signed();
+        let band_height = self.surface_band.map_or(u32::MAX, |b| {
+            b.below + b.above + 1 + 2 * CAMERA_LAYERS.cast_unsigned() + 1
+        });
+        let mut total = 0;
+        for dz in -h..=h {
+            for dx in -h..=h {
+                let column = (-v..=v)
+                    .filter(|&dy| self.contains_offset(IVec3::new(dx, dy, dz)))
+                    .count() as u32;
+                total += column.min(band_height);
+            }
+        }
+        total
+    }
+
+    /// Size in chunks of the box enclosing the volume.
+    #[must_use]
+    pub fn bounding_size(&self) -> UVec3 {
+        let h = 2 * self.horizontal + 1;
+        UVec3::new(h, 2 * self.vertical + 1, h)
+    }
+}
+
+/// Chunk y holding the surface of `column` (chunk x, z), sampled at the
+/// column's center.
+#[allow(clippy::cast_possible_wrap)]
+fn surface_chunk_y(height: &SurfaceHeight, column: IVec2) -> i32 {
+    let chunk = CHUNK_SIZE as i32;
+    let half = chunk / 2;
+    height(column.x * chunk + half, column.y * chunk + half).div_euclid(chunk)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn volume(horizontal: u32, vertical: u32, shape: ViewShape) -> ViewVolume {
+        ViewVolume {
+            horizontal,
+            vertical,
+            shape,
+            surface_band: None,
+        }
+    }
+
+    #[test]
+    fn cube_matches_the_full_box() {
+        let cube = ViewVolume::cube(2);
+        let chunks = cube.chunks_around(IVec3::new(3, -1, 0), None);
+        assert_eq!(chunks.len(), 125);
+        assert_eq!(cube.max_chunks(), 125);
+        assert!(chunks.contains(&IVec3::new(5, 1, 2)));
+        assert!(chunks.contains(&IVec3::new(1, -3, -2)));
+    }
+
+    #[test]
+    fn anisotropic_box_uses_separate_radii() {
+        let wide = volume(4, 1, ViewShape::Box);
+        assert_eq!(wide.chunks_around(IVec3::ZERO, None).len(), 9 * 9 * 3);
+        assert_eq!(wide.bounding_size(), UVec3::new(9, 3, 9));
+    }
+
+    #[test]
+    fn cylinder_and_sphere_drop_corners() {
+        let cylinder = volume(3, 1, ViewShape::Cylinder);
+        assert!(cylinder.contains_offset(IVec3::new(3, 1, 0)));
+        assert!(!cylinder.contains_offset(IVec3::new(3, 0, 3)));
+        // 29 columns within radius 3, three layers each.
+        assert_eq!(cylinder.chunks_around(IVec3::ZERO, None).len(), 29 * 3);
+
+        let sphere = volume(3, 3, ViewShape::Sphere);
+        assert!(sphere.contains_offset(IVec3::new(0, 3, 0)));
+        assert!(!sphere.contains_offset(IVec3::new(2, 2, 2)));
+        assert_eq!(
+            sphere.chunks_around(IVec3::ZERO, None).len() as u32,
+            sphere.max_chunks()
+        );
+    }
+
+    #[test]
+    fn surface_band_follows_terrain_height() {
+        let banded = ViewVolume {
+            surface_band: Some(SurfaceBand { below: 1, above: 0 }),
+            ..volume(1, 4, ViewShape::Box)
+        };
+        // Surface in chunk y=2 for x >= 0, y=-1 for x < 0.
+        let height = |x: i32, _z: i32| if x >= 0 { 70 } else { -10 };
+        let chunks = banded.chunks_around(IVec3::ZERO, Some(&height));
+        // Each column keeps its band plus the camera's layers -1..=1.
+        assert_eq!(chunks.len(), 9 * 4);
+        assert!(chunks.contains(&IVec3::new(1, 2, 0)));
+        assert!(chunks.contains(&IVec3::new(1, 1, 0)));
+        assert!(chunks.contains(&IVec3::new(-1, -2, 0)));
+        assert!(!chunks.contains(&IVec3::new(-1, 2, 0)));
+        assert!(!chunks.contains(&IVec3::new(1, -2, 0)));
+        assert_eq!(banded.max_chunks(), 9 * 5);
+
+        // Without a height estimate the band is ignored.
+        assert_eq!(banded.chunks_around(IVec3::ZERO, None).len(), 9 * 9);
+    }
+
+    #[test]
+    fn surface_band_is_clipped_to_vertical_radius() {
+        let banded = ViewVolume {
+            surface_band: Some(SurfaceBand { below: 0, above: 0 }),
+            ..volume(0, 2, ViewShape::Box)
+        };
+        // Only the camera's own layers remain.
+        let far_below = |_: i32, _: i32| -1000;
+        assert_eq!(
+            banded.chunks_around(IVec3::ZERO, Some(&far_below)),
+            vec![IVec3::new(0, -1, 0), IVec3::ZERO, IVec3::new(0, 1, 0)]
+        );
+    }
+
+    fn streamed_volume() -> ViewVolume {
+        ViewVolume {
+            horizontal: 5,
+            vertical: 3,
+            shape: ViewShape::Cylinder,
+            surface_band: Some(SurfaceBand { below: 1, above: 1 }),
+        }
+    }
+
+    #[test]
+    fn high_camera_keeps_its_own_layers() {
+        let volume = streamed_volume();
+        let surface = |_: i32, _: i32| 20;
+        let center = IVec3::new(0, 5, 0);
+        let chunks = volume.chunks_around(center, Some(&surface));
+        // The band around chunk y=0 is out of reach; the camera's layers
+        // remain.
+        assert_eq!(chunks.len(), 81 * 3);
+        assert!(chunks.contains(&center));
+        assert!(chunks.contains(&IVec3::new(2, 4, 0)));
+        assert!(chunks.contains(&IVec3::new(0, 6, 0)));
+        assert!(!chunks.contains(&IVec3::new(0, 3, 0)));
+        assert!(chunks.len() as u32 <= volume.max_chunks());
+    }
+
+    #[test]
+    fn deep_camera_keeps_its_own_layers() {
+        let volume = streamed_volume();
+        let surface = |_: i32, _: i32| 20;
+        let center = IVec3::new(1, -3, 1);
+        let chunks = volume.chunks_around(center, Some(&surface));
+        assert!(chunks.contains(&center));
+        assert!(chunks.contains(&IVec3::new(1, -2, 1)));
+        assert!(chunks.contains(&IVec3::new(1, -4, 1)));
+        assert!(chunks.contains(&IVec3::new(1, -1, 1)));
+        assert!(!chunks.contains(&IVec3::new(1, -5, 1)));
+        assert!(chunks.len() as u32 <= volume.max_chunks());
+    }
+}
@@ -1,12 +1,20 @@
-use glam::{IVec3, Vec3};
+use glam::{IVec3, UVec3, Vec3};
 use noise::{NoiseFn, Perlin};
 
+use crate::palette::PaletteStorage;
+
 pub const CHUNK_SIZE: usize = 32;
 
 pub const MAT_AIR: u8 = 0;
 pub const MAT_GRASS: u8 = 1;
 pub const MAT_DIRT: u8 = 2;
 pub const MAT_STONE: u8 = 3;
+pub const MAT_WATER: u8 = 4;
+pub const MAT_GLASS: u8 = 5;
+pub const MAT_ICE: u8 = 6;
+pub const MAT_LAVA: u8 = 7;
+pub const MAT_GLOWSTONE: u8 = 8;
+pub const MAT_CRYSTAL: u8 = 9;
 
 pub const DIRT_DEPTH: usize = 3;
 
@@ -16,6 +24,17 @@ pub const fn voxel_index(x: usize, y: usize, z: usize) -> usize {
     z * CHUNK_SIZE * CHUNK_SIZE + y * CHUNK_SIZE + x
 }
 
+/// Inverse of [`voxel_index`]: the local `(x, y, z)` of a flat voxel index.
+#[inline]
+#[must_use]
+pub const fn voxel_coords(index: usize) -> (usize, usize, usize) {
+    (
+        index % CHUNK_SIZE,
+        (index / CHUNK_SIZE) % CHUNK_SIZE,
+        index / (CHUNK_SIZE * CHUNK_SIZE),
+    )
+}
+
 /// Returns the terrain material for a voxel at world y relative to a surface.
 ///
 /// Grass at the surface, dirt within `DIRT_DEPTH` below, stone deeper.
@@ -119,28 +138,223 @@ pub const fn flags(voxel: u32) -> u8 {
     ((voxel >> 24) & 0xFF) as u8
 }
 
+/// `flags` bit: the voxel is a door. Doors block movement unless
+/// [`FLAG_OPEN`] is also set.
+pub const FLAG_DOOR: u8 = 1 << 0;
+/// `flags` bit: an open door, passable by entities and collision.
+pub const FLAG_OPEN: u8 = 1 << 1;
+/// `flags` bit: a ladder, vine or other climbable voxel.
+pub const FLAG_CLIMBABLE: u8 = 1 << 2;
+/// `flags` bit: the voxel damages entities standing on or in it.
+pub const FLAG_HAZARD: u8 = 1 << 3;
+/// `flags` bit: voxel mutations skip this voxel; only its flags may change.
+pub const FLAG_INDESTRUCTIBLE: u8 = 1 << 4;
+/// `flags` bit: a liquid voxel, passable regardless of its material.
+pub const FLAG_LIQUID: u8 = 1 << 5;
+
+/// Whether the voxel's flags let entities pass through it even if its
+/// material is solid: an open door or a liquid.
+#[inline]
+#[must_use]
+pub const fn is_passable(voxel: u32) -> bool {
+    let f = flags(voxel);
+    (f & FLAG_DOOR != 0 && f & FLAG_OPEN != 0) || f & FLAG_LIQUID != 0
+}
+
+/// Replace the `flags` byte of `voxel`, keeping material and params.
+#[inline]
+#[must_use]
+pub const fn with_flags(voxel: u32, flags: u8) -> u32 {
+    (voxel & 0x00FF_FFFF) | ((flags as u32) << 24)
+}
+
+/// Largest wetness level stored in `param1`.
+pub const MAX_WETNESS: u8 = 7;
+/// Largest damage level stored in `param1`.
+pub const MAX_DAMAGE: u8 = 7;
+/// Number of distinct visual variants stored in `param1`.
+pub const VARIANT_COUNT: u8 = 4;
+
+/// `param0` as a signed brightness offset: `0` is the plain palette color,
+/// `127` about 50% brighter and `-128` 50% darker.
+#[inline]
+#[must_use]
+pub const fn brightness_offset(voxel: u32) -> i8 {
+    param0(voxel).cast_signed()
+}
+
+/// Encode a signed brightness offset as a `param0` byte.
+#[inline]
+#[must_use]
+pub const fn pack_brightness(offset: i8) -> u8 {
+    offset.cast_unsigned()
+}
+
+/// Pack a `param1` byte: wetness in bits 0-2, damage in bits 3-5 and the
+/// variant index in bits 6-7. Out-of-range values are clamped.
+#[inline]
+#[must_use]
+pub const fn pack_param1(wetness: u8, damage: u8, variant: u8) -> u8 {
+    let w = if wetness > MAX_WETNESS {
+        MAX_WETNESS
+    } else {
+        wetness
+    };
+    let d = if damage > MAX_DAMAGE {
+        MAX_DAMAGE
+    } else {
+        damage
+    };
+    let v = if variant >= VARIANT_COUNT {
+        VARIANT_COUNT - 1
+    } else {
+        variant
+    };
+    w | (d << 3) | (v << 6)
+}
+
+/// Wetness level (`0..=MAX_WETNESS`) from `param1`.
+#[inline]
+#[must_use]
+pub const fn wetness(voxel: u32) -> u8 {
+    param1(voxel) & 0x07
+}
+
+/// Damage level (`0..=MAX_DAMAGE`) from `param1`.
+#[inline]
+#[must_use]
+pub const fn damage(voxel: u32) -> u8 {
+    (param1(voxel) >> 3) & 0x07
+}
+
+/// Visual variant index (`0..VARIANT_COUNT`) from `param1`.
+#[inline]
+#[must_use]
+pub const fn variant(voxel: u32) -> u8 {
+    param1(voxel) >> 6
+}
+
+/// Number of voxels in a chunk.
+pub const CHUNK_VOLUME: usize = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+
+/// A `CHUNK_SIZE`³ block of packed voxels, stored palette-compressed (see
+/// [`PaletteStorage`]). Uniform chunks such as open sky or solid rock hold
+/// no per-voxel data; [`to_voxels`](Self::to_voxels) expands to one `u32`
+/// per voxel for GPU upload.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Chunk {
-    pub voxels: Vec<u32>,
+    storage: PaletteStorage,
+}
+
+impl Default for Chunk {
+    fn default() -> Self {
+        Self::uniform(0)
+    }
 }
 
 impl Chunk {
+    /// A chunk filled with `voxel`.
+    #[must_use]
+    pub fn uniform(voxel: u32) -> Self {
+        Self {
+            storage: PaletteStorage::uniform(CHUNK_VOLUME, voxel),
+        }
+    }
+
+    /// Compress a flat voxel array in [`voxel_index`] order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `voxels` does not hold exactly [`CHUNK_VOLUME`] entries.
+    #[must_use]
+    pub fn from_voxels(voxels: &[u32]) -> Self {
+        assert_eq!(
+            voxels.len(),
+            CHUNK_VOLUME,
+            "chunk needs {CHUNK_VOLUME} voxels"
+        );
+        Self {
+            storage: PaletteStorage::from_values(voxels),
+        }
+    }
+
     /// Read the voxel at `(x, y, z)` within this chunk.
     #[inline]
     #[must_use]
     pub fn voxel_at(&self, x: usize, y: usize, z: usize) -> u32 {
-        self.voxels[voxel_index(x, y, z)]
+        self.storage.get(voxel_index(x, y, z))
     }
 
     /// Write `value` to the voxel at `(x, y, z)` within this chunk.
     #[inline]
     pub fn set_voxel(&mut self, x: usize, y: usize, z: usize, value: u32) {
-        self.voxels[voxel_index(x, y, z)] = value;
+        self.storage.set(voxel_index(x, y, z), value);
+    }
+
+    /// Read the voxel at flat [`voxel_index`] `index`.
+    #[inline]
+    #[must_use]
+    pub fn get(&self, index: usize) -> u32 {
+        self.storage.get(index)
+    }
+
+    /// Write `value` to the voxel at flat [`voxel_index`] `index`.
+    #[inline]
+    pub fn set(&mut self, index: usize, value: u32) {
+        self.storage.set(index, value);
+    }
+
+    /// Iterate over all voxels in [`voxel_index`] order.
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.storage.iter()
+    }
+
+    /// Expand to one `u32` per voxel in [`voxel_index`] order, the layout
+    /// the chunk atlas texture expects.
+    #[must_use]
+    pub fn to_voxels(&self) -> Vec<u32> {
+        self.storage.to_vec()
+    }
+
+    /// The voxel filling the whole chunk, if it is stored uniform.
+    #[must_use]
+    pub fn uniform_voxel(&self) -> Option<u32> {
+        self.storage.uniform_value()
+    }
+
+    /// The underlying palette storage.
+    #[must_use]
+    pub fn storage(&self) -> &PaletteStorage {
+        &self.storage
+    }
+
+    /// Heap memory held by the voxel data in bytes.
+    #[must_use]
+    pub fn heap_bytes(&self) -> usize {
+        self.storage.heap_bytes()
+    }
+
+    /// Repack the voxel data into its narrowest form. Call after bulk edits
+    /// such as map features, which may leave stale palette entries or a
+    /// temporarily uncompressed chunk.
+    pub fn compact(&mut self) {
+        self.storage.compact();
     }
 
     /// Returns `true` if every voxel in the chunk is air (`material_id` == 0).
     #[must_use]
     pub fn is_empty(&self) -> bool {
-        self.voxels.iter().all(|&v| v == 0)
+        if let Some(v) = self.uniform_voxel() {
+            return v == 0;
+        }
+        if self
+            .storage
+            .palette()
+            .is_some_and(|p| p.iter().all(|&v| v == 0))
+        {
+            return true;
+        }
+        self.iter().all(|v| v == 0)
     }
 
     /// Returns a 64-bit bitmask indicating which 8x8x8 sub-regions contain
@@ -148,6 +362,9 @@ impl Chunk {
     /// of sub-regions (64 total). Bit index: `(x/8) + (y/8)*4 + (z/8)*16`.
     #[must_use]
     pub fn occupancy_mask(&self) -> u64 {
+        if let Some(v) = self.uniform_voxel() {
+            return if material_id(v) == 0 { 0 } else { u64::MAX };
+        }
         let mut mask = 0u64;
         for z in 0..CHUNK_SIZE {
             for y in 0..CHUNK_SIZE {
@@ -163,6 +380,39 @@ impl Chunk {
         mask
     }
 
+    /// Returns `true` if the 8x8x8 sub-region at `(sx, sy, sz)` (each in
+    /// `0..4`) contains at least one non-air voxel.
+    #[must_use]
+    pub fn subregion_occupied(&self, sx: usize, sy: usize, sz: usize) -> bool {
+        (sz * 8..sz * 8 + 8).any(|z| {
+            (sy * 8..sy * 8 + 8)
+                .any(|y| (sx * 8..sx * 8 + 8).any(|x| material_id(self.voxel_at(x, y, z)) != 0))
+        })
+    }
+
+    /// Recompute only the occupancy bits for sub-regions overlapping the
+    /// inclusive local box `min..=max`, keeping every other bit of `mask`.
+    /// Equivalent to [`occupancy_mask`](Self::occupancy_mask) when `mask` was
+    /// correct before the voxels inside the box changed.
+    #[must_use]
+    pub fn update_occupancy_mask(&self, mut mask: u64, min: UVec3, max: UVec3) -> u64 {
+        let lo = min / 8;
+        let hi = max.min(UVec3::splat(CHUNK_SIZE as u32 - 1)) / 8;
+        for sz in lo.z..=hi.z {
+            for sy in lo.y..=hi.y {
+                for sx in lo.x..=hi.x {
+                    let bit = 1u64 << (sx + sy * 4 + sz * 16);
+                    if self.subregion_occupied(sx as usize, sy as usize, sz as usize) {
+                        mask |= bit;
+                    } else {
+                        mask &= !bit;
+                    }
+                }
+            }
+        }
+        mask
+    }
+
     #[must_use]
     #[allow(
         clippy::cast_precision_loss,
@@ -171,7 +421,7 @@ impl Chunk {
     )]
     pub fn new_terrain(seed: u32) -> Self {
         let perlin = Perlin::new(seed);
-        let mut voxels = vec![0u32; CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE];
+        let mut voxels = vec![0u32; CHUNK_VOLUME];
 
         for z in 0..CHUNK_SIZE {
             for x in 0..CHUNK_SIZE {
@@ -191,7 +441,7 @@ impl Chunk {
             }
         }
 
-        Self { voxels }
+        Self::from_voxels(&voxels)
     }
 
     /// Generates terrain for a chunk at the given world chunk coordinate.
@@ -205,21 +455,15 @@ impl Chunk {
     )]
     pub fn new_terrain_at(seed: u32, chunk_coord: IVec3) -> Self {
         let perlin = Perlin::new(seed);
-        let mut voxels = vec![0u32; CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE];
+        let mut voxels = vec![0u32; CHUNK_VOLUME];
 
-        let cx = f64::from(chunk_coord.x);
         let cy = chunk_coord.y;
-        let cz = f64::from(chunk_coord.z);
-        let chunk_f64 = CHUNK_SIZE as f64;
 
         for z in 0..CHUNK_SIZE {
             for x in 0..CHUNK_SIZE {
-                let wx = (cx * chunk_f64 + x as f64) / chunk_f64;
-                let wz = (cz * chunk_f64 + z as f64) / chunk_f64;
-                let noise_val = perlin.get([wx * 4.0, wz * 4.0]);
-
-                let world_height =
-                    ((noise_val + 1.0) * 0.5 * CHUNK_SIZE as f64 + (CHUNK_SIZE / 4) as f64) as i32;
+                let wx = chunk_coord.x * CHUNK_SIZE as i32 + x as i32;
+                let wz = chunk_coord.z * CHUNK_SIZE as i32 + z as i32;
+                let world_height = terrain_height(&perlin, wx, wz);
                 let y_offset = cy * CHUNK_SIZE as i32;
 
                 for y in 0..CHUNK_SIZE {
@@ -233,10 +477,21 @@ impl Chunk {
             }
         }
 
-        Self { voxels }
+        Self::from_voxels(&voxels)
     }
 }
 
+/// Surface height (world y) of [`Chunk::new_terrain_at`] terrain at world
+/// column `(x, z)`, before any map features. `perlin` is seeded with the
+/// terrain seed.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn terrain_height(perlin: &Perlin, x: i32, z: i32) -> i32 {
+    let chunk = CHUNK_SIZE as f64;
+    let noise_val = perlin.get([f64::from(x) / chunk * 4.0, f64::from(z) / chunk * 4.0]);
+    ((noise_val + 1.0) * 0.5 * chunk + (CHUNK_SIZE / 4) as f64) as i32
+}
+
 /// Generates a [`TEST_GRID_X`]x[`TEST_GRID_Y`]x[`TEST_GRID_Z`] grid of terrain
 /// chunks with deterministic seed [`TEST_GRID_SEED`].
 /// Returns `(chunk_coord, chunk)` pairs in ZYX iteration order.
@@ -267,6 +522,41 @@ mod tests {
         assert_eq!(flags(v), 0x03);
     }
 
+    #[test]
+    fn param1_fields_round_trip() {
+        let v = pack_voxel(MAT_STONE, pack_brightness(-20), pack_param1(5, 3, 2), 0);
+        assert_eq!(brightness_offset(v), -20);
+        assert_eq!(wetness(v), 5);
+        assert_eq!(damage(v), 3);
+        assert_eq!(variant(v), 2);
+        assert_eq!(material_id(v), MAT_STONE);
+    }
+
+    #[test]
+    fn pack_param1_clamps_out_of_range() {
+        let v = pack_voxel(MAT_STONE, 0, pack_param1(200, 9, 7), 0);
+        assert_eq!(wetness(v), MAX_WETNESS);
+        assert_eq!(damage(v), MAX_DAMAGE);
+        assert_eq!(variant(v), VARIANT_COUNT - 1);
+    }
+
+    #[test]
+    fn open_doors_and_liquids_are_passable() {
+        let closed = pack_voxel(MAT_STONE, 0, 0, FLAG_DOOR);
+        assert!(!is_passable(closed));
+        assert!(is_passable(with_flags(closed, FLAG_DOOR | FLAG_OPEN)));
+        assert!(!is_passable(pack_voxel(MAT_STONE, 0, 0, FLAG_OPEN)));
+        assert!(is_passable(pack_voxel(MAT_STONE, 0, 0, FLAG_LIQUID)));
+        assert!(!is_passable(pack_voxel(MAT_STONE, 0, 0, FLAG_CLIMBABLE)));
+    }
+
+    #[test]
+    fn with_flags_keeps_material_and_params() {
+        let v = pack_voxel(MAT_STONE, 7, 9, FLAG_HAZARD);
+        let w = with_flags(v, FLAG_CLIMBABLE);
+        assert_eq!(w, pack_voxel(MAT_STONE, 7, 9, FLAG_CLIMBABLE));
+    }
+
     #[test]
     fn air_is_zero() {
         assert_eq!(pack_voxel(0, 0, 0, 0), 0);
@@ -275,23 +565,15 @@ mod tests {
     #[test]
     fn chunk_dimensions() {
         let chunk = Chunk::new_terrain(42);
-        assert_eq!(chunk.voxels.len(), CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE);
+        assert_eq!(chunk.iter().count(), CHUNK_VOLUME);
     }
 
     #[test]
     fn terrain_has_surface() {
         let chunk = Chunk::new_terrain(42);
-        let solid_count = chunk
-            .voxels
-            .iter()
-            .filter(|&&v| material_id(v) != 0)
-            .count();
+        let solid_count = chunk.iter().filter(|&v| material_id(v) != 0).count();
         assert!(solid_count > 0, "terrain should have solid voxels");
-        let air_count = chunk
-            .voxels
-            .iter()
-            .filter(|&&v| material_id(v) == 0)
-            .count();
+        let air_count = chunk.iter().filter(|&v| material_id(v) == 0).count();
         assert!(air_count > 0, "terrain should have air above surface");
     }
 
@@ -317,22 +599,22 @@ mod tests {
     fn terrain_is_deterministic() {
         let a = Chunk::new_terrain(123);
         let b = Chunk::new_terrain(123);
-        assert_eq!(a.voxels, b.voxels);
+        assert_eq!(a, b);
     }
 
     #[test]
     fn different_seeds_differ() {
         let a = Chunk::new_terrain(1);
         let b = Chunk::new_terrain(2);
-        assert_ne!(a.voxels, b.voxels);
+        assert_ne!(a, b);
     }
 
     #[test]
     fn terrain_at_generates_32_cubed_voxels() {
         let chunk = Chunk::new_terrain_at(42, IVec3::ZERO);
-        assert_eq!(chunk.voxels.len(), CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE);
+        assert_eq!(chunk.iter().count(), CHUNK_VOLUME);
         // Should have some non-air voxels (terrain exists)
-        assert!(chunk.voxels.iter().any(|&v| material_id(v) != MAT_AIR));
+        assert!(chunk.iter().any(|v| material_id(v) != MAT_AIR));
     }
 
     #[test]
@@ -368,47 +650,55 @@ mod tests {
         }
     }
 
+    #[test]
+    fn sky_and_bedrock_chunks_are_stored_uniform() {
+        let sky = Chunk::new_terrain_at(42, IVec3::new(0, 4, 0));
+        assert_eq!(sky.uniform_voxel(), Some(0));
+        assert_eq!(sky.heap_bytes(), 0);
+        let rock = Chunk::new_terrain_at(42, IVec3::new(0, -2, 0));
+        assert_eq!(rock.uniform_voxel(), Some(pack_voxel(MAT_STONE, 0, 0, 0)));
+        assert_eq!(rock.occupancy_mask(), u64::MAX);
+    }
+
+    #[test]
+    fn surface_chunk_is_much_smaller_than_raw_voxels() {
+        let chunk = Chunk::new_terrain_at(42, IVec3::ZERO);
+        assert!(chunk.storage().bits_per_voxel() <= 4);
+        assert!(chunk.heap_bytes() * 8 <= CHUNK_VOLUME * 4);
+        assert_eq!(Chunk::from_voxels(&chunk.to_voxels()), chunk);
+    }
+
     #[test]
     fn empty_chunk_detected() {
-        let empty = Chunk {
-            voxels: vec![0; CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE],
-        };
+        let empty = Chunk::default();
         assert!(empty.is_empty());
     }
 
     #[test]
     fn nonempty_chunk_detected() {
-        let mut chunk = Chunk {
-            voxels: vec![0; CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE],
-        };
-        chunk.voxels[0] = pack_voxel(MAT_STONE, 0, 0, 0);
+        let mut chunk = Chunk::default();
+        chunk.set(0, pack_voxel(MAT_STONE, 0, 0, 0));
         assert!(!chunk.is_empty());
     }
 
     #[test]
     fn occupancy_mask_empty_chunk() {
-        let chunk = Chunk {
-            voxels: vec![0; CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE],
-        };
+        let chunk = Chunk::default();
         assert_eq!(chunk.occupancy_mask(), 0);
     }
 
     #[test]
     fn occupancy_mask_single_voxel_bottom_corner() {
-        let mut chunk = Chunk {
-            voxels: vec![0; CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE],
-        };
-        chunk.voxels[0] = pack_voxel(MAT_STONE, 0, 0, 0);
+        let mut chunk = Chunk::default();
+        chunk.set(0, pack_voxel(MAT_STONE, 0, 0, 0));
         assert_eq!(chunk.occupancy_mask(), 1);
     }
 
     #[test]
     fn occupancy_mask_voxel_in_last_subregion() {
-        let mut chunk = Chunk {
-            voxels: vec![0; CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE],
-        };
+        let mut chunk = Chunk::default();
         let idx = 31 * CHUNK_SIZE * CHUNK_SIZE + 31 * CHUNK_SIZE + 31;
-        chunk.voxels[idx] = pack_voxel(MAT_STONE, 0, 0, 0);
+        chunk.set(idx, pack_voxel(MAT_STONE, 0, 0, 0));
         assert_eq!(chunk.occupancy_mask(), 1u64 << 63);
     }
 
@@ -426,14 +716,34 @@ mod tests {
 
     #[test]
     fn occupancy_mask_bit_index_formula() {
-        let mut chunk = Chunk {
-            voxels: vec![0; CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE],
-        };
+        let mut chunk = Chunk::default();
         let idx = 0 * CHUNK_SIZE * CHUNK_SIZE + 0 * CHUNK_SIZE + 8;
-        chunk.voxels[idx] = pack_voxel(MAT_DIRT, 0, 0, 0);
+        chunk.set(idx, pack_voxel(MAT_DIRT, 0, 0, 0));
         assert_eq!(chunk.occupancy_mask(), 1u64 << 1);
     }
 
+    #[test]
+    fn update_occupancy_mask_clears_emptied_subregion() {
+        let mut chunk = Chunk::default();
+        chunk.set_voxel(9, 0, 0, pack_voxel(MAT_DIRT, 0, 0, 0));
+        chunk.set_voxel(31, 31, 31, pack_voxel(MAT_DIRT, 0, 0, 0));
+        let before = chunk.occupancy_mask();
+        chunk.set_voxel(9, 0, 0, 0);
+        let p = UVec3::new(9, 0, 0);
+        let after = chunk.update_occupancy_mask(before, p, p);
+        assert_eq!(after, chunk.occupancy_mask());
+        assert_eq!(after, 1u64 << 63);
+    }
+
+    #[test]
+    fn update_occupancy_mask_sets_bits_across_box() {
+        let mut chunk = Chunk::default();
+        chunk.set_voxel(7, 0, 0, pack_voxel(MAT_STONE, 0, 0, 0));
+        chunk.set_voxel(8, 0, 0, pack_voxel(MAT_STONE, 0, 0, 0));
+        let mask = chunk.update_occupancy_mask(0, UVec3::new(7, 0, 0), UVec3::new(8, 0, 0));
+        assert_eq!(mask, 0b11);
+    }
+
     #[test]
     fn build_test_grid_returns_expected_chunks() {
         let grid = build_test_grid();
@@ -462,11 +772,16 @@ mod tests {
         );
     }
 
+    #[test]
+    fn voxel_coords_inverts_voxel_index() {
+        for &(x, y, z) in &[(0, 0, 0), (31, 0, 0), (0, 31, 0), (0, 0, 31), (5, 17, 29)] {
+            assert_eq!(voxel_coords(voxel_index(x, y, z)), (x, y, z));
+        }
+    }
+
     #[test]
     fn chunk_voxel_at_roundtrips() {
-        let mut chunk = Chunk {
-            voxels: vec![0u32; CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE],
-        };
+        let mut chunk = Chunk::default();
         chunk.set_voxel(5, 10, 15, pack_voxel(MAT_GRASS, 0, 0, 0));
         assert_eq!(material_id(chunk.voxel_at(5, 10, 15)), MAT_GRASS);
     }
@@ -16,7 +16,7 @@ use std::path::PathBuf;
 use glam::{IVec3, UVec3, Vec3};
 
 use engine::camera::{Camera, GridInfo};
-use engine::render::chunk_atlas::{ChunkAtlas, world_to_slot};
+use engine::render::chunk_atlas::ChunkAtlas;
 use engine::render::gpu::GpuContext;
 use engine::render::raymarch_pass::RaymarchPass;
 use engine::render::{build_palette, create_storage_texture};
@@ -129,10 +129,10 @@ impl HeadlessRenderer {
         let storage_view = storage_texture.create_view(&wgpu::TextureViewDescriptor::default());
 
         let mut atlas = ChunkAtlas::new(&gpu.device, GRID_INFO.atlas_slots);
+        atlas.set_grid(&gpu.queue, GRID_INFO.origin, GRID_INFO.size);
         let grid = build_test_grid();
-        for (coord, chunk) in &grid {
-            let slot = world_to_slot(*coord, GRID_INFO.atlas_slots);
-            atlas.upload_chunk(&gpu.queue, slot, chunk, *coord);
+        for (slot, (coord, chunk)) in grid.iter().enumerate() {
+            atlas.upload_chunk(&gpu.queue, slot as u32, chunk, *coord);
         }
 
         let palette = build_palette();
@@ -14,20 +14,17 @@ struct Camera {
     atlas_slots: vec3<u32>,
 }
 
-struct ChunkSlot {
-    world_pos: vec3<i32>,
-    flags: u32,
-}
-
 @group(0) @binding(0) var output: texture_storage_2d<rgba8unorm, write>;
 @group(0) @binding(1) var<uniform> camera: Camera;
 @group(0) @binding(2) var atlas: texture_3d<u32>;
-@group(0) @binding(3) var<storage, read> chunk_index: array<ChunkSlot>;
+// Slot + 1 for each chunk of the grid (X fastest), 0 when not uploaded.
+@group(0) @binding(3) var<storage, read> chunk_lookup: array<u32>;
 @group(0) @binding(4) var<storage, read> palette: array<vec4<f32>>;
 @group(0) @binding(5) var<storage, read> occupancy: array<vec2<u32>>;
 @group(0) @binding(6) var depth_output: texture_storage_2d<r32float, write>;
 @group(0) @binding(7) var<storage, read> visibility: array<u32>;
 @group(0) @binding(8) var<storage, read> light_buf: array<u32>;
+@group(0) @binding(9) var<storage, read> static_lights: array<vec4<f32>>;
 
 struct RayResult {
     color: vec4<f32>,
@@ -45,6 +42,12 @@ const SHADOW_BIAS: f32 = 0.01;
 const SHADOW_MAX_DIST: f32 = 64.0;
 const AO_DISTANCE: f32 = 6.0;
 const AO_SAMPLES: u32 = 6u;
+// Translucent surfaces entered before the next one is shaded as opaque.
+const MAX_TRANSLUCENT_LAYERS: u32 = 4u;
+// Per-voxel absorption inside a translucent medium, scaled by opacity.
+const ABSORPTION: f32 = 0.25;
+// Transmittance below which shadow/AO rays count as fully blocked.
+const MIN_TRANSMITTANCE: f32 = 0.01;
 
 // AO sample directions for +X normal face
 const AO_POS_X: array<vec3<f32>, 6> = array(
@@ -176,16 +179,13 @@ fn is_subregion_occupied(slot: u32, sub_region: vec3<i32>) -> bool {
 }
 
 /// Look up the atlas slot for a world chunk coordinate.
-/// Returns the flat slot index, or -1 if the slot is empty.
+/// Returns the flat slot index, or -1 if the chunk is not uploaded.
 /// Caller must ensure `world` is within grid bounds before calling.
 fn lookup_chunk(world: vec3<i32>) -> i32 {
-    let slots = vec3<i32>(camera.atlas_slots);
-    let wrapped = ((world % slots) + slots) % slots;
-    let idx = wrapped.z * slots.x * slots.y + wrapped.y * slots.x + wrapped.x;
-    if chunk_index[idx].flags == 0u {
-        return -1;
-    }
-    return idx;
+    let local = world - camera.grid_origin;
+    let size = vec3<i32>(camera.grid_size);
+    let idx = local.x + local.y * size.x + local.z * size.x * size.y;
+    return i32(chunk_lookup[idx]) - 1;
 }
 
 /// Determine which face of a chunk AABB the ray exits through and advance
@@ -208,6 +208,24 @@ fn advance_chunk(origin: vec3<f32>, dir: vec3<f32>, c_min: vec3<f32>,
     return out;
 }
 
+/// Apply FOV dimming to a shaded color at a world-space hit position.
+fn apply_fov(color: vec3<f32>, hit_pos: vec3<f32>) -> vec3<f32> {
+    let vis = fov_factor(i32(floor(hit_pos.x)), i32(floor(hit_pos.z)));
+    if vis < 1.0 {
+        // Outside FOV: dim + desaturate ~50%
+        let lum = dot(color, vec3<f32>(0.299, 0.587, 0.114));
+        return mix(color, vec3<f32>(lum), 0.5) * vis;
+    }
+    return color;
+}
+
+/// Beer-Lambert style absorption through `dist` voxels of material `mat_id`.
+/// Colored materials absorb their complementary channels faster.
+fn absorb(mat_id: u32, dist: f32) -> vec3<f32> {
+    let base = palette[mat_id];
+    return exp(-(1.0 - base.rgb) * base.a * ABSORPTION * max(dist, 0.0));
+}
+
 fn ray_march(origin: vec3<f32>, dir: vec3<f32>) -> RayResult {
     let grid_min = vec3<f32>(camera.grid_origin) * CHUNK;
     let grid_max = grid_min + vec3<f32>(camera.grid_size) * CHUNK;
@@ -227,45 +245,81 @@ fn ray_march(origin: vec3<f32>, dir: vec3<f32>) -> RayResult {
 
     let step = vec3<i32>(sign(dir));
 
-    for (var ci = 0u; ci < MAX_CHUNK_STEPS; ci++) {
+    // Translucent compositing state. `acc` collects light reflected by
+    // translucent surfaces in front of the final hit, `tint` is the fraction
+    // of light still transmitted toward the camera.
+    var acc = vec3<f32>(0.0);
+    var tint = vec3<f32>(1.0);
+    var medium = 0u;
+    var medium_t = 0.0;
+    var layers = 0u;
+    var t_resume = 0.0;
+    var hit_any = false;
+    var first_depth = 1.0;
+    var first_pos = vec3<f32>(0.0);
+
+    for (var ci = 0u; ci < MAX_CHUNK_STEPS + 2u * MAX_TRANSLUCENT_LAYERS; ci++) {
         // Bounds check: if we left the grid, it's sky.
         let local = chunk_coord - camera.grid_origin;
         let grid = vec3<i32>(camera.grid_size);
         if any(local < vec3(0)) || any(local >= grid) {
-            return RayResult(SKY, 1.0);
+            break;
         }
 
         let c_min = vec3<f32>(chunk_coord) * CHUNK;
         let c_max = c_min + CHUNK;
+        let c_aabb = intersect_aabb(origin, dir, c_min, c_max);
 
         let slot = lookup_chunk(chunk_coord);
         if slot < 0 {
-            // Empty chunk within grid — skip through to next chunk.
+            // Empty chunk within grid — any medium ends at its boundary.
+            if medium != 0u {
+                tint *= absorb(medium, c_aabb.x - medium_t);
+                medium = 0u;
+            }
             chunk_coord = advance_chunk(origin, dir, c_min, c_max, step, chunk_coord);
             continue;
         }
 
         let slot_off = atlas_origin(u32(slot));
-        let c_aabb = intersect_aabb(origin, dir, c_min, c_max);
-        let ct = max(c_aabb.x, 0.0) + 0.001;
+        let ct = max(max(c_aabb.x, 0.0) + 0.001, t_resume);
 
-        let result = dda_chunk(origin, dir, ct, c_min, slot_off, step, u32(slot));
+        let result = dda_chunk(origin, dir, ct, c_min, slot_off, step, u32(slot), medium);
         if result.x >= 0.0 {
-            // Hit — result encodes (material_id, face, t_hit, _)
+            // Hit — result encodes (material_id, face, t_hit, params)
             let mat_id = u32(result.x);
+            let params = u32(result.w);
             let face = u32(result.y);
-            let t_hit = result.z;
+            let t_hit = max(result.z, ct);
             let hit_pos = origin + dir * t_hit;
-            let depth = clamp(t_hit / camera.max_ray_distance, 0.0, 1.0);
-            var shaded = shade(mat_id, face, step, hit_pos);
-            let vis = fov_factor(i32(floor(hit_pos.x)), i32(floor(hit_pos.z)));
-            if vis < 1.0 {
-                // Outside FOV: dim + desaturate ~50%
-                let lum = dot(shaded.rgb, vec3<f32>(0.299, 0.587, 0.114));
-                let desat = mix(shaded.rgb, vec3<f32>(lum), 0.5);
-                shaded = vec4(desat * vis, shaded.a);
+            if !hit_any {
+                hit_any = true;
+                first_depth = clamp(t_hit / camera.max_ray_distance, 0.0, 1.0);
+                first_pos = hit_pos;
+            }
+            if medium != 0u {
+                tint *= absorb(medium, t_hit - medium_t);
+            }
+
+            let alpha = palette[mat_id].a;
+            if mat_id == 0u || (alpha < 1.0 && layers < MAX_TRANSLUCENT_LAYERS) {
+                // Crossing into air or a translucent material: composite the
+                // surface and keep marching from just past the boundary.
+                if mat_id != 0u {
+                    let surface = shade(mat_id, params, face, step, hit_pos);
+                    acc += tint * alpha * surface.rgb;
+                    tint *= 1.0 - alpha;
+                    layers++;
+                }
+                medium = mat_id;
+                medium_t = t_hit;
+                t_resume = t_hit + 0.001;
+                continue;
             }
-            return RayResult(shaded, depth);
+
+            let shaded = shade(mat_id, params, face, step, hit_pos);
+            let color = acc + tint * shaded.rgb;
+            return RayResult(vec4(apply_fov(color, first_pos), 1.0), first_depth);
         }
 
         // Advance to next chunk along the exit face.
@@ -275,11 +329,19 @@ fn ray_march(origin: vec3<f32>, dir: vec3<f32>) -> RayResult {
         else { chunk_coord.z += step.z; }
     }
 
-    return RayResult(SKY, 1.0);
+    if !hit_any {
+        return RayResult(SKY, 1.0);
+    }
+    // Passed through translucent voxels without hitting anything opaque.
+    let color = acc + tint * SKY.rgb;
+    return RayResult(vec4(apply_fov(color, first_pos), 1.0), first_depth);
 }
 
-/// DDA within a single chunk. Returns:
-///   hit:  vec4(material_id, face, t_hit, 0)  — material_id > 0, t_hit is world-space parametric distance
+/// DDA within a single chunk, stopping at the first voxel whose material
+/// differs from `medium` (the material the ray is currently travelling
+/// through; 0 for air). Returns:
+///   hit:  vec4(material_id, face, t_hit, params) — t_hit is world-space parametric distance,
+///         params is the voxel's upper 24 bits (param0 | param1 << 8 | flags << 16)
 ///   miss: vec4(-(exit_face+1), 0, 0, 0)      — encodes which face the ray exited
 fn dda_chunk(
     origin: vec3<f32>, dir: vec3<f32>,
@@ -288,6 +350,7 @@ fn dda_chunk(
     slot_off: vec3<u32>,
     step: vec3<i32>,
     slot: u32,
+    medium: u32,
 ) -> vec4<f32> {
     let local_pos = origin + dir * t_start - chunk_min;
     var map = vec3<i32>(floor(local_pos));
@@ -309,9 +372,10 @@ fn dda_chunk(
             return vec4(-f32(face) - 1.0, 0.0, 0.0, 0.0);
         }
 
-        // Sub-region skip: check occupancy bitmask
+        // Sub-region skip: check occupancy bitmask. Only valid in air, since
+        // an empty sub-region is exactly where a medium ends.
         let sr = vec3<i32>(map.x >> 3, map.y >> 3, map.z >> 3);
-        if !is_subregion_occupied(slot, sr) {
+        if medium == 0u && !is_subregion_occupied(slot, sr) {
             // Advance to exit of this 8-voxel sub-region
             let sr_min = vec3<f32>(sr * 8);
             let sr_max = sr_min + 8.0;
@@ -343,7 +407,7 @@ fn dda_chunk(
         }
 
         let texel = textureLoad(atlas, slot_off + vec3<u32>(map), 0);
-        if texel.r != 0u {
+        if (texel.r & 0xFFu) != medium {
             // Compute t of entry into this voxel: side was already advanced past
             // the crossing, so subtract delta to get the crossing t (in local space).
             var t_voxel_entry: f32;
@@ -354,7 +418,9 @@ fn dda_chunk(
             } else {
                 t_voxel_entry = side.z - delta.z;
             }
-            return vec4(f32(texel.r), f32(face), t_start + t_voxel_entry, 0.0);
+            // The upper 24 bits (param0, param1, flags) are exact in an f32.
+            return vec4(f32(texel.r & 0xFFu), f32(face), t_start + t_voxel_entry,
+                        f32(texel.r >> 8u));
         }
 
         if side.x < side.y && side.x < side.z {
@@ -370,7 +436,9 @@ fn dda_chunk(
     return vec4(-f32(face) - 1.0, 0.0, 0.0, 0.0);
 }
 
-/// Boolean DDA within a single chunk. Returns true if any solid voxel is hit.
+/// Transmittance DDA within a single chunk. Returns the fraction of light
+/// that passes through the chunk: 0 once an opaque voxel is hit, reduced by
+/// each translucent voxel crossed.
 fn trace_ray_chunk(
     origin: vec3<f32>, dir: vec3<f32>,
     t_start: f32,
@@ -379,7 +447,7 @@ fn trace_ray_chunk(
     step: vec3<i32>,
     max_t: f32,
     slot: u32,
-) -> bool {
+) -> f32 {
     let local_pos = origin + dir * t_start - chunk_min;
     var map = vec3<i32>(floor(local_pos));
     map = clamp(map, vec3(0), vec3(CHUNK_I - 1));
@@ -391,17 +459,19 @@ fn trace_ray_chunk(
         select(f32(map.z) + 1.0, f32(map.z), dir.z < 0.0),
     ) - local_pos) / dir;
 
+    var trans = 1.0;
+
     for (var i = 0u; i < MAX_VOXEL_STEPS; i++) {
         if map.x < 0 || map.x >= CHUNK_I ||
            map.y < 0 || map.y >= CHUNK_I ||
            map.z < 0 || map.z >= CHUNK_I {
-            return false;
+            return trans;
         }
 
         // Distance check: use min(side) as approximation of current t.
         let current_t = t_start + min(min(side.x, side.y), side.z);
         if current_t > max_t {
-            return false;
+            return trans;
         }
 
         // Sub-region skip: check occupancy bitmask
@@ -429,8 +499,13 @@ fn trace_ray_chunk(
         }
 
         let texel = textureLoad(atlas, slot_off + vec3<u32>(map), 0);
-        if texel.r != 0u {
-            return true;
+        let mat = texel.r & 0xFFu;
+        if mat != 0u {
+            let alpha = palette[mat].a;
+            trans *= 1.0 - alpha;
+            if alpha >= 1.0 || trans < MIN_TRANSMITTANCE {
+                return 0.0;
+            }
         }
 
         if side.x < side.y && side.x < side.z {
@@ -442,18 +517,19 @@ fn trace_ray_chunk(
         }
     }
 
-    return false;
+    return trans;
 }
 
-/// Trace a ray through the grid. Returns true if any solid voxel is hit
-/// within max_dist. Used for shadow and AO rays.
-fn trace_ray(origin: vec3<f32>, dir: vec3<f32>, max_dist: f32) -> bool {
+/// Trace a ray through the grid. Returns the transmittance along the ray
+/// within max_dist: 1 when unobstructed, 0 when an opaque voxel is hit.
+/// Used for shadow and AO rays.
+fn trace_ray(origin: vec3<f32>, dir: vec3<f32>, max_dist: f32) -> f32 {
     let grid_min = vec3<f32>(camera.grid_origin) * CHUNK;
     let grid_max = grid_min + vec3<f32>(camera.grid_size) * CHUNK;
 
     let aabb = intersect_aabb(origin, dir, grid_min, grid_max);
     if aabb.x > aabb.y || aabb.y < 0.0 {
-        return false;
+        return 1.0;
     }
 
     let t_enter = max(aabb.x, 0.0) + 0.001;
@@ -465,12 +541,13 @@ fn trace_ray(origin: vec3<f32>, dir: vec3<f32>, max_dist: f32) -> bool {
     chunk_coord = clamp(chunk_coord, camera.grid_origin, grid_end);
 
     let step = vec3<i32>(sign(dir));
+    var trans = 1.0;
 
     for (var ci = 0u; ci < MAX_CHUNK_STEPS; ci++) {
         let local = chunk_coord - camera.grid_origin;
         let grid = vec3<i32>(camera.grid_size);
         if any(local < vec3(0)) || any(local >= grid) {
-            return false;
+            return trans;
         }
 
         let c_min = vec3<f32>(chunk_coord) * CHUNK;
@@ -482,8 +559,9 @@ fn trace_ray(origin: vec3<f32>, dir: vec3<f32>, max_dist: f32) -> bool {
             let c_aabb = intersect_aabb(origin, dir, c_min, c_max);
             let ct = max(c_aabb.x, 0.0) + 0.001;
 
-            if trace_ray_chunk(origin, dir, ct, c_min, slot_off, step, max_t, u32(slot)) {
-                return true;
+            trans *= trace_ray_chunk(origin, dir, ct, c_min, slot_off, step, max_t, u32(slot));
+            if trans < MIN_TRANSMITTANCE {
+                return 0.0;
             }
         }
 
@@ -494,11 +572,11 @@ fn trace_ray(origin: vec3<f32>, dir: vec3<f32>, max_dist: f32) -> bool {
         let next_min = vec3<f32>(chunk_coord) * CHUNK;
         let next_aabb = intersect_aabb(origin, dir, next_min, next_min + CHUNK);
         if next_aabb.x > max_t {
-            return false;
+            return trans;
         }
     }
 
-    return false;
+    return trans;
 }
 
 /// Sample ambient occlusion by casting short rays into the hemisphere
@@ -516,14 +594,13 @@ fn trace_ao(origin: vec3<f32>, face: u32, step: vec3<i32>) -> f32 {
         if step.z > 0 { dirs = AO_NEG_Z; } else { dirs = AO_POS_Z; }
     }
 
-    var hits = 0u;
+    // Translucent occluders count partially.
+    var occlusion = 0.0;
     for (var i = 0u; i < AO_SAMPLES; i++) {
-        if trace_ray(origin, dirs[i], AO_DISTANCE) {
-            hits += 1u;
-        }
+        occlusion += 1.0 - trace_ray(origin, dirs[i], AO_DISTANCE);
     }
 
-    return 1.0 - f32(hits) / f32(AO_SAMPLES);
+    return 1.0 - occlusion / f32(AO_SAMPLES);
 }
 
 /// Look up the FOV visibility for a world-space (x, z) position.
@@ -553,6 +630,10 @@ fn fov_factor(world_x: i32, world_z: i32) -> f32 {
 }
 
 const MAX_LIGHTS_PER_PIXEL: u32 = 8u;
+// Offset of the emission entries in the palette (base colors come first).
+const EMISSION_OFFSET: u32 = 256u;
+// vec4s per atlas slot in `static_lights`: a count header + 8 lights of 2 vec4s.
+const STATIC_LIGHT_SLOT_VEC4S: u32 = 17u;
 
 fn read_light_f32(base: u32, offset: u32) -> f32 {
     return bitcast<f32>(light_buf[base + offset]);
@@ -608,17 +689,15 @@ fn evaluate_lights(hit_pos: vec3<f32>, normal: vec3<f32>) -> vec3<f32> {
         let ndotl = max(dot(normal, light_dir), 0.0);
 
         // Optional shadow ray
-        var shadowed = false;
+        var visible = 1.0;
         if ENABLE_LIGHT_SHADOWS {
             if (kind & 2u) != 0u {
                 let shadow_origin = hit_pos + normal * SHADOW_BIAS;
-                shadowed = trace_ray(shadow_origin, light_dir, dist);
+                visible = trace_ray(shadow_origin, light_dir, dist);
             }
         }
 
-        if !shadowed {
-            total += light_color * att * ndotl;
-        }
+        total += light_color * att * ndotl * visible;
 
         evaluated++;
     }
@@ -626,13 +705,83 @@ fn evaluate_lights(hit_pos: vec3<f32>, normal: vec3<f32>) -> vec3<f32> {
     return total;
 }
 
-fn shade(mat_id: u32, face: u32, step: vec3<i32>, hit_pos: vec3<f32>) -> vec4<f32> {
+/// Sum the static lights registered by emissive voxels in the 2x2x2 chunks
+/// nearest `hit_pos`. Static light radii are capped at half a chunk, so no
+/// other chunk can hold a light that reaches this point.
+fn evaluate_static_lights(hit_pos: vec3<f32>, normal: vec3<f32>) -> vec3<f32> {
+    let base_chunk = vec3<i32>(floor((hit_pos - CHUNK * 0.5) / CHUNK));
+    let grid_end = camera.grid_origin + vec3<i32>(camera.grid_size);
+    var total = vec3<f32>(0.0);
+
+    for (var n = 0u; n < 8u; n++) {
+        let cc = base_chunk + vec3<i32>(vec3<u32>(n & 1u, (n >> 1u) & 1u, (n >> 2u) & 1u));
+        if any(cc < camera.grid_origin) || any(cc >= grid_end) { continue; }
+        let slot = lookup_chunk(cc);
+        if slot < 0 { continue; }
+
+        let base = u32(slot) * STATIC_LIGHT_SLOT_VEC4S;
+        let count = bitcast<u32>(static_lights[base].x);
+        for (var i = 0u; i < count; i++) {
+            let pos_radius = static_lights[base + 1u + i * 2u];
+            let to_light = pos_radius.xyz - hit_pos;
+            let dist = length(to_light);
+            if dist > pos_radius.w || dist < 1e-4 { continue; }
+
+            let att_linear = saturate(1.0 - dist / pos_radius.w);
+            let ndotl = max(dot(normal, to_light / dist), 0.0);
+            total += static_lights[base + 2u + i * 2u].rgb * att_linear * att_linear * ndotl;
+        }
+    }
+
+    return total;
+}
+
+// Per-variant color multipliers (param1 bits 6-7).
+const VARIANT_TINTS: array<vec3<f32>, 4> = array(
+    vec3(1.0, 1.0, 1.0),
+    vec3(1.04, 1.0, 0.94),
+    vec3(0.95, 1.03, 0.98),
+    vec3(0.98, 0.96, 1.04),
+);
+
+/// Palette color adjusted by the voxel's shading parameters: param0 is a
+/// signed brightness offset, param1 packs wetness (bits 0-2), damage
+/// (bits 3-5) and variant (bits 6-7). See `voxel.rs`.
+fn voxel_albedo(mat_id: u32, params: u32, normal: vec3<f32>, hit_pos: vec3<f32>) -> vec3<f32> {
+    var color = palette[mat_id].rgb;
+
+    let brightness = f32(bitcast<i32>((params & 0xFFu) << 24u) >> 24u) / 256.0;
+    color *= 1.0 + brightness;
+
+    let param1 = (params >> 8u) & 0xFFu;
+    color *= VARIANT_TINTS[param1 >> 6u];
+
+    // Wet surfaces darken and shift slightly toward blue.
+    let wet = f32(param1 & 7u) / 7.0;
+    color *= mix(vec3(1.0), vec3(0.55, 0.6, 0.7), wet);
+
+    // Damage darkens a band along the voxel's edges that widens per level.
+    let damage = f32((param1 >> 3u) & 7u);
+    if damage > 0.0 {
+        let f = fract(hit_pos);
+        // Face-local 2D coordinates: drop the axis along the normal.
+        var uv = f.xy;
+        if abs(normal.x) > 0.5 { uv = f.yz; } else if abs(normal.y) > 0.5 { uv = f.xz; }
+        let edge = min(min(uv.x, 1.0 - uv.x), min(uv.y, 1.0 - uv.y));
+        let crack = 1.0 - step(damage * 0.03, edge);
+        color *= 1.0 - 0.06 * damage - 0.35 * crack;
+    }
+
+    return max(color, vec3(0.0));
+}
+
+fn shade(mat_id: u32, params: u32, face: u32, step: vec3<i32>, hit_pos: vec3<f32>) -> vec4<f32> {
     var normal = vec3<f32>(0.0);
     if face == 0u { normal.x = -f32(step.x); }
     else if face == 1u { normal.y = -f32(step.y); }
     else { normal.z = -f32(step.z); }
 
-    let base = palette[mat_id];
+    let base = voxel_albedo(mat_id, params, normal, hit_pos);
     let shadow_origin = hit_pos + normal * SHADOW_BIAS;
 
     var ambient = 0.15;
@@ -645,15 +794,16 @@ fn shade(mat_id: u32, face: u32, step: vec3<i32>, hit_pos: vec3<f32>) -> vec4<f3
     if ENABLE_SUN_DIFFUSE {
         let ndotl = max(dot(normal, SUN_DIR), 0.0);
         if ENABLE_SUN_SHADOWS {
-            let in_shadow = trace_ray(shadow_origin, SUN_DIR, SHADOW_MAX_DIST);
-            diffuse = select(ndotl, 0.0, in_shadow);
+            diffuse = ndotl * trace_ray(shadow_origin, SUN_DIR, SHADOW_MAX_DIST);
         } else {
             diffuse = ndotl;
         }
     }
     if ENABLE_LOCAL_LIGHTS {
-        local = evaluate_lights(hit_pos, normal);
+        local = evaluate_lights(hit_pos, normal) + evaluate_static_lights(hit_pos, normal);
     }
 
-    return vec4(base.rgb * (ambient + diffuse + local), 1.0);
+    // Emissive materials glow on top of whatever light reaches them.
+    let emission = palette[EMISSION_OFFSET + mat_id].rgb;
+    return vec4(base * (ambient + diffuse + local) + emission, 1.0);
 }
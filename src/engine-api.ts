@@ -12,10 +12,15 @@ import {
   camera_y as _camera_y,
   camera_yaw as _camera_yaw,
   camera_z as _camera_z,
+  dungeon_connections as _dungeon_connections,
+  dungeon_rooms as _dungeon_rooms,
   is_chunk_loaded_at as _is_chunk_loaded_at,
   look_at as _look_at,
+  pin_chunk_region as _pin_chunk_region,
   preload_view as _preload_view,
+  remove_interest_point as _remove_interest_point,
   set_camera as _set_camera,
+  set_interest_point as _set_interest_point,
   type EasingKind,
 } from "../crates/engine/pkg/engine";
 
@@ -29,6 +34,7 @@ export {
   set_dolly,
   set_look_delta,
   take_animation_completed,
+  unpin_chunk_region,
 } from "../crates/engine/pkg/engine";
 
 import type { CameraPose, IVec3, Vec3 } from "./vec";
@@ -63,11 +69,55 @@ export function preloadView(pos: Vec3) {
   _preload_view(pos.x, pos.y, pos.z);
 }
 
+/**
+ * Stream a `radius`-chunk cube around `pos` under `name` (player, NPC,
+ * cinematic target). `weight` above 1 loads its chunks after the camera's.
+ */
+export function setInterestPoint(name: string, pos: Vec3, radius: number, weight = 1) {
+  _set_interest_point(name, pos.x, pos.y, pos.z, radius, weight);
+}
+
+/** Stop streaming around the interest point `name`. */
+export function removeInterestPoint(name: string) {
+  _remove_interest_point(name);
+}
+
+/** Keep the chunks between two chunk coordinates loaded until unpinned. */
+export function pinChunkRegion(name: string, a: IVec3, b: IVec3) {
+  _pin_chunk_region(name, a.x, a.y, a.z, b.x, b.y, b.z);
+}
+
 /** Check whether a chunk at the given chunk coordinate is loaded. */
 export function isChunkLoaded(coord: IVec3) {
   return _is_chunk_loaded_at(coord.x, coord.y, coord.z);
 }
 
+/** A dungeon room's interior, inclusive; `min.y` is the floor. */
+export interface DungeonRoom {
+  readonly level: number;
+  readonly min: IVec3;
+  readonly max: IVec3;
+}
+
+/** Rooms of the map's dungeon and the corridors joining them, as index pairs. */
+export function dungeonLayout(): { rooms: DungeonRoom[]; connections: [number, number][] } {
+  const r = _dungeon_rooms();
+  const c = _dungeon_connections();
+  const rooms: DungeonRoom[] = [];
+  for (let i = 0; i + 7 <= r.length; i += 7) {
+    rooms.push({
+      level: r[i],
+      min: { x: r[i + 1], y: r[i + 2], z: r[i + 3] },
+      max: { x: r[i + 4], y: r[i + 5], z: r[i + 6] },
+    });
+  }
+  const connections: [number, number][] = [];
+  for (let i = 0; i + 2 <= c.length; i += 2) {
+    connections.push([c[i], c[i + 1]]);
+  }
+  return { rooms, connections };
+}
+
 /** Get the current camera pose (position + yaw + pitch). */
 export function cameraPose(): CameraPose {
   return {
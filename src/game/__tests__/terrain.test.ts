@@ -1,36 +1,78 @@
 // @vitest-environment node
 import { describe, expect, it } from "vitest";
-import { deserializeTerrainGrid, getTerrainDef } from "../terrain";
+import {
+  applyMaterialTerrainTable,
+  deserializeTerrainGrid,
+  getTerrainDef,
+  isClimbableSurface,
+  isHazardSurface,
+  isWalkableSurface,
+  registerTerrainDef,
+  TILE_BLOCKED,
+  TILE_CLIMBABLE,
+  TILE_HAZARD,
+} from "../terrain";
+
+/** Encodes materials the way `MaterialRegistry::terrain_table_bytes` does. */
+function materialTable(entries: [number, boolean, number, string][]): ArrayBuffer {
+  const bytes: number[] = [];
+  for (const [id, walkable, cost, name] of entries) {
+    const utf8 = new TextEncoder().encode(name);
+    bytes.push(id, walkable ? 1 : 0, cost, utf8.length, ...utf8);
+  }
+  return new Uint8Array(bytes).buffer;
+}
 
 describe("deserializeTerrainGrid", () => {
   it("deserializes a single-surface column", () => {
-    const bytes = new Uint8Array(1 + 3 + 1023);
+    const bytes = new Uint8Array(1 + 4 + 1023);
     bytes[0] = 1;
     bytes[1] = 5;
     bytes[2] = 1;
     bytes[3] = 26;
+    bytes[4] = 0;
     const grid = deserializeTerrainGrid(0, 0, 0, bytes.buffer);
-    expect(grid.columns[0]).toEqual([{ y: 5, terrainId: 1, headroom: 26 }]);
+    expect(grid.columns[0]).toEqual([{ y: 5, terrainId: 1, headroom: 26, flags: 0 }]);
     expect(grid.columns[1]).toEqual([]);
   });
 
   it("deserializes a bridge column with two surfaces", () => {
-    const bytes = new Uint8Array(1 + 6 + 1023);
+    const bytes = new Uint8Array(1 + 8 + 1023);
     bytes[0] = 2;
     bytes[1] = 0;
     bytes[2] = 1;
     bytes[3] = 9;
-    bytes[4] = 10;
-    bytes[5] = 3;
-    bytes[6] = 21;
+    bytes[4] = 0;
+    bytes[5] = 10;
+    bytes[6] = 3;
+    bytes[7] = 21;
+    bytes[8] = 0;
     const grid = deserializeTerrainGrid(1, 0, 2, bytes.buffer);
     expect(grid.columns[0]).toHaveLength(2);
     expect(grid.columns[0][0].y).toBe(0);
     expect(grid.columns[0][1].y).toBe(10);
   });
+
+  it("reads climbable and hazard tile flags", () => {
+    const bytes = new Uint8Array(1 + 4 + 1 + 4 + 1022);
+    bytes.set([1, 5, 1, 26, TILE_CLIMBABLE], 0);
+    bytes.set([1, 5, 3, 26, TILE_HAZARD], 5);
+    const grid = deserializeTerrainGrid(0, 0, 0, bytes.buffer);
+    expect(isClimbableSurface(grid.columns[0][0])).toBe(true);
+    expect(isHazardSurface(grid.columns[0][0])).toBe(false);
+    expect(isHazardSurface(grid.columns[1][0])).toBe(true);
+    expect(isClimbableSurface(grid.columns[1][0])).toBe(false);
+  });
 });
 
 describe("getTerrainDef", () => {
+  applyMaterialTerrainTable(
+    materialTable([
+      [0, false, 255, "air"],
+      [1, true, 1, "grass"],
+    ]),
+  );
+
   it("returns grass terrain", () => {
     const def = getTerrainDef(1);
     expect(def).toBeDefined();
@@ -45,4 +87,45 @@ describe("getTerrainDef", () => {
   it("air is not walkable", () => {
     expect(getTerrainDef(0)?.walkable).toBe(false);
   });
+
+  it("returns registered runtime terrain", () => {
+    registerTerrainDef({
+      id: 42,
+      name: "mud",
+      walkable: true,
+      movementCost: 3,
+      combatModifier: -1,
+    });
+    expect(getTerrainDef(42)?.movementCost).toBe(3);
+  });
+});
+
+describe("applyMaterialTerrainTable", () => {
+  it("takes walkability and cost from the registry and keeps game extras", () => {
+    applyMaterialTerrainTable(
+      materialTable([
+        [3, true, 1, "stone"],
+        [6, true, 2, "ice"],
+        [7, false, 255, "lava"],
+      ]),
+    );
+    expect(getTerrainDef(6)).toMatchObject({ name: "ice", walkable: true, movementCost: 2 });
+    expect(getTerrainDef(3)?.combatModifier).toBe(1);
+    expect(getTerrainDef(7)?.effect).toEqual({ type: "damage", amount: 10 });
+  });
+
+  it("replaces a redefined material, dropping the old extras", () => {
+    applyMaterialTerrainTable(materialTable([[7, true, 3, "basalt"]]));
+    expect(getTerrainDef(7)).toMatchObject({ name: "basalt", walkable: true, movementCost: 3 });
+    expect(getTerrainDef(7)?.effect).toBeUndefined();
+  });
+});
+
+describe("isWalkableSurface", () => {
+  it("follows the blocked tile flag", () => {
+    expect(isWalkableSurface({ y: 0, terrainId: 3, headroom: 31, flags: 0 })).toBe(true);
+    expect(isWalkableSurface({ y: 0, terrainId: 3, headroom: 31, flags: TILE_BLOCKED })).toBe(
+      false,
+    );
+  });
 });
@@ -7,7 +7,7 @@ import { GameWorld } from "../world";
 function makeFlat(): ChunkTerrainGrid {
   const columns: TileSurface[][] = [];
   for (let i = 0; i < 32 * 32; i++) {
-    columns.push([{ y: 5, terrainId: 1, headroom: 26 }]);
+    columns.push([{ y: 5, terrainId: 1, headroom: 26, flags: 0 }]);
   }
   return { cx: 0, cy: 0, cz: 0, columns };
 }
@@ -15,12 +15,12 @@ function makeFlat(): ChunkTerrainGrid {
 function makeStaircase(): ChunkTerrainGrid {
   const columns: TileSurface[][] = [];
   for (let i = 0; i < 32 * 32; i++) {
-    columns.push([{ y: 5, terrainId: 1, headroom: 26 }]);
+    columns.push([{ y: 5, terrainId: 1, headroom: 26, flags: 0 }]);
   }
   // (6,5) has surface at y=6 (1 step up)
-  columns[5 * 32 + 6] = [{ y: 6, terrainId: 1, headroom: 25 }];
+  columns[5 * 32 + 6] = [{ y: 6, terrainId: 1, headroom: 25, flags: 0 }];
   // (7,5) has surface at y=9 (needs jump from y=5)
-  columns[5 * 32 + 7] = [{ y: 9, terrainId: 1, headroom: 22 }];
+  columns[5 * 32 + 7] = [{ y: 9, terrainId: 1, headroom: 22, flags: 0 }];
   return { cx: 0, cy: 0, cz: 0, columns };
 }
 
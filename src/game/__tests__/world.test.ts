@@ -6,7 +6,7 @@ import { GameWorld } from "../world";
 function makeFlat(cx: number, cz: number, surfaceY: number, terrainId: number): ChunkTerrainGrid {
   const columns: TileSurface[][] = [];
   for (let i = 0; i < 32 * 32; i++) {
-    columns.push([{ y: surfaceY, terrainId, headroom: 31 - surfaceY }]);
+    columns.push([{ y: surfaceY, terrainId, headroom: 31 - surfaceY, flags: 0 }]);
   }
   return { cx, cy: 0, cz, columns };
 }
@@ -14,18 +14,18 @@ function makeFlat(cx: number, cz: number, surfaceY: number, terrainId: number):
 function makeStaircase(): ChunkTerrainGrid {
   const columns: TileSurface[][] = [];
   for (let i = 0; i < 32 * 32; i++) {
-    columns.push([{ y: 5, terrainId: 1, headroom: 26 }]);
+    columns.push([{ y: 5, terrainId: 1, headroom: 26, flags: 0 }]);
   }
   // Column at (1,0) has surface at y=6 (1 step up from y=5)
-  columns[0 * 32 + 1] = [{ y: 6, terrainId: 1, headroom: 25 }];
+  columns[0 * 32 + 1] = [{ y: 6, terrainId: 1, headroom: 25, flags: 0 }];
   // Column at (2,0) has surface at y=9 (needs jump from y=5 or y=6)
-  columns[0 * 32 + 2] = [{ y: 9, terrainId: 1, headroom: 22 }];
+  columns[0 * 32 + 2] = [{ y: 9, terrainId: 1, headroom: 22, flags: 0 }];
   // Column at (3,0) has surface at y=20 (unreachable from y=5)
-  columns[0 * 32 + 3] = [{ y: 20, terrainId: 1, headroom: 11 }];
+  columns[0 * 32 + 3] = [{ y: 20, terrainId: 1, headroom: 11, flags: 0 }];
   // Column at (4,0) has two surfaces: y=5 and y=12 (bridge)
   columns[0 * 32 + 4] = [
-    { y: 5, terrainId: 1, headroom: 6 },
-    { y: 12, terrainId: 1, headroom: 19 },
+    { y: 5, terrainId: 1, headroom: 6, flags: 0 },
+    { y: 12, terrainId: 1, headroom: 19, flags: 0 },
   ];
   return { cx: 0, cy: 0, cz: 0, columns };
 }
@@ -115,7 +115,7 @@ describe("findReachableSurface", () => {
     // Terrain in chunk cy=1 (world Y offset = 32), surface at local y=5 = world y=37
     const columns: TileSurface[][] = [];
     for (let i = 0; i < 32 * 32; i++) {
-      columns.push([{ y: 5, terrainId: 1, headroom: 26 }]);
+      columns.push([{ y: 5, terrainId: 1, headroom: 26, flags: 0 }]);
     }
     world.loadTerrain({ cx: 0, cy: 1, cz: 0, columns });
     // Player at world y=37, stepping to (1,0) — should find surface at world y=37
@@ -139,8 +139,8 @@ describe("findReachableSurface", () => {
     const columns: TileSurface[][] = [];
     for (let i = 0; i < 32 * 32; i++) {
       columns.push([
-        { y: 5, terrainId: 1, headroom: 6 },
-        { y: 12, terrainId: 1, headroom: 19 },
+        { y: 5, terrainId: 1, headroom: 6, flags: 0 },
+        { y: 12, terrainId: 1, headroom: 19, flags: 0 },
       ]);
     }
     world.loadTerrain({ cx: 0, cy: 0, cz: 0, columns });
@@ -152,7 +152,7 @@ describe("findReachableSurface", () => {
     // Surface at local y=24 in chunk cy=0 = world y=24
     const columns: TileSurface[][] = [];
     for (let i = 0; i < 32 * 32; i++) {
-      columns.push([{ y: 24, terrainId: 1, headroom: 7 }]);
+      columns.push([{ y: 24, terrainId: 1, headroom: 7, flags: 0 }]);
     }
     world.loadTerrain({ cx: 0, cy: 0, cz: 0, columns });
     // Player at world y=24
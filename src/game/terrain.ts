@@ -16,8 +16,23 @@ export interface TileSurface {
   y: number;
   terrainId: number;
   headroom: number;
+  /** Bitfield of TILE_CLIMBABLE / TILE_HAZARD / TILE_BLOCKED. */
+  flags: number;
 }
 
+/** Tile flag bits, mirroring `TILE_*` in `terrain_grid.rs`. */
+export const TILE_CLIMBABLE = 1 << 0;
+export const TILE_HAZARD = 1 << 1;
+export const TILE_BLOCKED = 1 << 2;
+
+/** Voxel flag bits, mirroring `FLAG_*` in `voxel.rs`. */
+export const VOXEL_FLAG_DOOR = 1 << 0;
+export const VOXEL_FLAG_OPEN = 1 << 1;
+export const VOXEL_FLAG_CLIMBABLE = 1 << 2;
+export const VOXEL_FLAG_HAZARD = 1 << 3;
+export const VOXEL_FLAG_INDESTRUCTIBLE = 1 << 4;
+export const VOXEL_FLAG_LIQUID = 1 << 5;
+
 export interface ChunkTerrainGrid {
   cx: number;
   cy: number;
@@ -44,6 +59,7 @@ export function deserializeTerrainGrid(
         y: bytes[offset++],
         terrainId: bytes[offset++],
         headroom: bytes[offset++],
+        flags: bytes[offset++],
       });
     }
     columns.push(surfaces);
@@ -51,53 +67,65 @@ export function deserializeTerrainGrid(
   return { cx, cy, cz, columns };
 }
 
-export const TERRAIN_TABLE: Map<number, TerrainDef> = new Map([
-  [
-    0,
-    {
-      id: 0,
-      name: "air",
-      walkable: false,
-      movementCost: 255,
-      combatModifier: 0,
-    },
-  ],
-  [
-    1,
-    {
-      id: 1,
-      name: "grass",
-      walkable: true,
-      movementCost: 1,
-      combatModifier: 0,
-    },
-  ],
-  [
-    2,
-    {
-      id: 2,
-      name: "dirt",
-      walkable: true,
-      movementCost: 1,
-      combatModifier: 0,
-    },
-  ],
-  [
-    3,
-    {
-      id: 3,
-      name: "stone",
-      walkable: true,
-      movementCost: 1,
-      combatModifier: 1,
-    },
-  ],
+/**
+ * Game-only terrain data, keyed by material name. Names, walkability and
+ * movement costs come from the engine's material registry via
+ * `applyMaterialTerrainTable`.
+ */
+const TERRAIN_EXTRAS: Map<string, Pick<TerrainDef, "combatModifier" | "effect">> = new Map([
+  ["stone", { combatModifier: 1 }],
+  ["lava", { combatModifier: 0, effect: { type: "damage", amount: 10 } }],
 ]);
 
+/** Terrain definitions by material id, filled from the engine's material registry. */
+export const TERRAIN_TABLE: Map<number, TerrainDef> = new Map();
+
+/**
+ * Registers a terrain definition for every material in a table produced by
+ * `MaterialRegistry::terrain_table_bytes` in `material.rs`:
+ * `[id, walkable, movementCost, nameLen, name...]` per material.
+ */
+export function applyMaterialTerrainTable(data: ArrayBuffer): void {
+  const bytes = new Uint8Array(data);
+  const decoder = new TextDecoder();
+  let offset = 0;
+  while (offset + 4 <= bytes.length) {
+    const id = bytes[offset];
+    const walkable = bytes[offset + 1] !== 0;
+    const movementCost = bytes[offset + 2];
+    const nameLen = bytes[offset + 3];
+    offset += 4;
+    const name = decoder.decode(bytes.subarray(offset, offset + nameLen));
+    offset += nameLen;
+    const extras = TERRAIN_EXTRAS.get(name);
+    registerTerrainDef({
+      id,
+      name,
+      walkable,
+      movementCost,
+      combatModifier: extras?.combatModifier ?? 0,
+      effect: extras?.effect,
+    });
+  }
+}
+
+/** Registers or replaces a terrain definition, e.g. for a runtime-defined material. */
+export function registerTerrainDef(def: TerrainDef): void {
+  TERRAIN_TABLE.set(def.id, def);
+}
+
 export function getTerrainDef(terrainId: number): TerrainDef | undefined {
   return TERRAIN_TABLE.get(terrainId);
 }
 
 export function isWalkableSurface(s: TileSurface): boolean {
-  return getTerrainDef(s.terrainId)?.walkable ?? false;
+  return (s.flags & TILE_BLOCKED) === 0;
+}
+
+export function isClimbableSurface(s: TileSurface): boolean {
+  return (s.flags & TILE_CLIMBABLE) !== 0;
+}
+
+export function isHazardSurface(s: TileSurface): boolean {
+  return (s.flags & TILE_HAZARD) !== 0;
 }
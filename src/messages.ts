@@ -78,6 +78,27 @@ export type GameToRenderMessage =
       easing: number;
     }
   | { type: "preload_view"; x: number; y: number; z: number }
+  | {
+      type: "set_interest_point";
+      name: string;
+      x: number;
+      y: number;
+      z: number;
+      radius: number;
+      weight: number;
+    }
+  | { type: "remove_interest_point"; name: string }
+  | {
+      type: "pin_chunk_region";
+      name: string;
+      ax: number;
+      ay: number;
+      az: number;
+      bx: number;
+      by: number;
+      bz: number;
+    }
+  | { type: "unpin_chunk_region"; name: string }
   | { type: "query_camera_position"; id: number }
   | {
       type: "query_chunk_loaded";
@@ -87,6 +108,7 @@ export type GameToRenderMessage =
       cz: number;
     }
   | { type: "is_solid"; x: number; y: number; z: number; id: number }
+  | { type: "query_dungeon_layout"; id: number }
   | { type: "resize"; width: number; height: number }
   | {
       type: "sprite_update";
@@ -110,6 +132,25 @@ export type GameToRenderMessage =
       type: "voxel_mutate";
       changes: { x: number; y: number; z: number; materialId: number }[];
     }
+  | {
+      type: "voxel_set_flags";
+      // Bitfield of VOXEL_FLAG_* from terrain.ts.
+      changes: { x: number; y: number; z: number; flags: number }[];
+    }
+  | {
+      type: "voxel_mutate_region";
+      shape: "box" | "sphere" | "line" | "list";
+      // box/line: [x0, y0, z0, x1, y1, z1]; sphere: [cx, cy, cz, r]; list: [x, y, z, ...]
+      params: number[];
+      materialId: number;
+    }
+  | {
+      type: "define_material";
+      id: number;
+      name: string;
+      // [r, g, b, solid, walkable, movementCost, opacity, emissive]
+      data: Float32Array;
+    }
   | { type: "light_update"; data: Float32Array }
   | {
       type: "sprite_atlas";
@@ -160,6 +201,12 @@ export type RenderToGameMessage =
     }
   | { type: "chunk_loaded"; id: number; loaded: boolean }
   | { type: "is_solid_result"; id: number; solid: boolean }
+  | {
+      type: "dungeon_layout";
+      id: number;
+      rooms: Int32Array; // 7 per room: level, min xyz, max xyz
+      connections: Uint32Array; // pairs of room indices
+    }
   | {
       type: "stats";
       frame_time_ms: number;
@@ -189,6 +236,8 @@ export type RenderToGameMessage =
       light_count: number;
       render_scale: number;
       shader_preset: number;
+      chunk_voxel_bytes: number;
+      in_flight_chunks: number;
     }
   | {
       type: "chunk_terrain";
@@ -197,7 +246,12 @@ export type RenderToGameMessage =
       cz: number;
       data: ArrayBuffer;
     }
-  | { type: "chunk_terrain_unload"; cx: number; cy: number; cz: number };
+  | { type: "chunk_terrain_unload"; cx: number; cy: number; cz: number }
+  | {
+      type: "material_terrain";
+      // See applyMaterialTerrainTable in game/terrain.ts.
+      data: ArrayBuffer;
+    };
 
 // --- Game Worker → UI ---
 
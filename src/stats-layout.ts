@@ -27,3 +27,5 @@ export const STAT_SPRITE_COUNT = 23;
 export const STAT_LIGHT_COUNT = 24;
 export const STAT_RENDER_SCALE = 25;
 export const STAT_SHADER_PRESET = 26;
+export const STAT_CHUNK_VOXEL_BYTES = 27;
+export const STAT_IN_FLIGHT_CHUNKS = 28;
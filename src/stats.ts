@@ -24,6 +24,8 @@ export interface StatsSample {
   light_count: number;
   render_scale: number;
   shader_preset: number;
+  chunk_voxel_bytes: number;
+  in_flight_chunks: number;
 }
 
 export interface DiagnosticsDigest {
@@ -54,6 +56,8 @@ export interface DiagnosticsDigest {
   light_count: number;
   render_scale: number;
   shader_preset: number;
+  chunk_voxel_bytes: number;
+  in_flight_chunks: number;
 }
 
 export const EMPTY_DIGEST: DiagnosticsDigest = {
@@ -84,6 +88,8 @@ export const EMPTY_DIGEST: DiagnosticsDigest = {
   light_count: 0,
   render_scale: 0,
   shader_preset: 0,
+  chunk_voxel_bytes: 0,
+  in_flight_chunks: 0,
 };
 
 /**
@@ -157,6 +163,8 @@ export class StatsAggregator {
       light_count: s?.light_count ?? 0,
       render_scale: s?.render_scale ?? 0,
       shader_preset: s?.shader_preset ?? 0,
+      chunk_voxel_bytes: s?.chunk_voxel_bytes ?? 0,
+      in_flight_chunks: s?.in_flight_chunks ?? 0,
     };
   }
 }
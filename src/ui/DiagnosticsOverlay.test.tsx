@@ -60,6 +60,16 @@ describe("DiagnosticsOverlay", () => {
     expect(screen.getByText(/4\.0/)).toBeTruthy();
   });
 
+  it("displays chunk voxel memory in MB", () => {
+    const [data] = createSignal<DiagnosticsDigest>({
+      ...EMPTY_DIGEST,
+      chunk_voxel_bytes: 2_621_440, // 2.5 MB
+    });
+    render(() => <DiagnosticsOverlay data={data()} />);
+    fireEvent.keyDown(window, { key: "`" });
+    expect(screen.getByText(/Voxels: 2\.5 MB/)).toBeTruthy();
+  });
+
   it("displays streaming state", () => {
     const [data] = createSignal<DiagnosticsDigest>({
       ...EMPTY_DIGEST,
@@ -85,6 +95,16 @@ describe("DiagnosticsOverlay", () => {
     expect(screen.getByText(/45/)).toBeTruthy();
   });
 
+  it("displays in-flight chunk requests", () => {
+    const [data] = createSignal<DiagnosticsDigest>({
+      ...EMPTY_DIGEST,
+      in_flight_chunks: 7,
+    });
+    render(() => <DiagnosticsOverlay data={data()} />);
+    fireEvent.keyDown(window, { key: "`" });
+    expect(screen.getByText(/In flight: 7/)).toBeTruthy();
+  });
+
   it("displays camera chunk coordinate", () => {
     const [data] = createSignal<DiagnosticsDigest>({
       ...EMPTY_DIGEST,
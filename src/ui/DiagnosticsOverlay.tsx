@@ -126,7 +126,10 @@ const DiagnosticsOverlay: Component<DiagnosticsOverlayProps> = (props) => {
           Camera: ({formatPos(props.data.camera_x)}, {formatPos(props.data.camera_y)},{" "}
           {formatPos(props.data.camera_z)})
         </div>
-        <div>WASM: {formatMB(props.data.wasm_memory_bytes)} MB</div>
+        <div>
+          WASM: {formatMB(props.data.wasm_memory_bytes)} MB Voxels:{" "}
+          {formatMB(props.data.chunk_voxel_bytes)} MB
+        </div>
         <div>
           <span style={{ color: streamingColor(props.data.streaming_state) }}>
             Stream: {streamingLabel(props.data.streaming_state)}
@@ -140,7 +143,8 @@ const DiagnosticsOverlay: Component<DiagnosticsOverlayProps> = (props) => {
           )}
         </div>
         <div>
-          Pending: {props.data.pending_chunks} Cached: {props.data.cached_chunks}
+          Pending: {props.data.pending_chunks} In flight: {props.data.in_flight_chunks} Cached:{" "}
+          {props.data.cached_chunks}
         </div>
         <div>
           Chunk: ({props.data.camera_chunk_x}, {props.data.camera_chunk_y},{" "}
@@ -19,7 +19,7 @@ import { LightManager } from "../game/light-manager";
 import type { AtlasInfo } from "../game/particle-effects";
 import { createRunStats } from "../game/run-stats";
 import { type CameraParams, projectToScreen } from "../game/screen-projection";
-import { deserializeTerrainGrid } from "../game/terrain";
+import { applyMaterialTerrainTable, deserializeTerrainGrid } from "../game/terrain";
 import type { PlayerAction } from "../game/turn-loop";
 import { TurnLoop } from "../game/turn-loop";
 import { GameWorld } from "../game/world";
@@ -680,6 +680,8 @@ function onRenderMessage(e: MessageEvent<RenderToGameMessage>) {
       light_count: msg.light_count,
       render_scale: msg.render_scale,
       shader_preset: msg.shader_preset,
+      chunk_voxel_bytes: msg.chunk_voxel_bytes,
+      in_flight_chunks: msg.in_flight_chunks,
     });
     // Track camera state for entity hover projection (esp. free-look mode)
     lastCamX = msg.camera_x;
@@ -700,6 +702,8 @@ function onRenderMessage(e: MessageEvent<RenderToGameMessage>) {
     }
   } else if (msg.type === "chunk_terrain_unload") {
     world.unloadTerrain(msg.cx, msg.cy, msg.cz);
+  } else if (msg.type === "material_terrain") {
+    applyMaterialTerrainTable(msg.data);
   } else if (msg.type === "animation_complete") {
     if (followCamera.mode === "cinematic") {
       const next = followCamera.onAnimationComplete();
@@ -3,25 +3,37 @@ import init, {
   begin_intent,
   collect_frame_stats,
   create_emitter,
+  define_material,
   destroy_emitter,
+  dungeon_connections,
+  dungeon_rooms,
   end_intent,
   get_terrain_grid,
   init_renderer,
   is_chunk_loaded_at,
   is_solid,
   look_at,
+  material_terrain_table,
+  mutate_region,
   mutate_voxels,
+  pin_chunk_region,
   preload_view,
+  remove_interest_point,
   render_frame,
   resize_renderer,
   set_camera,
+  set_chunk_server,
   set_dolly,
+  set_interest_point,
   set_look_delta,
   set_projection,
   set_render_scale,
   set_shader_preset,
+  set_voxel_flags,
   spawn_burst,
   take_animation_completed,
+  take_dirty_terrain_chunks,
+  unpin_chunk_region,
   update_lights,
   update_sprite_atlas,
   update_sprites,
@@ -43,7 +55,9 @@ import {
   STAT_CAMERA_YAW,
   STAT_CAMERA_Z,
   STAT_CHUNK_BUDGET,
+  STAT_CHUNK_VOXEL_BYTES,
   STAT_FRAME_TIME_MS,
+  STAT_IN_FLIGHT_CHUNKS,
   STAT_LIGHT_COUNT,
   STAT_LOADED_CHUNKS,
   STAT_LOADED_THIS_TICK,
@@ -66,6 +80,29 @@ function post(msg: RenderToGameMessage, transfers?: Transferable[]): void {
   }
 }
 
+/** Re-send terrain grids for chunks reported as changed by a mutation or seam stitch. */
+function emitChangedTerrain(changed: Int32Array): void {
+  for (let i = 0; i + 2 < changed.length; i += 3) {
+    const cx = changed[i];
+    const cy = changed[i + 1];
+    const cz = changed[i + 2];
+    const data = get_terrain_grid(cx, cy, cz);
+    if (data) {
+      post({ type: "chunk_terrain", cx, cy, cz, data: data.buffer }, [data.buffer]);
+    } else {
+      post({ type: "chunk_terrain_unload", cx, cy, cz });
+    }
+  }
+}
+
+/** Send the material registry's terrain table to the game worker. */
+function emitMaterialTerrain(): void {
+  const data = material_terrain_table();
+  post({ type: "material_terrain", data: data.buffer }, [data.buffer]);
+}
+
+const REGION_SHAPES = { box: 0, sphere: 1, line: 2, list: 3 } as const;
+
 let atlasMetadata: {
   cols: number;
   rows: number;
@@ -84,12 +121,17 @@ self.onmessage = async (e: MessageEvent<GameToRenderMessage>) => {
     try {
       await init();
       await init_renderer(canvas, width, height);
+      const chunkServerUrl = import.meta.env.VITE_CHUNK_SERVER_URL;
+      if (chunkServerUrl) {
+        set_chunk_server(chunkServerUrl);
+      }
     } catch (err) {
       const message = err instanceof Error ? err.message : String(err);
       post({ type: "error", message });
       return;
     }
 
+    emitMaterialTerrain();
     post({ type: "ready" });
 
     const VIEW_DIST = 3;
@@ -131,6 +173,8 @@ self.onmessage = async (e: MessageEvent<GameToRenderMessage>) => {
         light_count: s[STAT_LIGHT_COUNT],
         render_scale: s[STAT_RENDER_SCALE],
         shader_preset: s[STAT_SHADER_PRESET],
+        chunk_voxel_bytes: s[STAT_CHUNK_VOXEL_BYTES],
+        in_flight_chunks: s[STAT_IN_FLIGHT_CHUNKS],
       });
 
       // Emit terrain grids for newly loaded chunks, or on first frame
@@ -160,6 +204,9 @@ self.onmessage = async (e: MessageEvent<GameToRenderMessage>) => {
         }
       }
 
+      // Grids already sent may change when a vertical neighbour loads.
+      emitChangedTerrain(take_dirty_terrain_chunks());
+
       setTimeout(loop, 16);
     }
     loop();
@@ -179,6 +226,14 @@ self.onmessage = async (e: MessageEvent<GameToRenderMessage>) => {
     animate_camera(msg.x, msg.y, msg.z, msg.yaw, msg.pitch, msg.duration, msg.easing);
   } else if (msg.type === "preload_view") {
     preload_view(msg.x, msg.y, msg.z);
+  } else if (msg.type === "set_interest_point") {
+    set_interest_point(msg.name, msg.x, msg.y, msg.z, msg.radius, msg.weight);
+  } else if (msg.type === "remove_interest_point") {
+    remove_interest_point(msg.name);
+  } else if (msg.type === "pin_chunk_region") {
+    pin_chunk_region(msg.name, msg.ax, msg.ay, msg.az, msg.bx, msg.by, msg.bz);
+  } else if (msg.type === "unpin_chunk_region") {
+    unpin_chunk_region(msg.name);
   } else if (msg.type === "query_camera_position") {
     const s = collect_frame_stats();
     post({
@@ -202,6 +257,13 @@ self.onmessage = async (e: MessageEvent<GameToRenderMessage>) => {
       id: msg.id,
       solid: is_solid(msg.x, msg.y, msg.z),
     });
+  } else if (msg.type === "query_dungeon_layout") {
+    const rooms = dungeon_rooms();
+    const connections = dungeon_connections();
+    post({ type: "dungeon_layout", id: msg.id, rooms, connections }, [
+      rooms.buffer,
+      connections.buffer,
+    ]);
   } else if (msg.type === "resize") {
     resize_renderer(msg.width, msg.height);
   } else if (msg.type === "visibility_mask") {
@@ -286,6 +348,25 @@ self.onmessage = async (e: MessageEvent<GameToRenderMessage>) => {
       flat[i * 4 + 2] = c.z;
       flat[i * 4 + 3] = c.materialId;
     }
-    mutate_voxels(flat);
+    emitChangedTerrain(mutate_voxels(flat));
+  } else if (msg.type === "voxel_set_flags") {
+    const flat = new Int32Array(msg.changes.length * 4);
+    for (let i = 0; i < msg.changes.length; i++) {
+      const c = msg.changes[i];
+      flat[i * 4] = c.x;
+      flat[i * 4 + 1] = c.y;
+      flat[i * 4 + 2] = c.z;
+      flat[i * 4 + 3] = c.flags;
+    }
+    emitChangedTerrain(set_voxel_flags(flat));
+  } else if (msg.type === "define_material") {
+    if (define_material(msg.id, msg.name, msg.data)) {
+      emitMaterialTerrain();
+      // Solidity may have changed; resend every rebuilt terrain grid.
+      emitChangedTerrain(take_dirty_terrain_chunks());
+    }
+  } else if (msg.type === "voxel_mutate_region") {
+    const params = Int32Array.from(msg.params);
+    emitChangedTerrain(mutate_region(REGION_SHAPES[msg.shape], params, msg.materialId));
   }
 };